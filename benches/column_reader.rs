@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sorer::dataframe::{from_file, get};
+use sorer::schema;
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let file_name = "benches/schema.sor";
+    let schema = schema::infer_schema(file_name).unwrap();
+    let dataframe = from_file(file_name, schema, 0, usize::MAX, 8);
+    let num_rows = dataframe[0].len();
+
+    c.bench_function("reading every cell with get", |b| {
+        b.iter(|| {
+            for col_idx in 0..dataframe.len() {
+                for row_idx in 0..num_rows {
+                    black_box(get(&dataframe, col_idx, row_idx));
+                }
+            }
+        })
+    });
+
+    c.bench_function("reading every cell with a ColumnReader", |b| {
+        b.iter(|| {
+            for column in &dataframe {
+                let reader = column.reader();
+                for row_idx in 0..reader.len() {
+                    black_box(reader.get(row_idx));
+                }
+            }
+        })
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -30,19 +30,19 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     c.bench_function("parsing thin row (4 columns) with schema", |b| {
         let line = read_line("benches/thin_row.sor");
-        let schema = schema::infer_schema("benches/thin_row.sor");
+        let schema = schema::infer_schema("benches/thin_row.sor").unwrap();
         b.iter(|| parsers::parse_line_with_schema(black_box(&line), &schema))
     });
 
     c.bench_function("parsing medium row (8 columns) with schema", |b| {
         let line = read_line("benches/medium_row.sor");
-        let schema = schema::infer_schema("benches/medium_row.sor");
+        let schema = schema::infer_schema("benches/medium_row.sor").unwrap();
         b.iter(|| parsers::parse_line_with_schema(black_box(&line), &schema))
     });
 
     c.bench_function("parsing wide row (32 columns) with schema", |b| {
         let line = read_line("benches/wide_row.sor");
-        let schema = schema::infer_schema("benches/wide_row.sor");
+        let schema = schema::infer_schema("benches/wide_row.sor").unwrap();
         b.iter(|| parsers::parse_line_with_schema(black_box(&line), &schema))
     });
 }
@@ -0,0 +1,100 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use sorer::dataframe::{Column, Data, SorTerator};
+use sorer::{parsers, schema};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+// `SorTerator::next` as it was before it scanned lines with `memchr`, using
+// `BufRead::split` which allocates a `Vec<u8>` per line.
+fn split_and_parse(file_name: &str, schema: &[schema::DataType]) -> usize {
+    let reader = BufReader::new(File::open(file_name).unwrap()).split(b'\n');
+    let mut parsed_data: Vec<Column> = schema
+        .iter()
+        .map(|t| match t {
+            schema::DataType::Bool => Column::Bool(Vec::new()),
+            schema::DataType::Int => Column::Int(Vec::new()),
+            schema::DataType::Float => Column::Float(Vec::new()),
+            schema::DataType::String => Column::String(Vec::new()),
+        })
+        .collect();
+    for line in reader {
+        if let Some(data) =
+            parsers::parse_line_with_schema(&line.unwrap(), schema)
+        {
+            for (d, col) in data.iter().zip(parsed_data.iter_mut()) {
+                match (d, col) {
+                    (Data::Bool(b), Column::Bool(c)) => c.push(Some(*b)),
+                    (Data::Int(i), Column::Int(c)) => c.push(Some(*i)),
+                    (Data::Float(f), Column::Float(c)) => c.push(Some(*f)),
+                    (Data::String(s), Column::String(c)) => {
+                        c.push(Some(s.clone()))
+                    }
+                    (Data::Null, Column::Bool(c)) => c.push(None),
+                    (Data::Null, Column::Int(c)) => c.push(None),
+                    (Data::Null, Column::Float(c)) => c.push(None),
+                    (Data::Null, Column::String(c)) => c.push(None),
+                    _ => panic!("Parser Failed"),
+                }
+            }
+        }
+    }
+    parsed_data[0].len()
+}
+
+// Writes a file with 64 columns of every data type to a temporary file.
+fn wide_file() -> tempfile::NamedTempFile {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let mut writer = BufWriter::new(file.as_file());
+    for row in 0..20_000 {
+        for col in 0..16 {
+            let n = row * 16 + col;
+            write!(writer, "<{}> <{}> <{}.5> <\"s {}\"> ", n % 2, n, n, n)
+                .unwrap();
+        }
+        writeln!(writer).unwrap();
+    }
+    drop(writer);
+    file
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let file_name = "benches/schema.sor";
+    let schema = schema::infer_schema(file_name).unwrap();
+
+    c.bench_function("chunking with BufRead::split", |b| {
+        b.iter(|| split_and_parse(file_name, &schema))
+    });
+
+    c.bench_function("chunking with SorTerator", |b| {
+        b.iter(|| {
+            SorTerator::new(file_name, schema.clone(), 1000)
+                .map(|chunk| chunk[0].len())
+                .sum::<usize>()
+        })
+    });
+
+    let wide = wide_file();
+    let wide_name = wide.path().to_str().unwrap();
+    let wide_schema = schema::infer_schema(wide_name).unwrap();
+
+    c.bench_function("chunking a wide file with SorTerator", |b| {
+        b.iter(|| {
+            SorTerator::new(wide_name, wide_schema.clone(), 1000)
+                .map(|chunk| chunk[0].len())
+                .sum::<usize>()
+        })
+    });
+
+    #[cfg(feature = "rayon")]
+    c.bench_function("chunking a wide file with a parallel SorTerator", |b| {
+        b.iter(|| {
+            SorTerator::new(wide_name, wide_schema.clone(), 1000)
+                .parallel(true)
+                .map(|chunk| chunk[0].len())
+                .sum::<usize>()
+        })
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
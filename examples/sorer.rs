@@ -1,95 +1,292 @@
-use num_cpus;
+use sorer::aggregate::{describe, print_summary};
 use sorer::dataframe::*;
-use sorer::schema::infer_schema;
+use sorer::parsers::ParserConfig;
+use sorer::schema::{
+    default_column_names, header_names, infer_schema_with_config, schema_diff,
+    DataType,
+};
 use std::env;
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+
+/// What `--print-col-idx` prints for a missing value. Present values never
+/// look like this since strings are printed inside double quotes.
+const MISSING_TOKEN: &str = "<>";
+
+/// The environment variable read for the number of threads when `--threads`
+/// isn't given.
+const THREADS_VAR: &str = "SORER_THREADS";
+
+/// The number of rows parsed at a time while streaming the file for
+/// `--sample`.
+const SAMPLE_CHUNK_SIZE: usize = 10_000;
 
 fn main() {
     // parse the arguments
     let args: Vec<String> = env::args().collect();
     let parsed_args = ProgArgs::from(args);
 
-    match &parsed_args.option {
-        Options::Help => {
-            println!("{}", HELP_MSG);
-            return;
+    if let Options::Help = parsed_args.option {
+        println!("{}", HELP_MSG);
+        return;
+    }
+    if let Options::Version = parsed_args.option {
+        println!("sorer {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+
+    let schema = infer(&parsed_args.file, &parsed_args);
+    let num_threads =
+        resolve_threads(parsed_args.threads, env::var(THREADS_VAR).ok());
+
+    let output = run(&parsed_args, &schema, || {
+        // the progress bar would only garble redirected output
+        if parsed_args.progress && io::stderr().is_terminal() {
+            let start = Instant::now();
+            let dataframe = from_file_with_progress(
+                &parsed_args.file,
+                schema.clone(),
+                parsed_args.from,
+                parsed_args.len,
+                num_threads,
+                &ParserConfig::default(),
+                |done, total| {
+                    eprint!("\r{}", progress_bar(done, total, start.elapsed()))
+                },
+            );
+            eprintln!();
+            dataframe
+        } else {
+            from_file(
+                &parsed_args.file,
+                schema.clone(),
+                parsed_args.from,
+                parsed_args.len,
+                num_threads,
+            )
         }
-        _ => (),
+    });
+    println!("{}", output);
+}
+
+/// Returns how many threads to parse with: the `--threads` flag if given,
+/// otherwise the value of the `SORER_THREADS` environment variable, otherwise
+/// the number of CPUs. A value of `0` or one that isn't a number is ignored.
+fn resolve_threads(flag: Option<usize>, env_var: Option<String>) -> usize {
+    flag.or_else(|| env_var.and_then(|v| v.trim().parse::<usize>().ok()))
+        .filter(|&n| n > 0)
+        .unwrap_or_else(num_cpus::get)
+}
+
+/// Infers the schema of `file`, ignoring rows with more fields than
+/// `--limit-cols` if it was given.
+fn infer(file: &str, args: &ProgArgs) -> Vec<DataType> {
+    let config = ParserConfig {
+        max_columns: args.limit_cols,
+        ..ParserConfig::default()
     };
+    infer_schema_with_config(file, &config).unwrap()
+}
+
+/// Renders a progress bar for `done` out of `total` bytes parsed after
+/// `elapsed` time, with the percentage done and an estimate of the time left
+/// assuming the rest of the file is parsed at the same rate.
+fn progress_bar(done: usize, total: usize, elapsed: Duration) -> String {
+    const WIDTH: usize = 30;
+    let fraction = if total == 0 {
+        1.0
+    } else {
+        done as f64 / total as f64
+    };
+    let filled = (fraction * WIDTH as f64) as usize;
+    let eta = if done == 0 {
+        "?".to_string()
+    } else {
+        let left = elapsed.as_secs_f64() * (total - done) as f64 / done as f64;
+        format!("{}s", left.ceil())
+    };
+    format!(
+        "[{}{}] {:>3}% ETA {}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        (fraction * 100.0) as usize,
+        eta
+    )
+}
 
-    let schema = infer_schema(&parsed_args.file.clone()).unwrap();
-    match &parsed_args.option {
+/// Answers the query in `args` and returns what should be printed.
+///
+/// Queries that only need the schema (`--print-col-type`, `--infer-only` and
+/// `--schema-diff`) are answered straight from `schema` and return before
+/// `parse` is ever called, so the file is never fully parsed for them.
+/// `--sample` streams the file instead, keeping only the chosen rows in
+/// memory, unless `--from` or `--len` limit it to a part of the file.
+fn run<F>(args: &ProgArgs, schema: &[DataType], parse: F) -> String
+where
+    F: FnOnce() -> Vec<Column>,
+{
+    match &args.option {
         Options::PrintColType(col_idx) => {
-            if *col_idx >= schema.len() {
-                println!(
+            let col_idx = *col_idx;
+            return if col_idx >= schema.len() {
+                format!(
                     "Error: There are only {} fields in the schema",
                     schema.len()
-                );
+                )
             } else {
-                println!(
-                    "{}",
-                    format!("{:?}", schema[*col_idx]).to_uppercase()
-                );
-            }
-            return;
+                format!("{:?}", schema[col_idx]).to_uppercase()
+            };
+        }
+        Options::InferOnly => {
+            return schema
+                .iter()
+                .map(|t| format!("{:?}", t).to_uppercase())
+                .collect::<Vec<String>>()
+                .join(" ");
+        }
+        Options::SchemaDiff(other_file) => {
+            let other_schema = infer(other_file, args);
+            let diff = schema_diff(schema, &other_schema);
+            return if diff.is_empty() {
+                "The schemas are equal".to_string()
+            } else {
+                diff.iter()
+                    .map(|entry| entry.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            };
+        }
+        Options::Sample(n) if args.from == 0 && args.len == usize::MAX => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            let terator =
+                SorTerator::new(&args.file, schema.to_vec(), SAMPLE_CHUNK_SIZE);
+            return format_table(&terator.sample(*n, seed), schema, *n);
         }
         _ => (),
     };
 
-    let num_threads = num_cpus::get();
-
-    let dataframe = from_file(
-        &parsed_args.file,
-        schema.clone(),
-        parsed_args.from,
-        parsed_args.len,
-        num_threads,
-    );
+    let dataframe = parse();
 
     // metadata about the parsed file
     let num_cols = dataframe.len();
-    let num_lines = if num_cols != 0 {
-        match &dataframe[0] {
-            Column::Bool(b) => b.len(),
-            Column::Int(b) => b.len(),
-            Column::Float(b) => b.len(),
-            Column::String(b) => b.len(),
-        }
-    } else {
-        0
-    };
+    let num_lines = dataframe.first().map_or(0, Column::len);
 
     // Retrieve and return the requested data
-    match parsed_args.option {
+    match args.option {
         Options::PrintColIdx(col_idx, row_idx) => {
             if col_idx >= num_cols {
-                println!(
+                format!(
                     "Error: There are only {} fields in the schema",
                     num_cols
-                );
+                )
             } else if row_idx >= num_lines {
-                println!("Error: Only {} lines were parsed", num_lines);
+                format!("Error: Only {} lines were parsed", num_lines)
             } else {
-                println!("{}", get(&dataframe, col_idx, row_idx));
+                match get(&dataframe, col_idx, row_idx) {
+                    Data::Null => args
+                        .null_as
+                        .as_deref()
+                        .unwrap_or(MISSING_TOKEN)
+                        .to_string(),
+                    data => format!("{}", data),
+                }
             }
         }
         Options::IsMissingIdx(col_idx, row_idx) => {
             if col_idx >= num_cols {
-                println!(
+                format!(
                     "Error: There are only {} fields in the schema",
                     num_cols
-                );
+                )
             } else if row_idx >= num_lines {
-                println!("Error: Only {} lines were parsed", num_lines);
+                format!("Error: Only {} lines were parsed", num_lines)
             } else if get(&dataframe, col_idx, row_idx) == Data::Null {
-                println!("1");
+                "1".to_string()
             } else {
-                println!("0");
+                "0".to_string()
+            }
+        }
+        Options::Head(n) => format_rows(&head(&dataframe, n), args),
+        Options::Sample(n) => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            format_table(&sample_rows(&dataframe, n, seed), schema, n)
+        }
+        Options::Describe => {
+            let mut out = Vec::new();
+            let names = column_names(args, num_cols);
+            match print_summary(&describe(&dataframe, schema), &names, &mut out)
+            {
+                // `print_summary` only writes valid utf-8
+                Ok(()) => String::from_utf8(out)
+                    .unwrap()
+                    .trim_end_matches('\n')
+                    .to_string(),
+                Err(e) => format!("Error: {}", e),
             }
         }
         _ => unreachable!(),
     }
 }
 
+// Formats at most `max_rows` rows of `dataframe` as a table, see
+// `print_table`
+fn format_table(
+    dataframe: &[Column],
+    schema: &[DataType],
+    max_rows: usize,
+) -> String {
+    let mut out = Vec::new();
+    match print_table(dataframe, schema, max_rows, &mut out) {
+        // `print_table` only writes valid utf-8
+        Ok(()) => String::from_utf8(out)
+            .unwrap()
+            .trim_end_matches('\n')
+            .to_string(),
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+// Formats each row of `dataframe` on its own line as comma separated values
+// after a header line of column names, leaving missing values empty unless
+// `--null-as` was given
+fn format_rows(dataframe: &[Column], args: &ProgArgs) -> String {
+    let mut out = Vec::new();
+    let options = CsvOptions {
+        quote_style: args.quote_style,
+        null_token: args.null_as.clone().unwrap_or_default(),
+        ..CsvOptions::default()
+    };
+    let names = column_names(args, dataframe.len());
+    let written = write_csv_header(&names, &mut out, args.quote_style)
+        .and_then(|_| write_csv(dataframe, &mut out, &options));
+    match written {
+        // `write_csv` only writes valid utf-8
+        Ok(()) => String::from_utf8(out)
+            .unwrap()
+            .trim_end_matches('\n')
+            .to_string(),
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+// Returns the names of the `width` columns, from `--names` or else from a
+// `# name1 name2` header line, with any unnamed columns named by position
+fn column_names(args: &ProgArgs, width: usize) -> Vec<String> {
+    let names = match &args.names {
+        Some(names) => names.clone(),
+        None => header_names(&args.file, b'#')
+            .ok()
+            .flatten()
+            .unwrap_or_default(),
+    };
+    let named = names.len();
+    names
+        .into_iter()
+        .chain(default_column_names(width).into_iter().skip(named))
+        .take(width)
+        .collect()
+}
+
 /// This command line program is a simple example of usage of the `sorer`
 /// crate. It simply parses a file, answers one of three possible queries, and
 /// exits. The point of this program is simply to be an example and nothing
@@ -102,22 +299,51 @@ pub(crate) struct ProgArgs {
     pub(crate) from: usize,
     /// The ending byte offset in the file to stop parsing at
     pub(crate) len: usize,
+    /// When strings are quoted in comma separated output
+    pub(crate) quote_style: QuoteStyle,
+    /// How missing values are printed, if not the default of each query
+    pub(crate) null_as: Option<String>,
+    /// The names of the columns, if given with `--names`
+    pub(crate) names: Option<Vec<String>>,
+    /// The number of threads to parse with, if given with `--threads`
+    pub(crate) threads: Option<usize>,
+    /// The seed for choosing the rows of `--sample`, if given with `--seed`
+    pub(crate) seed: Option<u64>,
+    /// Whether to show a progress bar on stderr while parsing
+    pub(crate) progress: bool,
+    /// The most columns the inferred schema may have, if given with
+    /// `--limit-cols`
+    pub(crate) limit_cols: Option<usize>,
     /// The query we will make about the parsed data
     pub(crate) option: Options,
 }
 
 // Enum to depict all the operations to be done on the binary file
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub(crate) enum Options {
     /// Prints the data type of the column at the given column index
     PrintColType(usize),
-    /// Prints the value at the given column, row index
+    /// Prints the value at the given column, row index, or `MISSING_TOKEN` if
+    /// the value is missing
     PrintColIdx(usize, usize),
     /// Prints "1" if the data at the given column, row index is 'missing' or
     /// "1" if it is present
     IsMissingIdx(usize, usize),
+    /// Prints the first n rows as comma separated values
+    Head(usize),
+    /// Prints n randomly chosen rows as a table
+    Sample(usize),
+    /// Prints a table summarizing every column
+    Describe,
+    /// Prints the inferred schema and exits without parsing the file
+    InferOnly,
+    /// Prints the differences between the inferred schema and the inferred
+    /// schema of the given file, without parsing either file
+    SchemaDiff(String),
     /// If the user needed help
     Help,
+    /// If the user asked for the version
+    Version,
 }
 
 // Parses command line arguments for this binary
@@ -126,29 +352,92 @@ impl From<Vec<String>> for ProgArgs {
         let mut file = None;
         let mut from = None;
         let mut len = None;
+        let mut quote_style = None;
+        let mut null_as = None;
+        let mut names: Option<Vec<String>> = None;
+        let mut threads = None;
+        let mut seed = None;
+        let mut progress = false;
+        let mut limit_cols = None;
         let mut opt: Option<Options> = None;
         for mut i in 1..args.len() {
             if args[i] == "-f" {
                 i += 1;
                 match file {
                     None => file = Some(args[i].clone()),
-                    Some(a) => panic!(format!("File was already set to {}", a)),
+                    Some(a) => panic!("File was already set to {}", a),
                 }
             }
             if args[i] == "--from" {
                 i += 1;
                 match from {
                     None => from = Some(args[i].parse::<usize>().unwrap()),
-                    Some(a) => panic!(format!("From was already set to {}", a)),
+                    Some(a) => panic!("From was already set to {}", a),
                 }
             }
             if args[i] == "--len" {
                 i += 1;
                 match len {
                     None => len = Some(args[i].parse::<usize>().unwrap()),
-                    Some(a) => panic!(format!("Len was already set to {}", a)),
+                    Some(a) => panic!("Len was already set to {}", a),
                 }
             }
+            if args[i] == "--quote-strings" {
+                i += 1;
+                let style = match args[i].as_str() {
+                    "minimal" => QuoteStyle::Minimal,
+                    "always" => QuoteStyle::Always,
+                    "never" => QuoteStyle::Never,
+                    a => panic!("Unknown quote style {}", a),
+                };
+                match quote_style {
+                    None => quote_style = Some(style),
+                    Some(a) => panic!("Quote style was already set to {:?}", a),
+                }
+            }
+            if args[i] == "--null-as" {
+                i += 1;
+                match null_as {
+                    None => null_as = Some(args[i].clone()),
+                    Some(a) => panic!("Null token was already set to {}", a),
+                }
+            }
+            if args[i] == "--names" {
+                i += 1;
+                match names {
+                    None => {
+                        names =
+                            Some(args[i].split(',').map(String::from).collect())
+                    }
+                    Some(a) => panic!("Names were already set to {:?}", a),
+                }
+            }
+            if args[i] == "--threads" {
+                i += 1;
+                match threads {
+                    None => threads = Some(args[i].parse::<usize>().unwrap()),
+                    Some(a) => panic!("Threads were already set to {}", a),
+                }
+            }
+            if args[i] == "--seed" {
+                i += 1;
+                match seed {
+                    None => seed = Some(args[i].parse::<u64>().unwrap()),
+                    Some(a) => panic!("Seed was already set to {}", a),
+                }
+            }
+            if args[i] == "--limit-cols" {
+                i += 1;
+                match limit_cols {
+                    None => {
+                        limit_cols = Some(args[i].parse::<usize>().unwrap())
+                    }
+                    Some(a) => panic!("Column limit was already set to {}", a),
+                }
+            }
+            if args[i] == "--progress" {
+                progress = true;
+            }
             if args[i] == "--print-col-type" {
                 match opt {
                     None => {
@@ -157,7 +446,7 @@ impl From<Vec<String>> for ProgArgs {
                         opt = Some(Options::PrintColType(n));
                     }
                     Some(a) => {
-                        panic!(format!("Option was already set to {:?}", a))
+                        panic!("Option was already set to {:?}", a)
                     }
                 }
             }
@@ -171,11 +460,11 @@ impl From<Vec<String>> for ProgArgs {
                         opt = Some(Options::PrintColIdx(n1, n2));
                     }
                     Some(a) => {
-                        panic!(format!("Option was already set to {:?}", a))
+                        panic!("Option was already set to {:?}", a)
                     }
                 }
             }
-            if args[i] == "--is_missing_idx" {
+            if args[i] == "--is-missing-idx" || args[i] == "--is_missing_idx" {
                 match opt {
                     None => {
                         i += 1;
@@ -185,7 +474,62 @@ impl From<Vec<String>> for ProgArgs {
                         opt = Some(Options::IsMissingIdx(n1, n2));
                     }
                     Some(a) => {
-                        panic!(format!("Option was already set to {:?}", a))
+                        panic!("Option was already set to {:?}", a)
+                    }
+                }
+            }
+            if args[i] == "--head" {
+                match opt {
+                    None => {
+                        i += 1;
+                        let n = args[i].parse::<usize>().unwrap();
+                        opt = Some(Options::Head(n));
+                    }
+                    Some(a) => panic!("Option was already set to {:?}", a),
+                }
+            }
+            if args[i] == "--sample" {
+                match opt {
+                    None => {
+                        i += 1;
+                        let n = args[i].parse::<usize>().unwrap();
+                        opt = Some(Options::Sample(n));
+                    }
+                    Some(a) => panic!("Option was already set to {:?}", a),
+                }
+            }
+            if args[i] == "--describe" {
+                match opt {
+                    None => {
+                        opt = Some(Options::Describe);
+                    }
+                    Some(a) => panic!("Option was already set to {:?}", a),
+                }
+            }
+            if args[i] == "--infer-only" {
+                match opt {
+                    None => {
+                        opt = Some(Options::InferOnly);
+                    }
+                    Some(a) => panic!("Option was already set to {:?}", a),
+                }
+            }
+            if args[i] == "--schema-diff" {
+                match opt {
+                    None => {
+                        i += 1;
+                        opt = Some(Options::SchemaDiff(args[i].clone()));
+                    }
+                    Some(a) => panic!("Option was already set to {:?}", a),
+                }
+            }
+            if args[i] == "--version" || args[i] == "-V" {
+                match opt {
+                    None => {
+                        opt = Some(Options::Version);
+                    }
+                    Some(a) => {
+                        panic!("Option was already set to {:?}", a)
                     }
                 }
             }
@@ -195,7 +539,7 @@ impl From<Vec<String>> for ProgArgs {
                         opt = Some(Options::Help);
                     }
                     Some(a) => {
-                        panic!(format!("Option was already set to {:?}", a))
+                        panic!("Option was already set to {:?}", a)
                     }
                 }
             }
@@ -206,30 +550,66 @@ impl From<Vec<String>> for ProgArgs {
                 from: *from,
                 len: *len,
                 option: option.to_owned(),
+                quote_style: quote_style.unwrap_or_default(),
+                null_as: null_as.clone(),
+                names: names.clone(),
+                threads,
+                seed,
+                progress,
+                limit_cols,
             },
             (Some(file), None, Some(len), Some(option)) => ProgArgs {
                 file: file.to_owned(),
                 from: 0,
                 len: *len,
                 option: option.to_owned(),
+                quote_style: quote_style.unwrap_or_default(),
+                null_as: null_as.clone(),
+                names: names.clone(),
+                threads,
+                seed,
+                progress,
+                limit_cols,
             },
             (Some(file), None, None, Some(option)) => ProgArgs {
                 file: file.to_owned(),
                 from: 0,
-                len: std::usize::MAX,
+                len: usize::MAX,
                 option: option.to_owned(),
+                quote_style: quote_style.unwrap_or_default(),
+                null_as: null_as.clone(),
+                names: names.clone(),
+                threads,
+                seed,
+                progress,
+                limit_cols,
             },
             (Some(file), Some(from), None, Some(option)) => ProgArgs {
                 file: file.to_owned(),
                 from: *from,
-                len: std::usize::MAX,
+                len: usize::MAX,
                 option: option.to_owned(),
+                quote_style: quote_style.unwrap_or_default(),
+                null_as: null_as.clone(),
+                names: names.clone(),
+                threads,
+                seed,
+                progress,
+                limit_cols,
             },
-            (_, _, _, Some(option @ Options::Help)) => ProgArgs {
+            (_, _, _, Some(option @ Options::Help))
+            | (_, _, _, Some(option @ Options::Version)) => ProgArgs {
                 file: "".to_string(),
                 from: 0,
-                len: std::usize::MAX,
+                len: usize::MAX,
                 option: option.to_owned(),
+                quote_style: quote_style.unwrap_or_default(),
+                null_as: null_as.clone(),
+                names: names.clone(),
+                threads,
+                seed,
+                progress,
+                limit_cols,
             },
             _ => panic!("Missing required arguments"),
         }
@@ -246,14 +626,379 @@ USAGE:
 FLAGS:
     -h, --help       Prints help information
     -V, --version    Prints version information
+        --progress   Shows a progress bar with an estimate of the time left on stderr while parsing
 
 OPTIONS:
-    -f, --file <file>    The name of the file to parse
-        --from <from>    The starting byte offset in the file to begin parsing from [default: 0]
-        --len <len>      The ending byte offset in the file to stop parsing at [default: 4294967295]
+    -f, --file <file>                The name of the file to parse
+        --from <from>                The starting byte offset in the file to begin parsing from [default: 0]
+        --len <len>                  The ending byte offset in the file to stop parsing at [default: 4294967295]
+        --quote-strings <style>      When strings are quoted by --head: minimal, always or never [default: minimal]
+        --null-as <token>            How missing values are printed [default: '<>' for --print-col-idx, empty for --head]
+        --threads <n>                The number of threads to parse with [default: $SORER_THREADS, or the number of CPUs]
+        --seed <seed>                The seed for choosing the rows of --sample [default: random]
+        --limit-cols <n>             Ignores rows with more than n fields when inferring the schema [default: no limit]
+        --names <names>              Comma separated column names for the --head and --describe tables [default: a '# name1 name2' first line, or col0 to colN]
 
 SUBCOMMANDS:
+    --describe                             Prints the type, null count and min, max and mean or distinct count of every column
+    --head <n>                             Prints the first n rows as comma separated values
+    --infer-only                           Prints the inferred schema without parsing the file
     --is-missing-idx <col-idx> <row-idx>   Prints '1' if the data at the given column, row index is 'missing' or '1' if it is present
-    --print-col-idx <col-idx> <row-idx>    Prints the value at the given column, row index
+    --print-col-idx <col-idx> <row-idx>    Prints the value at the given column, row index, or '<>' if it is missing
     --print-col-type <col-idx>             Prints the data type of the column at the given column index
+    --sample <n>                           Prints n randomly chosen rows as a table
+    --schema-diff <other-file>             Prints the differences between the schemas of the two files without parsing them
 ";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sorer::schema::infer_schema;
+
+    fn args(v: &[&str]) -> ProgArgs {
+        ProgArgs::from(v.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_schema_queries_do_not_parse() {
+        // tests/3.sor is ~30 MB, a full parse would be noticeably slow
+        let schema = infer_schema("tests/3.sor").unwrap();
+
+        let print_col_type =
+            args(&["sorer", "-f", "tests/3.sor", "--print-col-type", "4"]);
+        let out = run(&print_col_type, &schema, || {
+            panic!("--print-col-type should not parse the file")
+        });
+        assert_eq!(out, "BOOL");
+
+        let infer_only = args(&["sorer", "-f", "tests/3.sor", "--infer-only"]);
+        let out = run(&infer_only, &schema, || {
+            panic!("--infer-only should not parse the file")
+        });
+        assert_eq!(
+            out,
+            schema
+                .iter()
+                .map(|t| format!("{:?}", t).to_uppercase())
+                .collect::<Vec<String>>()
+                .join(" ")
+        );
+    }
+
+    #[test]
+    fn test_limit_cols() {
+        // a single line of 50 fields among rows of 4
+        let file = "tests/garbled_width.sor";
+        let unlimited = args(&["sorer", "-f", file, "--infer-only"]);
+        assert_eq!(unlimited.limit_cols, None);
+        assert_eq!(infer(file, &unlimited).len(), 50);
+
+        let limited =
+            args(&["sorer", "-f", file, "--limit-cols", "4", "--infer-only"]);
+        assert_eq!(limited.limit_cols, Some(4));
+        let schema = infer(file, &limited);
+        let out = run(&limited, &schema, || unreachable!());
+        assert_eq!(out, "INT FLOAT STRING BOOL");
+    }
+
+    #[test]
+    fn test_data_queries_parse() {
+        let schema = infer_schema("tests/2.sor").unwrap();
+        let mut parsed = 0;
+        let print_col_idx =
+            args(&["sorer", "-f", "tests/2.sor", "--print-col-idx", "3", "1"]);
+        let out = run(&print_col_idx, &schema, || {
+            parsed += 1;
+            from_file("tests/2.sor", schema.clone(), 0, usize::MAX, 8)
+        });
+        assert_eq!(out, "\"ho ho ho\"");
+        assert_eq!(parsed, 1);
+    }
+
+    #[test]
+    fn test_print_col_idx_missing() {
+        let schema = infer_schema("tests/2.sor").unwrap();
+        let parse =
+            || from_file("tests/2.sor", schema.clone(), 0, usize::MAX, 8);
+        let print_col_idx =
+            args(&["sorer", "-f", "tests/2.sor", "--print-col-idx", "1", "0"]);
+        assert_eq!(run(&print_col_idx, &schema, parse), "<>");
+
+        let is_missing_idx =
+            args(&["sorer", "-f", "tests/2.sor", "--is-missing-idx", "1", "0"]);
+        assert_eq!(run(&is_missing_idx, &schema, parse), "1");
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let schema = infer_schema("tests/diff_a.sor").unwrap();
+        let schema_diff = args(&[
+            "sorer",
+            "-f",
+            "tests/diff_a.sor",
+            "--schema-diff",
+            "tests/diff_b.sor",
+        ]);
+        let out = run(&schema_diff, &schema, || {
+            panic!("--schema-diff should not parse the file")
+        });
+        assert_eq!(out, "column 1: String != Int\ncolumn 3: Bool only in b");
+
+        let same = args(&[
+            "sorer",
+            "-f",
+            "tests/diff_a.sor",
+            "--schema-diff",
+            "tests/diff_a.sor",
+        ]);
+        let out = run(&same, &schema, || unreachable!());
+        assert_eq!(out, "The schemas are equal");
+    }
+
+    #[test]
+    fn test_version() {
+        for flag in &["--version", "-V"] {
+            let parsed = args(&["sorer", flag]);
+            assert!(matches!(parsed.option, Options::Version));
+        }
+    }
+
+    #[test]
+    fn test_head() {
+        let schema = infer_schema("tests/2.sor").unwrap();
+        let head = args(&["sorer", "-f", "tests/2.sor", "--head", "1"]);
+        let out = run(&head, &schema, || {
+            from_file("tests/2.sor", schema.clone(), 0, usize::MAX, 8)
+        });
+        assert_eq!(out, "col0,col1,col2,col3\n1,,1.2,hi");
+
+        let head = args(&["sorer", "-f", "tests/2.sor", "--head", "5"]);
+        let out = run(&head, &schema, || {
+            from_file("tests/2.sor", schema.clone(), 0, usize::MAX, 8)
+        });
+        assert_eq!(out, "col0,col1,col2,col3\n1,,1.2,hi\n0,12,-0.2,ho ho ho");
+    }
+
+    #[test]
+    fn test_null_as() {
+        let schema = infer_schema("tests/2.sor").unwrap();
+        let parse =
+            || from_file("tests/2.sor", schema.clone(), 0, usize::MAX, 8);
+        let print_col_idx = args(&[
+            "sorer",
+            "-f",
+            "tests/2.sor",
+            "--print-col-idx",
+            "1",
+            "0",
+            "--null-as",
+            "NULL",
+        ]);
+        assert_eq!(run(&print_col_idx, &schema, parse), "NULL");
+
+        let head = args(&[
+            "sorer",
+            "-f",
+            "tests/2.sor",
+            "--null-as",
+            "NULL",
+            "--head",
+            "1",
+        ]);
+        assert_eq!(
+            run(&head, &schema, parse),
+            "col0,col1,col2,col3\n1,NULL,1.2,hi"
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        let schema = infer_schema("tests/names.sor").unwrap();
+        let parse =
+            || from_file("tests/names.sor", schema.clone(), 0, usize::MAX, 8);
+        let head = args(&["sorer", "-f", "tests/names.sor", "--head", "1"]);
+        assert_eq!(run(&head, &schema, parse), "id,name\n1,a");
+
+        let head = args(&[
+            "sorer",
+            "-f",
+            "tests/names.sor",
+            "--head",
+            "1",
+            "--names",
+            "key",
+        ]);
+        assert_eq!(run(&head, &schema, parse), "key,col1\n1,a");
+
+        let schema = infer_schema("tests/2.sor").unwrap();
+        let head = args(&[
+            "sorer",
+            "-f",
+            "tests/2.sor",
+            "--head",
+            "1",
+            "--names",
+            "a,b,c,d",
+        ]);
+        let out = run(&head, &schema, || {
+            from_file("tests/2.sor", schema.clone(), 0, usize::MAX, 8)
+        });
+        assert_eq!(out, "a,b,c,d\n1,,1.2,hi");
+    }
+
+    #[test]
+    fn test_quote_strings() {
+        let schema = infer_schema("tests/csv.sor").unwrap();
+        let head = |style: &str| {
+            let head = args(&[
+                "sorer",
+                "-f",
+                "tests/csv.sor",
+                "--head",
+                "2",
+                "--quote-strings",
+                style,
+            ]);
+            run(&head, &schema, || {
+                from_file("tests/csv.sor", schema.clone(), 0, usize::MAX, 8)
+            })
+        };
+        assert_eq!(head("minimal"), "col0,col1\n\"a,b\",1\nplain,2");
+        assert_eq!(head("always"), "\"col0\",\"col1\"\n\"a,b\",1\n\"plain\",2");
+        assert_eq!(
+            head("never"),
+            "Error: \"a,b\" can not be written without quotes"
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let schema = infer_schema("tests/2.sor").unwrap();
+        let describe = args(&["sorer", "-f", "tests/2.sor", "--describe"]);
+        let out = run(&describe, &schema, || {
+            from_file("tests/2.sor", schema.clone(), 0, usize::MAX, 8)
+        });
+        assert_eq!(
+            out,
+            "\
++------+--------+-------+-------+------+-----+--------+----------+
+| NAME | TYPE   | COUNT | NULLS | MIN  | MAX | MEAN   | DISTINCT |
++------+--------+-------+-------+------+-----+--------+----------+
+| col0 | BOOL   |     2 |     0 |    0 |   1 |  0.500 |          |
+| col1 | INT    |     1 |     1 |   12 |  12 | 12.000 |          |
+| col2 | FLOAT  |     2 |     0 | -0.2 | 1.2 |  0.500 |          |
+| col3 | STRING |     2 |     0 |      |     |        |        2 |
++------+--------+-------+-------+------+-----+--------+----------+"
+        );
+    }
+
+    #[test]
+    fn test_resolve_threads() {
+        let env = |v: &str| Some(v.to_string());
+        assert_eq!(resolve_threads(None, env("3")), 3);
+        assert_eq!(resolve_threads(Some(2), env("3")), 2);
+        assert_eq!(resolve_threads(Some(2), None), 2);
+        assert_eq!(resolve_threads(None, None), num_cpus::get());
+        assert_eq!(resolve_threads(None, env("lots")), num_cpus::get());
+        assert_eq!(resolve_threads(None, env("0")), num_cpus::get());
+
+        let parsed = args(&[
+            "sorer",
+            "-f",
+            "tests/2.sor",
+            "--threads",
+            "4",
+            "--infer-only",
+        ]);
+        assert_eq!(parsed.threads, Some(4));
+        let parsed = args(&["sorer", "-f", "tests/2.sor", "--infer-only"]);
+        assert_eq!(parsed.threads, None);
+    }
+
+    #[test]
+    fn test_progress_bar() {
+        let second = Duration::from_secs(1);
+        assert_eq!(
+            progress_bar(0, 100, second),
+            format!("[{}]   0% ETA ?", "-".repeat(30))
+        );
+        assert_eq!(
+            progress_bar(25, 100, second * 2),
+            format!("[{}{}]  25% ETA 6s", "#".repeat(7), "-".repeat(23))
+        );
+        assert_eq!(
+            progress_bar(100, 100, second * 8),
+            format!("[{}] 100% ETA 0s", "#".repeat(30))
+        );
+
+        let parsed =
+            args(&["sorer", "-f", "tests/2.sor", "--progress", "--infer-only"]);
+        assert!(parsed.progress);
+        assert!(matches!(parsed.option, Options::InferOnly));
+        assert!(
+            !args(&["sorer", "-f", "tests/2.sor", "--infer-only"]).progress
+        );
+    }
+
+    #[test]
+    fn test_sample() {
+        let schema = infer_schema("tests/1.sor").unwrap();
+        let sample = args(&[
+            "sorer",
+            "-f",
+            "tests/1.sor",
+            "--sample",
+            "3",
+            "--seed",
+            "42",
+        ]);
+        let out = run(&sample, &schema, || {
+            panic!("--sample should stream the file instead of parsing it")
+        });
+        assert_eq!(
+            out,
+            run(&sample, &schema, || unreachable!("the file is streamed"))
+        );
+        // a sample larger than the file prints every row
+        let everything = args(&[
+            "sorer",
+            "-f",
+            "tests/1.sor",
+            "--sample",
+            &usize::MAX.to_string(),
+        ]);
+        let parsed = from_file("tests/1.sor", schema.clone(), 0, usize::MAX, 8);
+        assert_eq!(
+            run(&everything, &schema, || unreachable!()),
+            format_table(&parsed, &schema, usize::MAX)
+        );
+        // within a byte range the parsed rows are sampled
+        let ranged = args(&[
+            "sorer",
+            "-f",
+            "tests/1.sor",
+            "--from",
+            "0",
+            "--len",
+            "1000",
+            "--sample",
+            "3",
+            "--seed",
+            "42",
+        ]);
+        let parse =
+            || from_file("tests/1.sor", schema.clone(), 0, usize::MAX, 8);
+        assert_eq!(run(&ranged, &schema, parse), out);
+        assert_eq!(
+            out,
+            "\
++--------+
+| STRING |
++--------+
+| -0     |
+| .12    |
+| lo     |
++--------+"
+        );
+    }
+}
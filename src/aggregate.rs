@@ -0,0 +1,676 @@
+//! A module for computing per-column statistics over parsed `SoR` data,
+//! either over a whole `Vec<Column>` at once or incrementally over the chunks
+//! of a [`SorTerator`](crate::dataframe::SorTerator).
+//!
+//! Approximate quantiles of numeric columns can be estimated in the same
+//! streaming pass with a [`StreamingHistogram`].
+
+use crate::dataframe::{truncate_cell, write_table, Column, Data, SorTerator};
+use crate::schema::DataType;
+use std::io::{self, Write};
+
+/// Summary statistics of a single column.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ColumnStats {
+    /// The number of cells that are not missing.
+    pub count: usize,
+    /// The number of missing cells.
+    pub null_count: usize,
+    /// The sum of the column: a `Data::Int` for `Int` columns, a
+    /// `Data::Float` for `Float` columns, the number of `true` cells as a
+    /// `Data::Int` for `Bool` columns and `Data::Null` for `String` columns.
+    /// The sum of an `Int` column that does not fit in an `i64` is a
+    /// `Data::Float` instead.
+    pub sum: Data,
+    /// The smallest cell in the column, or `Data::Null` if every cell is
+    /// missing. `NaN`s are ignored.
+    pub min: Data,
+    /// The largest cell in the column, or `Data::Null` if every cell is
+    /// missing. `NaN`s are ignored.
+    pub max: Data,
+}
+
+impl ColumnStats {
+    /// Creates the statistics of an empty column of the given `DataType`.
+    pub fn new(data_type: &DataType) -> Self {
+        let sum = match data_type {
+            DataType::Bool | DataType::Int => Data::Int(0),
+            DataType::Float => Data::Float(0.0),
+            DataType::String => Data::Null,
+        };
+        ColumnStats {
+            count: 0,
+            null_count: 0,
+            sum,
+            min: Data::Null,
+            max: Data::Null,
+        }
+    }
+
+    /// Updates these statistics with every cell of `column`, as if `column`
+    /// was appended to the column these statistics were computed over.
+    ///
+    /// # Panics
+    /// If `column` is of a different type than these statistics.
+    pub fn update(&mut self, column: &Column) {
+        let count_before = self.count;
+        match column {
+            Column::Bool(c) => {
+                for b in c.iter().flatten() {
+                    self.add(Data::Bool(*b));
+                }
+            }
+            Column::PackedBool(c) => {
+                for b in c.iter().flatten() {
+                    self.add(Data::Bool(b));
+                }
+            }
+            Column::Int(c) => {
+                for n in c.iter().flatten() {
+                    self.add(Data::Int(*n));
+                }
+            }
+            Column::Int32(c) => {
+                for n in c.iter().flatten() {
+                    self.add(Data::Int(i64::from(*n)));
+                }
+            }
+            Column::Float(c) => {
+                for f in c.iter().flatten() {
+                    self.add(Data::Float(*f));
+                }
+            }
+            Column::String(c) => {
+                for s in c.iter().flatten() {
+                    self.add(Data::String(s.clone()));
+                }
+            }
+        }
+        self.null_count += column.len() - (self.count - count_before);
+    }
+
+    // adds a single non-missing cell to these statistics
+    fn add(&mut self, value: Data) {
+        self.count += 1;
+        self.sum = match (&self.sum, &value) {
+            (Data::Int(sum), Data::Bool(b)) => Data::Int(sum + *b as i64),
+            (Data::Int(sum), Data::Int(n)) => match sum.checked_add(*n) {
+                Some(sum) => Data::Int(sum),
+                None => Data::Float(*sum as f64 + *n as f64),
+            },
+            // an `Int` column whose sum overflowed an `i64`
+            (Data::Float(sum), Data::Int(n)) => Data::Float(sum + *n as f64),
+            (Data::Float(sum), Data::Float(f)) => Data::Float(sum + f),
+            (Data::Null, Data::String(_)) => Data::Null,
+            _ => panic!("Column type does not match the statistics type"),
+        };
+        if let Data::Float(f) = value {
+            if f.is_nan() {
+                return;
+            }
+        }
+        if self.min == Data::Null || less_than(&value, &self.min) {
+            self.min = value.clone();
+        }
+        if self.max == Data::Null || less_than(&self.max, &value) {
+            self.max = value;
+        }
+    }
+}
+
+// Compares two non-missing cells of the same type
+fn less_than(a: &Data, b: &Data) -> bool {
+    match (a, b) {
+        (Data::Bool(a), Data::Bool(b)) => a < b,
+        (Data::Int(a), Data::Int(b)) => a < b,
+        (Data::Float(a), Data::Float(b)) => a < b,
+        (Data::String(a), Data::String(b)) => a < b,
+        _ => panic!("Column type does not match the statistics type"),
+    }
+}
+
+impl From<&Column> for ColumnStats {
+    fn from(column: &Column) -> Self {
+        let mut stats = ColumnStats::new(&column.data_type());
+        stats.update(column);
+        stats
+    }
+}
+
+/// Computes the [`ColumnStats`] of every column in `columns` at once.
+pub fn column_stats(columns: &[Column]) -> Vec<ColumnStats> {
+    columns.iter().map(ColumnStats::from).collect()
+}
+
+/// A one line summary of a column, as computed by [`describe`].
+#[derive(PartialEq, Clone, Debug)]
+pub struct ColumnSummary {
+    /// The type of the column in the schema.
+    pub data_type: DataType,
+    /// The number of cells that are not missing.
+    pub count: usize,
+    /// The number of missing cells.
+    pub null_count: usize,
+    /// The smallest cell of a `Bool`, `Int` or `Float` column, or
+    /// `Data::Null` for `String` columns and columns with no present cells.
+    pub min: Data,
+    /// The largest cell of a `Bool`, `Int` or `Float` column, or `Data::Null`
+    /// for `String` columns and columns with no present cells.
+    pub max: Data,
+    /// The mean of the present cells of a `Bool` (the share of `true` cells),
+    /// `Int` or `Float` column, or `None` for `String` columns and columns
+    /// with no present cells.
+    pub mean: Option<f64>,
+    /// The number of distinct present cells of a `String` column, or `None`
+    /// for all other columns.
+    pub distinct: Option<usize>,
+}
+
+/// Summarizes every column of `columns`, whose types are given by `schema`,
+/// similarly to `describe` in pandas: numeric columns report their minimum,
+/// maximum and mean, while `String` columns report how many distinct values
+/// they hold instead.
+///
+/// # Panics
+/// If a column's type does not match its type in `schema`.
+pub fn describe(columns: &[Column], schema: &[DataType]) -> Vec<ColumnSummary> {
+    columns
+        .iter()
+        .zip(schema)
+        .map(|(column, data_type)| {
+            let mut stats = ColumnStats::new(data_type);
+            stats.update(column);
+            let is_string = *data_type == DataType::String;
+            let mean = match stats.sum.as_f64() {
+                Some(sum) if stats.count > 0 => Some(sum / stats.count as f64),
+                _ => None,
+            };
+            // `unique` counts all missing cells as a single distinct cell
+            let distinct = if is_string {
+                Some(column.unique().len() - usize::from(stats.null_count > 0))
+            } else {
+                None
+            };
+            ColumnSummary {
+                data_type: data_type.clone(),
+                count: stats.count,
+                null_count: stats.null_count,
+                min: if is_string { Data::Null } else { stats.min },
+                max: if is_string { Data::Null } else { stats.max },
+                mean,
+                distinct,
+            }
+        })
+        .collect()
+}
+
+/// Writes `summaries` to `out` as a bordered ASCII table with one row per
+/// column, named by `names`. Values that don't apply to a column are left
+/// empty.
+pub fn print_summary<W: Write>(
+    summaries: &[ColumnSummary],
+    names: &[String],
+    out: &mut W,
+) -> io::Result<()> {
+    let headers: Vec<String> = [
+        "NAME", "TYPE", "COUNT", "NULLS", "MIN", "MAX", "MEAN", "DISTINCT",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect();
+    let data_cell = |d: &Data| match d {
+        Data::Null => String::new(),
+        Data::String(s) => s.clone(),
+        d => d.to_string(),
+    };
+    let cells: Vec<Vec<String>> = vec![
+        names.iter().cloned().map(truncate_cell).collect(),
+        summaries
+            .iter()
+            .map(|s| format!("{:?}", s.data_type).to_uppercase())
+            .collect(),
+        summaries.iter().map(|s| s.count.to_string()).collect(),
+        summaries.iter().map(|s| s.null_count.to_string()).collect(),
+        summaries
+            .iter()
+            .map(|s| truncate_cell(data_cell(&s.min)))
+            .collect(),
+        summaries
+            .iter()
+            .map(|s| truncate_cell(data_cell(&s.max)))
+            .collect(),
+        summaries
+            .iter()
+            .map(|s| s.mean.map_or(String::new(), |m| format!("{:.3}", m)))
+            .collect(),
+        summaries
+            .iter()
+            .map(|s| s.distinct.map_or(String::new(), |d| d.to_string()))
+            .collect(),
+    ];
+    let right_aligned = [false, false, true, true, true, true, true, true];
+    write_table(&headers, &cells, &right_aligned, out)
+}
+
+/// Estimates quantiles of a numeric column in a single pass using bounded
+/// memory, so that quantiles of columns too large to fit in memory can be
+/// approximated.
+///
+/// Values are summarized by a sorted list of centroids (a mean and a count)
+/// in the style of a merging t-digest: centroids near the median may hold
+/// many values while centroids near the tails hold few, so extreme quantiles
+/// stay accurate. At most about `compression` centroids are kept. Two
+/// histograms can be [merged](StreamingHistogram::merge), so different
+/// chunks of a file can be summarized separately, for example in parallel,
+/// and combined afterwards.
+#[derive(Clone, Debug)]
+pub struct StreamingHistogram {
+    compression: usize,
+    // sorted by mean, holds every value seen before the last compression
+    centroids: Vec<(f64, u64)>,
+    // values seen since the last compression
+    buffer: Vec<f64>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl StreamingHistogram {
+    /// Creates an empty `StreamingHistogram` keeping about `compression`
+    /// centroids. Larger values are more accurate but use more memory, `100`
+    /// is usually accurate to well within a percent.
+    pub fn new(compression: usize) -> Self {
+        StreamingHistogram {
+            compression: compression.max(1),
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Returns the number of values added so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Adds a single value. `NaN`s are ignored.
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push(value);
+        if self.buffer.len() >= 8 * self.compression {
+            self.compress();
+        }
+    }
+
+    /// Adds every non-missing cell of `column`.
+    ///
+    /// # Panics
+    /// If `column` is not an `Int` or `Float` column.
+    pub fn update(&mut self, column: &Column) {
+        match column {
+            Column::Int(c) => {
+                c.iter().flatten().for_each(|n| self.add(*n as f64))
+            }
+            Column::Int32(c) => {
+                c.iter().flatten().for_each(|n| self.add(f64::from(*n)))
+            }
+            Column::Float(c) => c.iter().flatten().for_each(|f| self.add(*f)),
+            _ => panic!("Quantiles can only be estimated for numeric columns"),
+        }
+    }
+
+    /// Merges every value summarized by `other` into this histogram, as if
+    /// they had been added to it directly.
+    pub fn merge(&mut self, other: &StreamingHistogram) {
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.centroids.extend_from_slice(&other.centroids);
+        self.buffer.extend_from_slice(&other.buffer);
+        self.compress();
+    }
+
+    /// Estimates the `q` quantile of the values added so far, where `q` is
+    /// between `0.0` and `1.0`, so `0.5` estimates the median. Returns `None`
+    /// if no values have been added.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let mut histogram = self.clone();
+        histogram.compress();
+        let centroids = &histogram.centroids;
+
+        // each centroid is treated as if its values were centered on its
+        // mean, and values between centroids are interpolated
+        let target = q.clamp(0.0, 1.0) * self.count as f64;
+        let mut before = 0.0;
+        let mut prev = (self.min, 0.0);
+        for (mean, count) in centroids {
+            let center = before + *count as f64 / 2.0;
+            if target < center {
+                return Some(interpolate(prev, (*mean, center), target));
+            }
+            prev = (*mean, center);
+            before += *count as f64;
+        }
+        Some(interpolate(prev, (self.max, before), target))
+    }
+
+    // Merges the buffered values into the centroids, combining neighbouring
+    // centroids while they stay within the size limit for their quantile
+    fn compress(&mut self) {
+        let mut all = std::mem::take(&mut self.centroids);
+        all.extend(self.buffer.drain(..).map(|v| (v, 1)));
+        if all.is_empty() {
+            return;
+        }
+        // `NaN`s are never added, so the means are always comparable
+        all.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let total = all.iter().map(|c| c.1).sum::<u64>() as f64;
+        let mut merged = Vec::with_capacity(self.compression);
+        let mut before = 0.0;
+        let mut current = all[0];
+        for &(mean, count) in &all[1..] {
+            let combined = (current.1 + count) as f64;
+            let q = (before + combined / 2.0) / total;
+            let limit = 4.0 * total * q * (1.0 - q) / self.compression as f64;
+            if combined <= limit.max(1.0) {
+                current.0 += (mean - current.0) * count as f64 / combined;
+                current.1 += count;
+            } else {
+                before += current.1 as f64;
+                merged.push(current);
+                current = (mean, count);
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+}
+
+// Linearly interpolates the value at `target` between two (value, position)
+// points
+fn interpolate(a: (f64, f64), b: (f64, f64), target: f64) -> f64 {
+    if b.1 <= a.1 {
+        return b.0;
+    }
+    a.0 + (b.0 - a.0) * (target - a.1) / (b.1 - a.1)
+}
+
+/// Computes [`ColumnStats`] for every column in a single streaming pass, so
+/// that files too large to fit in memory can be summarized.
+#[derive(Clone, Debug)]
+pub struct Aggregator {
+    stats: Vec<ColumnStats>,
+    // empty unless enabled with `with_histograms`, otherwise has a histogram
+    // for every numeric column
+    histograms: Vec<Option<StreamingHistogram>>,
+}
+
+impl Aggregator {
+    /// Creates a new `Aggregator` for data with the given `schema`.
+    pub fn new(schema: &[DataType]) -> Self {
+        Aggregator {
+            stats: schema.iter().map(ColumnStats::new).collect(),
+            histograms: Vec::new(),
+        }
+    }
+
+    /// Also estimates the quantiles of every `Int` and `Float` column with a
+    /// [`StreamingHistogram`] of the given `compression`, which can be read
+    /// with [`histogram`](Aggregator::histogram).
+    pub fn with_histograms(
+        mut self,
+        schema: &[DataType],
+        compression: usize,
+    ) -> Self {
+        self.histograms = schema
+            .iter()
+            .map(|t| match t {
+                DataType::Int | DataType::Float => {
+                    Some(StreamingHistogram::new(compression))
+                }
+                _ => None,
+            })
+            .collect();
+        self
+    }
+
+    /// Updates the statistics with a chunk of parsed data.
+    pub fn update(&mut self, chunk: &[Column]) {
+        for (stats, column) in self.stats.iter_mut().zip(chunk) {
+            stats.update(column);
+        }
+        for (histogram, column) in self.histograms.iter_mut().zip(chunk) {
+            if let Some(histogram) = histogram {
+                histogram.update(column);
+            }
+        }
+    }
+
+    /// Returns the [`StreamingHistogram`] of the column at `col_idx`, or
+    /// `None` if histograms were not enabled with
+    /// [`with_histograms`](Aggregator::with_histograms) or the column is not
+    /// numeric.
+    pub fn histogram(&self, col_idx: usize) -> Option<&StreamingHistogram> {
+        self.histograms.get(col_idx).and_then(Option::as_ref)
+    }
+
+    /// Updates the statistics with every chunk produced by `terator`.
+    pub fn consume(&mut self, terator: SorTerator) {
+        for chunk in terator {
+            self.update(&chunk);
+        }
+    }
+
+    /// Returns the statistics of every column seen so far.
+    pub fn finish(self) -> Vec<ColumnStats> {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::from_file;
+    use crate::schema::infer_schema;
+
+    #[test]
+    fn test_describe() {
+        let columns = vec![
+            Column::Bool(vec![Some(true), Some(false), Some(true), None]),
+            Column::Int(vec![Some(3), None, Some(-1), Some(4)]),
+            Column::Float(vec![None, None, None, None]),
+            Column::String(vec![
+                Some("b".to_string()),
+                None,
+                Some("a".to_string()),
+                Some("b".to_string()),
+            ]),
+        ];
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let summaries = describe(&columns, &schema);
+        assert_eq!(
+            summaries[0],
+            ColumnSummary {
+                data_type: DataType::Bool,
+                count: 3,
+                null_count: 1,
+                min: Data::Bool(false),
+                max: Data::Bool(true),
+                mean: Some(2.0 / 3.0),
+                distinct: None,
+            }
+        );
+        assert_eq!(
+            summaries[1],
+            ColumnSummary {
+                data_type: DataType::Int,
+                count: 3,
+                null_count: 1,
+                min: Data::Int(-1),
+                max: Data::Int(4),
+                mean: Some(2.0),
+                distinct: None,
+            }
+        );
+        assert_eq!(summaries[2].count, 0);
+        assert_eq!(summaries[2].min, Data::Null);
+        assert_eq!(summaries[2].mean, None);
+        assert_eq!(
+            summaries[3],
+            ColumnSummary {
+                data_type: DataType::String,
+                count: 3,
+                null_count: 1,
+                min: Data::Null,
+                max: Data::Null,
+                mean: None,
+                distinct: Some(2),
+            }
+        );
+
+        let names: Vec<String> = vec!["flag", "n", "f", "s"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let mut out = Vec::new();
+        print_summary(&summaries, &names, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
++------+--------+-------+-------+-----+-----+-------+----------+
+| NAME | TYPE   | COUNT | NULLS | MIN | MAX | MEAN  | DISTINCT |
++------+--------+-------+-------+-----+-----+-------+----------+
+| flag | BOOL   |     3 |     1 |   0 |   1 | 0.667 |          |
+| n    | INT    |     3 |     1 |  -1 |   4 | 2.000 |          |
+| f    | FLOAT  |     0 |     4 |     |     |       |          |
+| s    | STRING |     3 |     1 |     |     |       |        2 |
++------+--------+-------+-------+-----+-----+-------+----------+
+"
+        );
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let ints = Column::Int(vec![Some(3), None, Some(-1), Some(5)]);
+        assert_eq!(
+            ColumnStats::from(&ints),
+            ColumnStats {
+                count: 3,
+                null_count: 1,
+                sum: Data::Int(7),
+                min: Data::Int(-1),
+                max: Data::Int(5),
+            }
+        );
+
+        // a sum that overflows an `i64` becomes a float
+        let large = Column::Int(vec![Some(i64::MAX), Some(i64::MAX), Some(-1)]);
+        let stats = ColumnStats::from(&large);
+        assert_eq!(stats.sum, Data::Float(2.0 * i64::MAX as f64 - 1.0));
+        assert_eq!(stats.max, Data::Int(i64::MAX));
+        let summary = &describe(&[large], &[DataType::Int])[0];
+        assert_eq!(summary.mean, Some(2.0 * i64::MAX as f64 / 3.0));
+
+        let bools = Column::Bool(vec![Some(true), Some(true), None]);
+        let stats = ColumnStats::from(&bools);
+        assert_eq!(stats.sum, Data::Int(2));
+        assert_eq!(stats.min, Data::Bool(true));
+
+        let strings = Column::String(vec![
+            Some("b".to_string()),
+            Some("a".to_string()),
+            Some("c".to_string()),
+        ]);
+        let stats = ColumnStats::from(&strings);
+        assert_eq!(stats.sum, Data::Null);
+        assert_eq!(stats.min, Data::String("a".to_string()));
+        assert_eq!(stats.max, Data::String("c".to_string()));
+
+        let nulls = Column::Float(vec![None, None]);
+        let stats = ColumnStats::from(&nulls);
+        assert_eq!(stats.null_count, 2);
+        assert_eq!(stats.min, Data::Null);
+    }
+
+    #[test]
+    fn test_aggregator_matches_batch() {
+        let file = "tests/sor_terator.sor";
+        let schema = infer_schema(file).unwrap();
+        let batch =
+            column_stats(&from_file(file, schema.clone(), 0, usize::MAX, 8));
+
+        let mut aggregator = Aggregator::new(&schema);
+        aggregator.consume(SorTerator::new(file, schema, 4));
+        assert_eq!(aggregator.finish(), batch);
+    }
+
+    #[test]
+    fn test_streaming_histogram_median() {
+        // a permutation of 0..100_000 so that values arrive out of order
+        let values: Vec<f64> = (0..100_000u64)
+            .map(|i| ((i * 7919) % 100_000) as f64)
+            .collect();
+        let mut histogram = StreamingHistogram::new(100);
+        for v in &values {
+            histogram.add(*v);
+        }
+        assert_eq!(histogram.count(), 100_000);
+        let median = histogram.quantile(0.5).unwrap();
+        assert!((median - 50_000.0).abs() < 500.0, "median was {}", median);
+        let p99 = histogram.quantile(0.99).unwrap();
+        assert!((p99 - 99_000.0).abs() < 100.0, "p99 was {}", p99);
+        assert_eq!(histogram.quantile(0.0), Some(0.0));
+        assert_eq!(histogram.quantile(1.0), Some(99_999.0));
+
+        // merging histograms of two halves gives the same estimates
+        let mut a = StreamingHistogram::new(100);
+        let mut b = StreamingHistogram::new(100);
+        for v in &values[..30_000] {
+            a.add(*v);
+        }
+        for v in &values[30_000..] {
+            b.add(*v);
+        }
+        a.merge(&b);
+        assert_eq!(a.count(), 100_000);
+        let median = a.quantile(0.5).unwrap();
+        assert!((median - 50_000.0).abs() < 500.0, "median was {}", median);
+
+        assert_eq!(StreamingHistogram::new(100).quantile(0.5), None);
+    }
+
+    #[test]
+    fn test_aggregator_histograms() {
+        let file = "tests/sor_terator.sor";
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let mut aggregator =
+            Aggregator::new(&schema).with_histograms(&schema, 100);
+        aggregator.consume(SorTerator::new(file, schema, 4));
+        assert!(aggregator.histogram(0).is_none());
+        assert!(aggregator.histogram(3).is_none());
+        // few enough values that every value is its own centroid
+        assert_eq!(aggregator.histogram(1).unwrap().quantile(0.5), Some(5.0));
+        assert_eq!(aggregator.histogram(2).unwrap().count(), 15);
+    }
+}
@@ -1,31 +1,23 @@
 //! Generates a test file with random data in 8 columns 2 of each type.
 //! can be run with `cargo run --bin generate`
 
-extern crate rand;
-
-use rand::distributions::Alphanumeric;
-use rand::{thread_rng, Rng};
+use sorer::generate::generate_sor;
+use sorer::schema::DataType;
 
 use std::fs::File;
-use std::io::prelude::*;
+use std::io::BufWriter;
 
 fn main() {
-    let mut f = File::create("foo.txt").unwrap();
-    let mut rng = thread_rng();
-    for _ in 0..10_000_000 {
-        let s1: String = rng.sample_iter(&Alphanumeric).take(12).collect();
-        let s2: String = rng.sample_iter(&Alphanumeric).take(12).collect();
-        let to_insert = format!(
-            "< {} > < {} > < {} > < {} > < {} > < {} > < {} > < {} >\n",
-            rng.gen::<i32>(),
-            rng.gen_range(-100.0, 100.0),
-            rng.gen::<i32>(),
-            rng.gen_range(-100.0, 100.0),
-            rng.gen_range::<i32, i32, i32>(0, 2),
-            rng.gen_range::<i32, i32, i32>(0, 2),
-            s1,
-            s2
-        );
-        f.write_all(to_insert.as_bytes()).unwrap();
-    }
+    let mut f = BufWriter::new(File::create("foo.txt").unwrap());
+    let schema = vec![
+        DataType::Int,
+        DataType::Float,
+        DataType::Int,
+        DataType::Float,
+        DataType::Bool,
+        DataType::Bool,
+        DataType::String,
+        DataType::String,
+    ];
+    generate_sor(&schema, 10_000_000, &mut f, rand::random()).unwrap();
 }
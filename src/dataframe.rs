@@ -1,18 +1,35 @@
 //! This module defines functions to parse a `SoR` file into a columnar
 //! format as a `Vec<Column>`.
 
-use crate::parsers::parse_line_with_schema;
+use crate::parsers::{
+    decode_line, has_unclosed_quote, is_comment, parse_line_with_schema_opts,
+    parse_line_with_schema_projected_stats, parse_line_with_schema_stats,
+    ParseStats, ParserConfig,
+};
 use crate::schema::DataType;
 use deepsize::DeepSizeOf;
+use memchr::memchr;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::convert::{From, TryFrom};
 use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom, Split};
+use std::hash::Hash;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::iter::FromIterator;
+use std::ops::Range;
+use std::sync::mpsc::{channel, Receiver};
 use std::thread;
 
 /// Represents a column of parsed data from a `SoR` file.
-#[derive(PartialEq, Clone, Debug, Serialize, Deserialize, DeepSizeOf)]
+///
+/// Columns are equal when they hold the same cells, however they are stored,
+/// so a `Column::PackedBool` equals a `Column::Bool` with the same cells, and
+/// a `Column::Int32` equals a `Column::Int` with the same values.
+#[derive(Clone, Debug, Serialize, Deserialize, DeepSizeOf)]
 pub enum Column {
     /// A Column consisting of optional `i64`s.
     Int(Vec<Option<i64>>),
@@ -22,506 +39,5061 @@ pub enum Column {
     Float(Vec<Option<f64>>),
     /// A Column consisting of optional `String`s.
     String(Vec<Option<String>>),
+    // new variants are added last so that frames serialized by older
+    // versions still deserialize into the same variants
+    /// A Column of `DataType::Int` consisting of optional `i32`s, used
+    /// instead of `Column::Int` when
+    /// [`narrow_ints`](crate::parsers::ParserConfig::narrow_ints) is set and
+    /// every value fits in an `i32`. Pushing a value that does not fit widens
+    /// the column to a `Column::Int`.
+    Int32(Vec<Option<i32>>),
+    /// A Column of `DataType::Bool` stored as two bitsets, using two bits per
+    /// cell instead of the byte of a `Column::Bool`. Used instead of
+    /// `Column::Bool` when
+    /// [`pack_bools`](crate::parsers::ParserConfig::pack_bools) is set.
+    PackedBool(PackedBools),
+}
+
+impl PartialEq for Column {
+    fn eq(&self, other: &Column) -> bool {
+        match (self, other) {
+            (Column::Bool(a), Column::Bool(b)) => a == b,
+            (Column::PackedBool(a), Column::PackedBool(b)) => a == b,
+            (Column::Bool(a), Column::PackedBool(b))
+            | (Column::PackedBool(b), Column::Bool(a)) => {
+                a.iter().copied().eq(b.iter())
+            }
+            (Column::Int(a), Column::Int(b)) => a == b,
+            (Column::Int32(a), Column::Int32(b)) => a == b,
+            (Column::Int(a), Column::Int32(b))
+            | (Column::Int32(b), Column::Int(a)) => {
+                a.iter().copied().eq(b.iter().map(|v| v.map(i64::from)))
+            }
+            (Column::Float(a), Column::Float(b)) => a == b,
+            (Column::String(a), Column::String(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
 impl Column {
+    /// Returns the number of rows in this `Column`.
     pub fn len(&self) -> usize {
-        match &self {
-            &Column::Bool(col) => col.len(),
-            &Column::Int(col) => col.len(),
-            &Column::Float(col) => col.len(),
-            &Column::String(col) => col.len(),
+        match self {
+            Column::Bool(col) => col.len(),
+            Column::PackedBool(col) => col.len(),
+            Column::Int(col) => col.len(),
+            Column::Int32(col) => col.len(),
+            Column::Float(col) => col.len(),
+            Column::String(col) => col.len(),
         }
     }
-}
 
-/// An enumeration of the possible `SoR` data types, that also contains the
-/// data itself.
-#[derive(PartialEq, Clone, Debug, Serialize, Deserialize, DeepSizeOf)]
-pub enum Data {
-    /// A `String` cell.
-    String(String),
-    /// A `i64` cell.
-    Int(i64),
-    /// A `f64` cell.
-    Float(f64),
-    /// A `bool` cell.
-    Bool(bool),
-    /// A missing value.
-    Null,
-}
+    /// Returns `true` if this `Column` has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-impl Data {
-    /// Get the data assuming its a String
-    pub fn unwrap_string(&self) -> String {
+    /// Compares two `Column`s, allowing `Float` cells to differ by at most
+    /// `epsilon` and treating two `NaN` cells as equal. All other column
+    /// types are compared exactly. Columns of different types or lengths are
+    /// never equal.
+    pub fn approx_eq(&self, other: &Column, epsilon: f64) -> bool {
+        match (self, other) {
+            (Column::Float(c1), Column::Float(c2)) => {
+                c1.len() == c2.len()
+                    && c1.iter().zip(c2.iter()).all(|pair| match pair {
+                        (Some(f1), Some(f2)) => {
+                            (f1.is_nan() && f2.is_nan())
+                                || (f1 - f2).abs() <= epsilon
+                        }
+                        (None, None) => true,
+                        _ => false,
+                    })
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Compares two `Column`s only at the rows where both cells are present,
+    /// so a column whose missing cells were filled (such as with
+    /// [`fill_null`](Column::fill_null)) still equals the original. Columns
+    /// of different `DataType`s or lengths are never equal, but a
+    /// `Column::Int32` may equal a `Column::Int`.
+    pub fn values_eq_ignoring_nulls(&self, other: &Column) -> bool {
+        if self.data_type() != other.data_type() || self.len() != other.len() {
+            return false;
+        }
+        let (a, b) = (self.reader(), other.reader());
+        (0..a.len()).all(|row_idx| match (a.get(row_idx), b.get(row_idx)) {
+            (Data::Null, _) | (_, Data::Null) => true,
+            (d1, d2) => d1 == d2,
+        })
+    }
+
+    /// Returns `true` if the cells of this `Column` are in non-decreasing
+    /// order. Missing cells are ignored, so only the cells that are present
+    /// need to be in order. A `Float` column containing a `NaN` is never
+    /// sorted.
+    pub fn is_sorted(&self) -> bool {
         match self {
-            Data::String(s) => s.clone(),
-            _ => panic!("unwrap error"),
+            Column::Bool(c) => is_sorted_ignoring_nulls(c),
+            Column::PackedBool(c) => is_sorted_ignoring_nulls(&c.to_vec()),
+            Column::Int(c) => is_sorted_ignoring_nulls(c),
+            Column::Int32(c) => is_sorted_ignoring_nulls(c),
+            Column::Float(c) => {
+                !c.iter().flatten().any(|f| f.is_nan())
+                    && is_sorted_ignoring_nulls(c)
+            }
+            Column::String(c) => is_sorted_ignoring_nulls(c),
         }
     }
 
-    /// Get the data assuming its an int
-    pub fn unwrap_int(&self) -> i64 {
+    /// Returns a copy of this `Column` with every missing cell replaced by
+    /// `fill`. Filling with `Data::Null` returns an unchanged copy.
+    ///
+    /// Returns a [`TypeMismatch`] if `fill` is not of this column's type.
+    pub fn fill_null(&self, fill: &Data) -> Result<Column, TypeMismatch> {
+        match (self, fill) {
+            (_, Data::Null) => Ok(self.clone()),
+            (Column::Bool(c), Data::Bool(b)) => {
+                Ok(Column::Bool(c.iter().map(|v| v.or(Some(*b))).collect()))
+            }
+            (Column::PackedBool(c), Data::Bool(b)) => Ok(Column::PackedBool(
+                c.iter().map(|v| v.or(Some(*b))).collect(),
+            )),
+            (Column::Int(c), Data::Int(n)) => {
+                Ok(Column::Int(c.iter().map(|v| v.or(Some(*n))).collect()))
+            }
+            (Column::Int32(c), Data::Int(n)) => match i32::try_from(*n) {
+                Ok(n) => {
+                    Ok(Column::Int32(c.iter().map(|v| v.or(Some(n))).collect()))
+                }
+                Err(_) => {
+                    let mut wide = self.clone();
+                    wide.widen();
+                    wide.fill_null(fill)
+                }
+            },
+            (Column::Float(c), Data::Float(f)) => {
+                Ok(Column::Float(c.iter().map(|v| v.or(Some(*f))).collect()))
+            }
+            (Column::String(c), Data::String(s)) => Ok(Column::String(
+                c.iter()
+                    .map(|v| v.clone().or_else(|| Some(s.clone())))
+                    .collect(),
+            )),
+            _ => Err(TypeMismatch {
+                expected: self.data_type(),
+                found: fill.clone(),
+            }),
+        }
+    }
+
+    /// Returns the name of this `Column`'s type for use in messages:
+    /// `"bool"`, `"int"`, `"float"` or `"string"`. A `Column::Int32` is an
+    /// `"int"` as well.
+    pub fn type_name(&self) -> &'static str {
         match self {
-            Data::Int(n) => *n,
-            _ => panic!("unwrap error"),
+            Column::Bool(_) | Column::PackedBool(_) => "bool",
+            Column::Int(_) | Column::Int32(_) => "int",
+            Column::Float(_) => "float",
+            Column::String(_) => "string",
         }
     }
 
-    /// Get the data assuming its a float
-    pub fn unwrap_float(&self) -> f64 {
+    /// Returns the `DataType` of this `Column`.
+    pub fn data_type(&self) -> DataType {
         match self {
-            Data::Float(n) => *n,
-            _ => panic!("unwrap error"),
+            Column::Bool(_) | Column::PackedBool(_) => DataType::Bool,
+            Column::Int(_) | Column::Int32(_) => DataType::Int,
+            Column::Float(_) => DataType::Float,
+            Column::String(_) => DataType::String,
         }
     }
 
-    /// Get the data assuming its a bool
-    pub fn unwrap_bool(&self) -> bool {
+    /// Appends a missing cell to this `Column`.
+    pub fn push_null(&mut self) {
         match self {
-            Data::Bool(n) => *n,
-            _ => panic!("unwrap error"),
+            Column::Bool(c) => c.push(None),
+            Column::PackedBool(c) => c.push(None),
+            Column::Int(c) => c.push(None),
+            Column::Int32(c) => c.push(None),
+            Column::Float(c) => c.push(None),
+            Column::String(c) => c.push(None),
         }
     }
-}
 
-/// Generate a `Vec<Column>` matching the given schema.
-fn init_columnar(schema: &[DataType]) -> Vec<Column> {
-    let mut result = Vec::with_capacity(schema.len() + 1);
-    for t in schema {
-        match t {
-            DataType::Bool => result.push(Column::Bool(Vec::new())),
-            DataType::Int => result.push(Column::Int(Vec::new())),
-            DataType::Float => result.push(Column::Float(Vec::new())),
-            DataType::String => result.push(Column::String(Vec::new())),
+    /// Shortens this `Column` to its first `len` cells. Has no effect if it
+    /// has `len` or fewer cells.
+    pub fn truncate(&mut self, len: usize) {
+        match self {
+            Column::Bool(c) => c.truncate(len),
+            Column::PackedBool(c) => c.truncate(len),
+            Column::Int(c) => c.truncate(len),
+            Column::Int32(c) => c.truncate(len),
+            Column::Float(c) => c.truncate(len),
+            Column::String(c) => c.truncate(len),
         }
     }
-    result
-}
 
-// TODO: this has a bug if num_threads is == 1. See tests/lib.rs
-// `is_missing_idx` and `print_col_idx`
-// TODO: use crossbeam for scoped thread spawning and change from_file to
-// take `schema: &[DataType]`
+    /// Appends `n` missing cells to this `Column`.
+    pub fn extend_nulls(&mut self, n: usize) {
+        let len = self.len() + n;
+        match self {
+            Column::Bool(c) => c.resize(len, None),
+            Column::PackedBool(c) => c.extend_nulls(n),
+            Column::Int(c) => c.resize(len, None),
+            Column::Int32(c) => c.resize(len, None),
+            Column::Float(c) => c.resize(len, None),
+            Column::String(c) => c.resize(len, None),
+        }
+    }
 
-/// Reads `len` number of bytes from a given file starting at the `from` byte
-/// offset an according to the given `schema`.
-///
-/// This is the top level function for using `SoRer` and the one you should be
-///  using unless you are trying to extend `SoRer`. There are many intricate
-/// facets to using `SoRer` so you *must* RTFM [here](../index.html)
-pub fn from_file(
-    file_path: &str,
-    schema: Vec<DataType>,
-    from: usize,
-    len: usize,
-    num_threads: usize,
-) -> Vec<Column> {
-    // the total number of bytes to read
-    let num_chars = if len == std::usize::MAX {
-        (std::fs::metadata(file_path).unwrap().len() - from as u64) as f64
-    } else {
-        len as f64
-    };
-    // each thread will parse this many characters +- some number
-    let step = (num_chars / num_threads as f64).ceil() as usize;
+    /// Appends `b` to this `Column`.
+    ///
+    /// Returns a [`TypeMismatch`] if this is not a `Bool` column.
+    pub fn push_bool(&mut self, b: bool) -> Result<(), TypeMismatch> {
+        match self {
+            Column::Bool(c) => {
+                c.push(Some(b));
+                Ok(())
+            }
+            Column::PackedBool(c) => {
+                c.push(Some(b));
+                Ok(())
+            }
+            _ => Err(self.mismatch(Data::Bool(b))),
+        }
+    }
 
-    // setup the work array with the from / len for each thread
-    // each element in the work array is a tuple of (starting index, number of byte for this thread)
-    let f: File = File::open(file_path).unwrap();
-    let mut reader = BufReader::new(f);
-    let mut work: Vec<(usize, usize)> = Vec::with_capacity(num_threads + 1);
+    /// Appends `n` to this `Column`.
+    ///
+    /// Returns a [`TypeMismatch`] if this is not an `Int` column.
+    pub fn push_int(&mut self, n: i64) -> Result<(), TypeMismatch> {
+        if let Column::Int32(c) = self {
+            match i32::try_from(n) {
+                Ok(n) => {
+                    c.push(Some(n));
+                    return Ok(());
+                }
+                Err(_) => self.widen(),
+            }
+        }
+        match self {
+            Column::Int(c) => {
+                c.push(Some(n));
+                Ok(())
+            }
+            _ => Err(self.mismatch(Data::Int(n))),
+        }
+    }
 
-    // add the first one separately since we want to access the previous thread's
-    // work when in the loop. Since the work of the first thread will call
-    // `read_file(schema, 0, step)` it will not throw away the first line
-    // since from is 0 and will throw away the last line since step > 0
-    work.push((from, step));
+    /// Appends `f` to this `Column`.
+    ///
+    /// Returns a [`TypeMismatch`] if this is not a `Float` column.
+    pub fn push_float(&mut self, f: f64) -> Result<(), TypeMismatch> {
+        match self {
+            Column::Float(c) => {
+                c.push(Some(f));
+                Ok(())
+            }
+            _ => Err(self.mismatch(Data::Float(f))),
+        }
+    }
 
-    let mut so_far = from;
-    let mut buffer = Vec::new();
+    /// Appends `s` to this `Column`.
+    ///
+    /// Returns a [`TypeMismatch`] if this is not a `String` column.
+    pub fn push_string(&mut self, s: String) -> Result<(), TypeMismatch> {
+        match self {
+            Column::String(c) => {
+                c.push(Some(s));
+                Ok(())
+            }
+            _ => Err(self.mismatch(Data::String(s))),
+        }
+    }
 
-    // This loop finds the byte offset for the start of a line
-    // by adding the length of the last line that a previous thread would've
-    // thrown away. The work gets added to the following thread so that
-    // each thread starts at a full line and reads only until the end of a line
-    for i in 1..num_threads {
-        so_far += step;
-        // advance the reader to this threads starting index then
-        // find the next newline character
-        reader.seek(SeekFrom::Start(so_far as u64)).unwrap();
-        reader.read_until(b'\n', &mut buffer).unwrap();
-        work.push((so_far, step));
+    /// Appends the value of `data` to this `Column`, or a missing cell if
+    /// `data` is `Data::Null`.
+    ///
+    /// Returns a [`TypeMismatch`] if `data` is not of this column's type.
+    pub fn push_data(&mut self, data: Data) -> Result<(), TypeMismatch> {
+        match data {
+            Data::Bool(b) => self.push_bool(b),
+            Data::Int(n) => self.push_int(n),
+            Data::Float(f) => self.push_float(f),
+            Data::String(s) => self.push_string(s),
+            Data::Null => {
+                self.push_null();
+                Ok(())
+            }
+        }
+    }
 
-        // Since the previous thread throws away the last line, add the length
-        // of the last line of prev thread to the work of this thread so that
-        // we read all lines.
-        work.get_mut(i - 1).unwrap().1 += buffer.len() as usize + 1;
-        buffer.clear();
+    /// Collects `iter` into a `Column` whose type is the type of the first
+    /// value that is not `Data::Null`. An iterator of only `Data::Null`s
+    /// becomes a `Bool` column, the same as schema inference does for a
+    /// column of only missing values.
+    ///
+    /// Returns a [`TypeMismatch`] for the first value that is not of the same
+    /// type as the first one.
+    pub fn from_data_iter(
+        iter: impl Iterator<Item = Data>,
+    ) -> Result<Column, TypeMismatch> {
+        let mut leading_nulls = 0;
+        let mut column = None;
+        for data in iter {
+            let column = match (&mut column, &data) {
+                (Some(column), _) => column,
+                (None, Data::Null) => {
+                    leading_nulls += 1;
+                    continue;
+                }
+                (None, Data::Bool(_)) => {
+                    column.insert(Column::Bool(vec![None; leading_nulls]))
+                }
+                (None, Data::Int(_)) => {
+                    column.insert(Column::Int(vec![None; leading_nulls]))
+                }
+                (None, Data::Float(_)) => {
+                    column.insert(Column::Float(vec![None; leading_nulls]))
+                }
+                (None, Data::String(_)) => {
+                    column.insert(Column::String(vec![None; leading_nulls]))
+                }
+            };
+            column.push_data(data)?;
+        }
+        Ok(column.unwrap_or_else(|| Column::Bool(vec![None; leading_nulls])))
     }
 
-    // initialize the threads with their own BufReader
-    let mut threads = Vec::new();
-    for w in work {
-        let new_schema = schema.clone();
-        let f: File = File::open(file_path.clone()).unwrap();
-        let mut r = BufReader::new(f);
-        // spawn the thread and give it a closure which calls `from_file`
-        // to parse the data into columnar format.
-        threads.push(thread::spawn(move || {
-            read_chunk(new_schema, &mut r, w.0, w.1)
-        }));
+    /// Converts this `Column` into a `Vec` of its values, dropping missing
+    /// cells.
+    ///
+    /// Returns an error if this is not a `Bool` column.
+    pub fn into_vec_bool_filtered(self) -> Result<Vec<bool>, WrongType> {
+        Ok(Vec::<Option<bool>>::try_from(self)?
+            .into_iter()
+            .flatten()
+            .collect())
     }
 
-    // initialize the resulting columnar data frame
-    let mut parsed_data: Vec<Column> = init_columnar(&schema);
-    // let all the threads finish then combine the parsed data into the
-    // columnar data frame
-    for t in threads {
-        let mut x: Vec<Column> = t.join().unwrap();
-        let iter = parsed_data.iter_mut().zip(x.iter_mut());
-        for (complete, partial) in iter {
-            match (complete, partial) {
-                (Column::Bool(c1), Column::Bool(c2)) => c1.append(c2),
-                (Column::Int(c1), Column::Int(c2)) => c1.append(c2),
-                (Column::Float(c1), Column::Float(c2)) => c1.append(c2),
-                (Column::String(c1), Column::String(c2)) => c1.append(c2),
-                _ => panic!("Unexpected result from thread"),
+    /// Converts this `Column` into a `Vec` of its values, replacing missing
+    /// cells with `default`.
+    ///
+    /// Returns an error if this is not a `Bool` column.
+    pub fn into_vec_bool_or(
+        self,
+        default: bool,
+    ) -> Result<Vec<bool>, WrongType> {
+        Ok(fill_nones(Vec::<Option<bool>>::try_from(self)?, default))
+    }
+
+    /// Converts this `Column` into a `Vec` of its values, dropping missing
+    /// cells.
+    ///
+    /// Returns an error if this is not an `Int` column.
+    pub fn into_vec_int_filtered(self) -> Result<Vec<i64>, WrongType> {
+        Ok(Vec::<Option<i64>>::try_from(self)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Converts this `Column` into a `Vec` of its values, replacing missing
+    /// cells with `default`.
+    ///
+    /// Returns an error if this is not an `Int` column.
+    pub fn into_vec_int_or(self, default: i64) -> Result<Vec<i64>, WrongType> {
+        Ok(fill_nones(Vec::<Option<i64>>::try_from(self)?, default))
+    }
+
+    /// Converts this `Column` into a `Vec` of its values, dropping missing
+    /// cells.
+    ///
+    /// Returns an error if this is not a `Float` column.
+    pub fn into_vec_float_filtered(self) -> Result<Vec<f64>, WrongType> {
+        Ok(Vec::<Option<f64>>::try_from(self)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Converts this `Column` into a `Vec` of its values, replacing missing
+    /// cells with `default`.
+    ///
+    /// Returns an error if this is not a `Float` column.
+    pub fn into_vec_float_or(
+        self,
+        default: f64,
+    ) -> Result<Vec<f64>, WrongType> {
+        Ok(fill_nones(Vec::<Option<f64>>::try_from(self)?, default))
+    }
+
+    /// Converts this `Column` into a `Vec` of its values, dropping missing
+    /// cells.
+    ///
+    /// Returns an error if this is not a `String` column.
+    pub fn into_vec_string_filtered(self) -> Result<Vec<String>, WrongType> {
+        Ok(Vec::<Option<String>>::try_from(self)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Converts this `Column` into a `Vec` of its values, replacing missing
+    /// cells with `default`.
+    ///
+    /// Returns an error if this is not a `String` column.
+    pub fn into_vec_string_or(
+        self,
+        default: String,
+    ) -> Result<Vec<String>, WrongType> {
+        Ok(fill_nones(Vec::<Option<String>>::try_from(self)?, default))
+    }
+
+    /// Splits this `Column` into a contiguous `Vec` of its values and a
+    /// validity mask that is `true` for every present cell, such as for
+    /// handing the column to C code. Missing cells are `false` in the mask
+    /// and `0` in the values. A `Column::Int32` is widened to `i64`s.
+    ///
+    /// Returns an error if this is not an `Int` column.
+    pub fn to_split_repr_int(
+        &self,
+    ) -> Result<(Vec<i64>, Vec<bool>), WrongType> {
+        match self {
+            Column::Int(c) => Ok(split_repr(c.iter().copied())),
+            Column::Int32(c) => {
+                Ok(split_repr(c.iter().map(|v| v.map(i64::from))))
             }
+            c => Err(WrongType::new(c, "int")),
         }
     }
 
-    parsed_data
-}
+    /// The same as [`to_split_repr_int`](Column::to_split_repr_int), but for
+    /// `Float` columns. Missing cells are `0.0` in the values.
+    ///
+    /// Returns an error if this is not a `Float` column.
+    pub fn to_split_repr_float(
+        &self,
+    ) -> Result<(Vec<f64>, Vec<bool>), WrongType> {
+        match self {
+            Column::Float(c) => Ok(split_repr(c.iter().copied())),
+            c => Err(WrongType::new(c, "float")),
+        }
+    }
 
-/// Get the (i,j) element from the DataFrame
-pub fn get(d: &[Column], col_idx: usize, row_idx: usize) -> Data {
-    match &d[col_idx] {
-        Column::Bool(b) => {
-            if let Some(val) = &b[row_idx] {
-                Data::Bool(*val)
-            } else {
-                Data::Null
-            }
+    /// The same as [`to_split_repr_int`](Column::to_split_repr_int), but for
+    /// `Bool` columns. Missing cells are `false` in the values.
+    ///
+    /// Returns an error if this is not a `Bool` column.
+    pub fn to_split_repr_bool(
+        &self,
+    ) -> Result<(Vec<bool>, Vec<bool>), WrongType> {
+        match self {
+            Column::Bool(c) => Ok(split_repr(c.iter().copied())),
+            Column::PackedBool(c) => Ok(split_repr(c.iter())),
+            c => Err(WrongType::new(c, "bool")),
         }
-        Column::Int(b) => {
-            if let Some(val) = &b[row_idx] {
-                Data::Int(*val)
-            } else {
-                Data::Null
+    }
+
+    /// Appends every cell of `other` to this `Column`, leaving `other` empty.
+    /// A `Column::Int32` is widened if either column is a `Column::Int`.
+    ///
+    /// # Panics
+    /// If the columns are of different `DataType`s.
+    pub fn append(&mut self, other: &mut Column) {
+        if let (Column::Int(_), Column::Int32(_)) = (&*self, &*other) {
+            other.widen();
+        } else if let (Column::Int32(_), Column::Int(_)) = (&*self, &*other) {
+            self.widen();
+        }
+        match (self, other) {
+            (Column::Bool(c1), Column::Bool(c2)) => c1.append(c2),
+            (Column::PackedBool(c1), Column::PackedBool(c2)) => {
+                c2.iter().for_each(|b| c1.push(b));
+                *c2 = PackedBools::new();
+            }
+            (Column::PackedBool(c1), Column::Bool(c2)) => {
+                c2.drain(..).for_each(|b| c1.push(b));
             }
+            (Column::Bool(c1), Column::PackedBool(c2)) => {
+                c1.extend(c2.iter());
+                *c2 = PackedBools::new();
+            }
+            (Column::Int(c1), Column::Int(c2)) => c1.append(c2),
+            (Column::Int32(c1), Column::Int32(c2)) => c1.append(c2),
+            (Column::Float(c1), Column::Float(c2)) => c1.append(c2),
+            (Column::String(c1), Column::String(c2)) => c1.append(c2),
+            _ => panic!("Can not append columns of different types"),
         }
-        Column::Float(b) => {
-            if let Some(val) = &b[row_idx] {
-                Data::Float(*val)
-            } else {
-                Data::Null
+    }
+
+    /// Returns a `Column` of the same type with the distinct cells of this
+    /// `Column`, in the order they were first seen. All missing cells count
+    /// as a single distinct cell, as do all `NaN`s, and `0.0` and `-0.0` are
+    /// the same cell.
+    pub fn unique(&self) -> Column {
+        match self {
+            Column::Bool(c) => Column::Bool(unique_by_key(c, |b| *b)),
+            Column::PackedBool(c) => {
+                Column::PackedBool(unique_by_key(&c.to_vec(), |b| *b).into())
+            }
+            Column::Int(c) => Column::Int(unique_by_key(c, |n| *n)),
+            Column::Int32(c) => Column::Int32(unique_by_key(c, |n| *n)),
+            Column::Float(c) => Column::Float(unique_by_key(c, float_key)),
+            Column::String(c) => {
+                Column::String(unique_by_key(c, |s| s.clone()))
             }
         }
-        Column::String(b) => {
-            if let Some(val) = &b[row_idx] {
-                Data::String(val.clone())
-            } else {
-                Data::Null
+    }
+
+    /// Returns a `Column::String` with every present cell of this `Column`
+    /// formatted the same way as the `Display` impl of `Data`, except that
+    /// `String` cells are copied without surrounding quotes. Missing cells
+    /// stay missing.
+    pub fn to_string_column(&self) -> Column {
+        match self {
+            Column::Bool(c) => Column::String(
+                c.iter()
+                    .map(|b| b.map(|b| if b { "1" } else { "0" }.to_string()))
+                    .collect(),
+            ),
+            Column::PackedBool(c) => {
+                Column::Bool(c.to_vec()).to_string_column()
             }
+            Column::Int(c) => stringify(c),
+            Column::Int32(c) => stringify(c),
+            Column::Float(c) => stringify(c),
+            Column::String(c) => Column::String(c.clone()),
         }
     }
-}
 
-/// A helper function to help with multi-threading in the top level `from_file`
-/// function. Does the heavy lifting of actually calling
-/// [parser functions](::crate::parsers). Parsers a chunk of the given `reader`
-/// up to `len` bytes starting at the `from` byte offset.
-fn read_chunk<T>(
-    schema: Vec<DataType>,
-    reader: &mut T,
-    from: usize,
-    len: usize,
-) -> Vec<Column>
-where
-    T: BufRead + Seek,
-{
-    reader.seek(SeekFrom::Start(from as u64)).unwrap();
-    let mut buffer = Vec::new();
+    /// Returns the number of bytes used by this `Column`, including the heap
+    /// allocations of its cells, as reported by [`DeepSizeOf`].
+    pub fn memory_usage(&self) -> usize {
+        self.deep_size_of()
+    }
 
-    let mut so_far = if from != 0 {
-        // throw away the first line
-        let l1_len = reader.read_until(b'\n', &mut buffer).unwrap();
-        buffer.clear();
-        l1_len
-    } else {
-        0
-    };
+    /// Creates a [`ColumnReader`] for reading many cells of this `Column`.
+    pub fn reader(&self) -> ColumnReader<'_> {
+        ColumnReader {
+            cells: match self {
+                Column::Bool(c) => Cells::Bool(c),
+                Column::PackedBool(c) => Cells::PackedBool(c),
+                Column::Int(c) => Cells::Int(c),
+                Column::Int32(c) => Cells::Int32(c),
+                Column::Float(c) => Cells::Float(c),
+                Column::String(c) => Cells::String(c),
+            },
+        }
+    }
 
-    let mut parsed_data = init_columnar(&schema);
+    /// Returns the indices of the rows whose cell, as a `Data`, satisfies
+    /// `pred`. Missing cells are passed to `pred` as `Data::Null`.
+    ///
+    /// A mask for [`apply_mask`] can be built from the indices.
+    pub fn iter_indices_where<'a, F>(
+        &'a self,
+        pred: F,
+    ) -> impl Iterator<Item = usize> + 'a
+    where
+        F: Fn(&Data) -> bool + 'a,
+    {
+        let reader = self.reader();
+        (0..reader.len()).filter(move |&i| pred(&reader.get(i)))
+    }
 
-    loop {
-        let line_len = reader.read_until(b'\n', &mut buffer).unwrap();
-        so_far += line_len;
-        if line_len == 0 || so_far >= len {
-            break;
-        }
+    /// Returns the rows of this `Column` in groups of `n` rows, as new
+    /// `Column`s of the same type. The last group has fewer than `n` rows if
+    /// `n` does not divide the length of the column.
+    ///
+    /// # Panics
+    /// Panics if `n` is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use sorer::dataframe::Column;
+    ///
+    /// let col = Column::Int(vec![Some(1), None, Some(3)]);
+    /// let chunks: Vec<Column> = col.chunks(2).collect();
+    /// assert_eq!(
+    ///     chunks,
+    ///     vec![Column::Int(vec![Some(1), None]), Column::Int(vec![Some(3)])]
+    /// );
+    /// ```
+    pub fn chunks(&self, n: usize) -> impl Iterator<Item = Column> + '_ {
+        assert!(n != 0, "chunk size must be non-zero");
+        (0..self.len())
+            .step_by(n)
+            .map(move |from| self.slice(from, from + n))
+    }
 
-        // parse line with schema and place into the columnar vec here
-        match parse_line_with_schema(&buffer[..], &schema) {
-            None => {
-                buffer.clear();
-                continue;
-            }
-            Some(data) => {
-                let iter = data.iter().zip(parsed_data.iter_mut());
-                for (d, col) in iter {
-                    match (d, col) {
-                        (Data::Bool(b), Column::Bool(c)) => c.push(Some(*b)),
-                        (Data::Int(i), Column::Int(c)) => c.push(Some(*i)),
-                        (Data::Float(f), Column::Float(c)) => c.push(Some(*f)),
-                        (Data::String(s), Column::String(c)) => {
-                            c.push(Some(s.clone()))
-                        }
-                        (Data::Null, Column::Bool(c)) => c.push(None),
-                        (Data::Null, Column::Int(c)) => c.push(None),
-                        (Data::Null, Column::Float(c)) => c.push(None),
-                        (Data::Null, Column::String(c)) => c.push(None),
-                        _ => panic!("Parser Failed"),
-                    }
-                }
+    // Copies the rows from index `from` (inclusive) up to index `to`
+    // (exclusive), with both indices clamped to the length of the column
+    fn slice(&self, from: usize, to: usize) -> Column {
+        let to = to.min(self.len());
+        let from = from.min(to);
+        match self {
+            Column::Bool(c) => Column::Bool(c[from..to].to_vec()),
+            Column::PackedBool(c) => {
+                Column::PackedBool((from..to).map(|i| c.get(i)).collect())
             }
+            Column::Int(c) => Column::Int(c[from..to].to_vec()),
+            Column::Int32(c) => Column::Int32(c[from..to].to_vec()),
+            Column::Float(c) => Column::Float(c[from..to].to_vec()),
+            Column::String(c) => Column::String(c[from..to].to_vec()),
         }
-        buffer.clear();
     }
-    parsed_data
-}
 
-/// Used for chunking `SoR` files.
-pub struct SorTerator {
-    buf_reader: Split<BufReader<File>>,
-    chunk_size: usize,
-    schema: Vec<DataType>,
-    empty_col: Column,
-}
+    // Converts a `Column::Int32` into a `Column::Int`, does nothing for any
+    // other column
+    fn widen(&mut self) {
+        if let Column::Int32(c) = self {
+            *self = Column::Int(c.iter().map(|v| v.map(i64::from)).collect());
+        }
+    }
 
-/// A chunking iterator that can chunk `SoR` files into `Vec<Column>`s where
-/// all columns have the same length (number of rows) based an argument in
-/// the constructor. The last element returned by `next` may have less than
-/// `chunk_size` number of rows and it is up to the caller to verify the
-/// length if needed.
-impl SorTerator {
-    /// Creates a new [`SorTerator`](::crate::dataframe::SorTerator)
-    pub fn new(
-        file_name: &str,
-        schema: Vec<DataType>,
-        chunk_size: usize,
-    ) -> Self {
-        SorTerator {
-            buf_reader: BufReader::new(File::open(file_name).unwrap())
-                .split(b'\n'),
-            empty_col: Column::Bool(Vec::new()),
-            chunk_size,
-            schema,
+    // Creates the error for pushing `found` to this column
+    fn mismatch(&self, found: Data) -> TypeMismatch {
+        TypeMismatch {
+            expected: self.data_type(),
+            found,
         }
     }
 }
 
-/// Implementation for an `Iterator` that chunks a `SoR` file
-impl Iterator for SorTerator {
-    type Item = Vec<Column>;
+/// Random access to the cells of a single [`Column`], created with
+/// [`Column::reader`]. Unlike the top level [`get`](crate::dataframe::get)
+/// function, the column is looked up once when the reader is created rather
+/// than on every call to [`get`](ColumnReader::get), and the check of its
+/// variant is simple enough to be hoisted out of a loop over the rows.
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnReader<'a> {
+    cells: Cells<'a>,
+}
 
-    /// Advances this iterator until `self.chunk_size` rows have been parsed,
-    /// returning `Some(Vec<Column>)` of the parsed rows when done, or `None`
-    /// or the file has been completely parsed. The last element returned by
-    /// `next` may have less than `chunk_size` number of rows and it is up to
-    /// the caller to verify the length if needed.
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut parsed_data = init_columnar(&self.schema);
-        while let Some(Ok(line)) = self.buf_reader.next() {
-            match parse_line_with_schema(&line, &self.schema) {
-                None => continue,
-                Some(data) => {
-                    let iter = data.iter().zip(parsed_data.iter_mut());
-                    for (d, col) in iter {
-                        match (d, col) {
-                            (Data::Bool(b), Column::Bool(c)) => {
-                                c.push(Some(*b))
-                            }
-                            (Data::Int(i), Column::Int(c)) => c.push(Some(*i)),
-                            (Data::Float(f), Column::Float(c)) => {
-                                c.push(Some(*f))
-                            }
-                            (Data::String(s), Column::String(c)) => {
-                                c.push(Some(s.clone()))
-                            }
-                            (Data::Null, Column::Bool(c)) => c.push(None),
-                            (Data::Null, Column::Int(c)) => c.push(None),
-                            (Data::Null, Column::Float(c)) => c.push(None),
-                            (Data::Null, Column::String(c)) => c.push(None),
-                            _ => panic!("Parser Failed"),
-                        }
-                    }
-                }
+// The cells of a column, borrowed from it
+#[derive(Clone, Copy, Debug)]
+enum Cells<'a> {
+    Bool(&'a [Option<bool>]),
+    PackedBool(&'a PackedBools),
+    Int(&'a [Option<i64>]),
+    Int32(&'a [Option<i32>]),
+    Float(&'a [Option<f64>]),
+    String(&'a [Option<String>]),
+}
+
+impl<'a> ColumnReader<'a> {
+    /// Returns the `Data` at `row_idx`, or `Data::Null` if it is missing.
+    ///
+    /// # Panics
+    /// If `row_idx` is out of bounds.
+    #[inline]
+    pub fn get(&self, row_idx: usize) -> Data {
+        match self.cells {
+            Cells::Bool(c) => c[row_idx].map_or(Data::Null, Data::Bool),
+            Cells::PackedBool(c) => {
+                c.get(row_idx).map_or(Data::Null, Data::Bool)
             }
-            if let Some(column) = parsed_data.get(0) {
-                if column.len() == self.chunk_size {
-                    return Some(parsed_data);
-                }
+            Cells::Int(c) => c[row_idx].map_or(Data::Null, Data::Int),
+            Cells::Int32(c) => {
+                c[row_idx].map_or(Data::Null, |n| Data::Int(i64::from(n)))
             }
+            Cells::Float(c) => c[row_idx].map_or(Data::Null, Data::Float),
+            Cells::String(c) => c[row_idx]
+                .as_ref()
+                .map_or(Data::Null, |s| Data::String(s.clone())),
         }
-        if parsed_data.get(0).unwrap_or(&self.empty_col).len() > 0 {
-            Some(parsed_data)
-        } else {
+    }
+
+    /// Returns the number of rows in the column being read.
+    pub fn len(&self) -> usize {
+        match self.cells {
+            Cells::Bool(c) => c.len(),
+            Cells::PackedBool(c) => c.len(),
+            Cells::Int(c) => c.len(),
+            Cells::Int32(c) => c.len(),
+            Cells::Float(c) => c.len(),
+            Cells::String(c) => c.len(),
+        }
+    }
+
+    /// Returns `true` if the column being read has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Optional `bool`s packed into two bitsets, one for whether each cell is
+/// present and one for the values of the present cells.
+///
+/// # Examples
+/// ```
+/// use sorer::dataframe::PackedBools;
+///
+/// let cells = vec![Some(true), None, Some(false)];
+/// let packed: PackedBools = cells.iter().copied().collect();
+/// assert_eq!(packed.len(), 3);
+/// assert_eq!(packed.get(1), None);
+/// assert_eq!(packed.to_vec(), cells);
+/// ```
+#[derive(
+    PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize, DeepSizeOf,
+)]
+pub struct PackedBools {
+    // bits past `len` are always unset, as are the values of missing cells,
+    // so that equal cells always have equal bitsets
+    validity: Vec<u64>,
+    values: Vec<u64>,
+    len: usize,
+}
+
+impl PackedBools {
+    /// Creates an empty `PackedBools`.
+    pub fn new() -> Self {
+        PackedBools::default()
+    }
+
+    /// Returns the number of cells.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no cells.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the cell at `idx`, or `None` if it is missing.
+    ///
+    /// # Panics
+    /// If `idx` is out of bounds.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<bool> {
+        assert!(
+            idx < self.len,
+            "Index {} is out of bounds for {} cells",
+            idx,
+            self.len
+        );
+        let (word, bit) = (idx / 64, 1 << (idx % 64));
+        if self.validity[word] & bit == 0 {
             None
+        } else {
+            Some(self.values[word] & bit != 0)
+        }
+    }
+
+    /// Appends `cell`.
+    pub fn push(&mut self, cell: Option<bool>) {
+        let (word, bit) = (self.len / 64, 1 << (self.len % 64));
+        if word == self.validity.len() {
+            self.validity.push(0);
+            self.values.push(0);
+        }
+        if let Some(b) = cell {
+            self.validity[word] |= bit;
+            if b {
+                self.values[word] |= bit;
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Shortens this to its first `len` cells. Has no effect if it has `len`
+    /// or fewer cells.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let words = len.div_ceil(64);
+        self.validity.truncate(words);
+        self.values.truncate(words);
+        let bits_in_last_word = len % 64;
+        if bits_in_last_word > 0 {
+            let mask = (1 << bits_in_last_word) - 1;
+            self.validity[words - 1] &= mask;
+            self.values[words - 1] &= mask;
         }
+        self.len = len;
+    }
+
+    /// Appends `n` missing cells.
+    pub fn extend_nulls(&mut self, n: usize) {
+        self.len += n;
+        let words = self.len.div_ceil(64);
+        self.validity.resize(words, 0);
+        self.values.resize(words, 0);
+    }
+
+    /// Returns an iterator over the cells.
+    pub fn iter(&self) -> impl Iterator<Item = Option<bool>> + '_ {
+        (0..self.len).map(move |idx| self.get(idx))
+    }
+
+    /// Returns the cells unpacked into a `Vec`, as stored by a
+    /// `Column::Bool`.
+    pub fn to_vec(&self) -> Vec<Option<bool>> {
+        self.iter().collect()
     }
 }
 
-impl From<Vec<Option<bool>>> for Column {
-    fn from(v: Vec<Option<bool>>) -> Column {
-        Column::Bool(v)
+impl FromIterator<Option<bool>> for PackedBools {
+    fn from_iter<I: IntoIterator<Item = Option<bool>>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let words = iter.size_hint().0.div_ceil(64);
+        let mut packed = PackedBools {
+            validity: Vec::with_capacity(words),
+            values: Vec::with_capacity(words),
+            len: 0,
+        };
+        for cell in iter {
+            packed.push(cell);
+        }
+        packed
     }
 }
 
-impl From<Vec<Option<i64>>> for Column {
-    fn from(v: Vec<Option<i64>>) -> Column {
-        Column::Int(v)
+impl From<&[Option<bool>]> for PackedBools {
+    fn from(cells: &[Option<bool>]) -> Self {
+        cells.iter().copied().collect()
     }
 }
 
-impl From<Vec<Option<f64>>> for Column {
-    fn from(v: Vec<Option<f64>>) -> Column {
-        Column::Float(v)
+impl From<Vec<Option<bool>>> for PackedBools {
+    fn from(cells: Vec<Option<bool>>) -> Self {
+        cells.into_iter().collect()
     }
 }
 
-impl From<Vec<Option<String>>> for Column {
-    fn from(v: Vec<Option<String>>) -> Column {
-        Column::String(v)
+// A hashable key for a float where all `NaN`s are equal and `0.0` equals
+// `-0.0`
+fn float_key(f: &f64) -> u64 {
+    if f.is_nan() {
+        f64::NAN.to_bits()
+    } else if *f == 0.0 {
+        0.0f64.to_bits()
+    } else {
+        f.to_bits()
     }
 }
 
-impl TryFrom<Column> for Vec<Option<bool>> {
-    type Error = &'static str;
+// A hashable version of a `Data`, with floats compared by `float_key`
+#[derive(PartialEq, Eq, Hash)]
+enum DataKey {
+    Bool(bool),
+    Int(i64),
+    Float(u64),
+    String(String),
+    Null,
+}
 
-    fn try_from(c: Column) -> Result<Self, Self::Error> {
-        match c {
-            Column::Bool(col) => Ok(col),
-            _ => Err("The given column was not of type bool"),
+impl From<Data> for DataKey {
+    fn from(data: Data) -> Self {
+        match data {
+            Data::Bool(b) => DataKey::Bool(b),
+            Data::Int(n) => DataKey::Int(n),
+            Data::Float(f) => DataKey::Float(float_key(&f)),
+            Data::String(s) => DataKey::String(s),
+            Data::Null => DataKey::Null,
         }
     }
 }
 
-impl TryFrom<Column> for Vec<Option<i64>> {
-    type Error = &'static str;
+// Keeps the first cell of `col` for every distinct `key` of its values, and
+// the first `None`
+fn unique_by_key<T, K, F>(col: &[Option<T>], key: F) -> Vec<Option<T>>
+where
+    T: Clone,
+    K: Eq + Hash,
+    F: Fn(&T) -> K,
+{
+    let mut seen = HashSet::new();
+    col.iter()
+        .filter(|v| seen.insert(v.as_ref().map(&key)))
+        .cloned()
+        .collect()
+}
 
-    fn try_from(c: Column) -> Result<Self, Self::Error> {
-        match c {
-            Column::Int(col) => Ok(col),
-            _ => Err("The given column was not of type int"),
-        }
+// Splits `cells` into their values, with missing cells as `T::default()`, and
+// whether each cell is present
+fn split_repr<T: Default>(
+    cells: impl Iterator<Item = Option<T>>,
+) -> (Vec<T>, Vec<bool>) {
+    let (len, _) = cells.size_hint();
+    let mut values = Vec::with_capacity(len);
+    let mut validity = Vec::with_capacity(len);
+    for cell in cells {
+        validity.push(cell.is_some());
+        values.push(cell.unwrap_or_default());
     }
+    (values, validity)
 }
 
-impl TryFrom<Column> for Vec<Option<f64>> {
-    type Error = &'static str;
+// Unwraps every value of `col`, using `default` for the `None`s
+fn fill_nones<T: Clone>(col: Vec<Option<T>>, default: T) -> Vec<T> {
+    col.into_iter()
+        .map(|v| v.unwrap_or_else(|| default.clone()))
+        .collect()
+}
 
-    fn try_from(c: Column) -> Result<Self, Self::Error> {
-        match c {
-            Column::Float(col) => Ok(col),
-            _ => Err("The given column was not of type float"),
+// Checks that the `Some` values of `col` are in non-decreasing order
+fn is_sorted_ignoring_nulls<T: PartialOrd>(col: &[Option<T>]) -> bool {
+    let mut present = col.iter().flatten();
+    let mut prev = match present.next() {
+        Some(v) => v,
+        None => return true,
+    };
+    for cur in present {
+        if prev > cur {
+            return false;
         }
+        prev = cur;
     }
+    true
 }
 
-impl TryFrom<Column> for Vec<Option<String>> {
-    type Error = &'static str;
+/// An enumeration of the possible `SoR` data types, that also contains the
+/// data itself.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize, DeepSizeOf)]
+pub enum Data {
+    /// A `String` cell.
+    String(String),
+    /// A `i64` cell.
+    Int(i64),
+    /// A `f64` cell.
+    Float(f64),
+    /// A `bool` cell.
+    Bool(bool),
+    /// A missing value.
+    Null,
+}
 
-    fn try_from(c: Column) -> Result<Self, Self::Error> {
-        match c {
-            Column::String(col) => Ok(col),
-            _ => Err("The given column was not of type String"),
+impl Data {
+    /// Shorthand for `Data::String(s.to_string())`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sorer::dataframe::Data;
+    ///
+    /// assert_eq!(Data::string("hi"), Data::String("hi".to_string()));
+    /// assert_eq!(Data::int(-3), Data::Int(-3));
+    /// assert_eq!(Data::float(1.5), Data::Float(1.5));
+    /// assert_eq!(Data::boolean(true), Data::Bool(true));
+    /// ```
+    pub fn string(s: &str) -> Data {
+        Data::String(s.to_string())
+    }
+
+    /// Shorthand for `Data::Int(n)`.
+    pub fn int(n: i64) -> Data {
+        Data::Int(n)
+    }
+
+    /// Shorthand for `Data::Float(f)`.
+    pub fn float(f: f64) -> Data {
+        Data::Float(f)
+    }
+
+    /// Shorthand for `Data::Bool(b)`.
+    pub fn boolean(b: bool) -> Data {
+        Data::Bool(b)
+    }
+
+    /// Get the data assuming its a String
+    pub fn unwrap_string(&self) -> String {
+        match self {
+            Data::String(s) => s.clone(),
+            _ => panic!("unwrap error"),
         }
     }
-}
 
-/// Print the `Data` of a `Data` cell.
-/// The number for `Int`s and `float`s.
-/// 0 for `false`.
-/// 1 for `true`.
-/// A double quote delimited `String`.
-/// and "Missing Value" for missing data.
-impl fmt::Display for Data {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    /// Get the data assuming its an int
+    pub fn unwrap_int(&self) -> i64 {
         match self {
-            Data::String(s) => write!(f, "\"{}\"", s),
-            Data::Int(n) => write!(f, "{}", n),
-            Data::Float(fl) => write!(f, "{}", fl),
-            Data::Bool(true) => write!(f, "1"),
-            Data::Bool(false) => write!(f, "0"),
-            Data::Null => write!(f, "Missing Value"),
+            Data::Int(n) => *n,
+            _ => panic!("unwrap error"),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Get the data assuming its a float
+    pub fn unwrap_float(&self) -> f64 {
+        match self {
+            Data::Float(n) => *n,
+            _ => panic!("unwrap error"),
+        }
+    }
 
-    use super::*;
-    use std::io::Cursor;
+    /// Get the data assuming its a bool
+    pub fn unwrap_bool(&self) -> bool {
+        match self {
+            Data::Bool(n) => *n,
+            _ => panic!("unwrap error"),
+        }
+    }
 
-    #[test]
-    fn test_read_file() {
-        let schema = vec![DataType::String, DataType::Bool];
+    /// Get the numeric value of an `Int` or a `Float` as a `f64`, so that
+    /// both can be compared against a number uniformly. Returns `None` for
+    /// all other variants.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Data::Int(n) => Some(*n as f64),
+            Data::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
 
-        let expected_col1 = Column::String(vec![
-            Some("1".to_string()),
-            Some("a".to_string()),
-            Some("1.2".to_string()),
-        ]);
-        let expected_col2 = Column::Bool(vec![Some(true), Some(false), None]);
-        let expected = vec![expected_col1, expected_col2];
+    /// Formats this `Data` the same way as its `Display` impl, except that
+    /// `Data::Bool`s are written in the given `bool_style`.
+    pub fn to_display_string(&self, bool_style: BoolStyle) -> String {
+        match (self, bool_style) {
+            (Data::Bool(b), BoolStyle::TrueFalse) => b.to_string(),
+            _ => self.to_string(),
+        }
+    }
 
-        // Simple case : first nd last line are not discarded
-        let mut input = Cursor::new(b"<1><1>\n<a><0>\n<1.2><>");
-        let parsed1: Vec<Column> =
-            read_chunk(schema.clone(), &mut input, 0, 26);
-        assert_eq!(parsed1, expected.clone());
+    /// Converts this value to one of the `target` type, following these rules:
+    /// - `Null` stays `Null`, whatever the `target`.
+    /// - A value that already is of the `target` type is unchanged.
+    /// - `Int` to `Float` is exact for ints within ±2^53, larger ints are
+    ///   rounded to the nearest `f64`.
+    /// - `Float` to `Int` truncates towards zero. Floats that are not finite
+    ///   or do not fit in an `i64` are an error.
+    /// - `Bool` to `Int` or `Float` is `1` for `true` and `0` for `false`,
+    ///   and `Int` to `Bool` is `true` for `1` and `false` for `0`. Any other
+    ///   `Int`, and any `Float`, is an error.
+    /// - `String` to `Int`, `Float` or `Bool` parses the string, after
+    ///   trimming whitespace, the same way as the value it is converted to is
+    ///   written in `SoR`. A string that doesn't parse is an error, and so
+    ///   is one that parses to a `Float` that is not finite, such as `"NaN"`.
+    /// - Anything to `String` is the value as it is written in `SoR`, so
+    ///   `Bool`s become `"1"` or `"0"`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sorer::dataframe::Data;
+    /// use sorer::schema::DataType;
+    ///
+    /// let five = Data::Int(5).coerce_to(&DataType::Float);
+    /// assert_eq!(five, Ok(Data::Float(5.0)));
+    /// let neg_two = Data::Float(-2.7).coerce_to(&DataType::Int);
+    /// assert_eq!(neg_two, Ok(Data::Int(-2)));
+    /// assert!(Data::string("five").coerce_to(&DataType::Int).is_err());
+    /// ```
+    pub fn coerce_to(&self, target: &DataType) -> Result<Data, CoerceError> {
+        let coerced = match (self, target) {
+            (Data::Null, _) => Some(Data::Null),
+            (Data::String(s), DataType::String) => {
+                Some(Data::String(s.clone()))
+            }
+            (_, DataType::String) => Some(Data::String(self.to_string())),
+            (Data::Int(n), DataType::Int) => Some(Data::Int(*n)),
+            (Data::Int(n), DataType::Float) => Some(Data::Float(*n as f64)),
+            (Data::Int(0), DataType::Bool) => Some(Data::Bool(false)),
+            (Data::Int(1), DataType::Bool) => Some(Data::Bool(true)),
+            (Data::Int(_), DataType::Bool) => None,
+            (Data::Float(f), DataType::Float) => Some(Data::Float(*f)),
+            // `i64::MAX as f64` is 2^63, which is just past the largest i64
+            (Data::Float(f), DataType::Int)
+                if f.is_finite()
+                    && *f >= i64::MIN as f64
+                    && *f < i64::MAX as f64 =>
+            {
+                Some(Data::Int(f.trunc() as i64))
+            }
+            (Data::Float(_), _) => None,
+            (Data::Bool(b), DataType::Bool) => Some(Data::Bool(*b)),
+            (Data::Bool(b), DataType::Int) => Some(Data::Int(*b as i64)),
+            (Data::Bool(b), DataType::Float) => {
+                Some(Data::Float(*b as i64 as f64))
+            }
+            (Data::String(s), DataType::Int) => {
+                s.trim().parse().ok().map(Data::Int)
+            }
+            // `parse` also accepts `NaN`, `inf` and `infinity`, and rounds
+            // huge numbers to infinity, so only finite floats are kept
+            (Data::String(s), DataType::Float) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(Data::Float),
+            (Data::String(s), DataType::Bool) => match s.trim() {
+                "0" => Some(Data::Bool(false)),
+                "1" => Some(Data::Bool(true)),
+                _ => None,
+            },
+        };
+        coerced.ok_or_else(|| CoerceError {
+            target: target.clone(),
+            value: self.clone(),
+        })
+    }
+}
 
-        // last line is discarded
-        let mut larger_input = Cursor::new(b"<1><1>\n<a><0>\n<1.2><>\n<no><1>");
-        let parsed2: Vec<Column> =
-            read_chunk(schema.clone(), &mut larger_input, 0, 27);
-        assert_eq!(parsed2, expected.clone());
+// Formats every present cell of `cells` with `Display`
+fn stringify<T: ToString>(cells: &[Option<T>]) -> Column {
+    Column::String(cells.iter().map(|v| v.as_ref().map(T::to_string)).collect())
+}
 
-        // first line is discarded
-        let mut input_skipped_l1 =
-            Cursor::new(b"<b><1>\n<1><1>\n<a><0>\n<1.2><>");
-        let parsed3: Vec<Column> =
-            read_chunk(schema.clone(), &mut input_skipped_l1, 3, 26);
-        assert_eq!(parsed3, expected.clone());
+/// Generate a `Vec<Column>` matching the given schema, or only its projected
+/// columns if `config` has a projection.
+pub(crate) fn init_columnar(
+    schema: &[DataType],
+    config: &ParserConfig,
+) -> Vec<Column> {
+    check_projection(schema, config);
+    let types: Vec<&DataType> = match &config.projection {
+        Some(projection) => projection.iter().map(|&i| &schema[i]).collect(),
+        None => schema.iter().collect(),
+    };
+    let mut result = Vec::with_capacity(types.len() + 1);
+    for t in types {
+        match t {
+            DataType::Bool if config.pack_bools => {
+                result.push(Column::PackedBool(PackedBools::new()))
+            }
+            DataType::Bool => result.push(Column::Bool(Vec::new())),
+            DataType::Int if config.narrow_ints => {
+                result.push(Column::Int32(Vec::new()))
+            }
+            DataType::Int => result.push(Column::Int(Vec::new())),
+            DataType::Float => result.push(Column::Float(Vec::new())),
+            DataType::String => result.push(Column::String(Vec::new())),
+        }
+    }
+    result
+}
 
-        // Invalid line is discarded
-        // Note since parsed lines with schema is correctly tested we do not
-        // need to test every possible way a line can be invalid here
-        let mut input_with_invalid =
-            Cursor::new(b"<1><1>\n<a><0>\n<c><1.2>\n<1.2><>");
-        let parsed4: Vec<Column> =
-            read_chunk(schema.clone(), &mut input_with_invalid, 0, 32);
-        assert_eq!(parsed4, expected.clone());
+/// Panics if the projection of `config` has an index that is not a column of
+/// `schema`. Readers check this before spawning any threads, so that the
+/// panic happens on the calling thread.
+pub(crate) fn check_projection(schema: &[DataType], config: &ParserConfig) {
+    if let Some(projection) = &config.projection {
+        if let Some(idx) = projection.iter().find(|&&idx| idx >= schema.len()) {
+            panic!(
+                "projected column {} is out of bounds for a schema of {} \
+                 columns",
+                idx,
+                schema.len()
+            );
+        }
     }
+}
 
-    #[test]
-    fn test_sor_terator() {
-        let schema = vec![
-            DataType::Bool,
-            DataType::Int,
-            DataType::Float,
-            DataType::String,
-        ];
-        let mut sor_terator =
-            SorTerator::new("tests/sor_terator.sor", schema, 10);
-        let mut chunk = sor_terator.next();
-        assert_eq!(chunk.unwrap().get(0).unwrap().len(), 10);
-        chunk = sor_terator.next();
-        assert_eq!(chunk.unwrap().get(0).unwrap().len(), 5);
-        chunk = sor_terator.next();
+// TODO: use crossbeam for scoped thread spawning and change from_file to
+// take `schema: &[DataType]`
+
+/// Reads `len` number of bytes from a given file starting at the `from` byte
+/// offset an according to the given `schema`.
+///
+/// This is the top level function for using `SoRer` and the one you should be
+///  using unless you are trying to extend `SoRer`. There are many intricate
+/// facets to using `SoRer` so you *must* RTFM [here](../index.html)
+pub fn from_file(
+    file_path: &str,
+    schema: Vec<DataType>,
+    from: usize,
+    len: usize,
+    num_threads: usize,
+) -> Vec<Column> {
+    from_file_with_config(
+        file_path,
+        schema,
+        from,
+        len,
+        num_threads,
+        &ParserConfig::default(),
+    )
+}
+
+/// The same as [`from_file`](crate::dataframe::from_file), but reads the file
+/// according to the given [`ParserConfig`](crate::parsers::ParserConfig).
+///
+/// Note that the work for each thread is split at newlines, so when
+/// `config.multi_line_strings` is set a quoted string that spans a thread
+/// boundary is still split. Use a [`SorTerator`] if that matters.
+///
+/// # Panics
+/// Panics if `config.projection` has an index that is out of bounds for
+/// `schema`.
+pub fn from_file_with_config(
+    file_path: &str,
+    schema: Vec<DataType>,
+    from: usize,
+    len: usize,
+    num_threads: usize,
+    config: &ParserConfig,
+) -> Vec<Column> {
+    let work = split_work(file_path, from, len, num_threads);
+    parse_work(file_path, schema, work, config, usize::MAX, |_, _| ())
+}
+
+/// The number of bytes each thread parses between calls to the `progress`
+/// callback of [`from_file_with_progress`].
+pub const PROGRESS_STEP: usize = 1 << 20;
+
+/// The same as [`from_file_with_config`], but calls `progress` with the
+/// number of bytes parsed so far and the total number of bytes to parse as
+/// the file is parsed, such as to show a progress bar. `progress` is called
+/// on the calling thread each time a thread has parsed another
+/// [`PROGRESS_STEP`] bytes, and when a thread finishes, so the last call is
+/// always with both numbers equal.
+///
+/// # Panics
+/// Panics if `config.projection` has an index that is out of bounds for
+/// `schema`.
+pub fn from_file_with_progress<F>(
+    file_path: &str,
+    schema: Vec<DataType>,
+    from: usize,
+    len: usize,
+    num_threads: usize,
+    config: &ParserConfig,
+    progress: F,
+) -> Vec<Column>
+where
+    F: FnMut(usize, usize),
+{
+    let work = split_work(file_path, from, len, num_threads);
+    parse_work(file_path, schema, work, config, PROGRESS_STEP, progress)
+}
+
+// Parses the file at `file_path` with a thread for each element of `work`,
+// as split by `split_work`, with each thread reporting its progress every
+// `step` bytes
+fn parse_work<F>(
+    file_path: &str,
+    schema: Vec<DataType>,
+    work: Vec<(usize, usize)>,
+    config: &ParserConfig,
+    step: usize,
+    mut progress: F,
+) -> Vec<Column>
+where
+    F: FnMut(usize, usize),
+{
+    check_projection(&schema, config);
+    let total = work.iter().map(|w| w.1).sum();
+
+    // initialize the threads with their own BufReader
+    let (sender, receiver) = channel();
+    let mut threads = Vec::new();
+    for w in work {
+        let new_schema = schema.clone();
+        let new_config = config.clone();
+        let sender = sender.clone();
+        let f: File = File::open(file_path).unwrap();
+        let mut r = BufReader::new(f);
+        // spawn the thread and give it a closure which calls `from_file`
+        // to parse the data into columnar format.
+        threads.push(thread::spawn(move || {
+            let mut unsent = 0;
+            let columns = read_chunk_with_progress(
+                new_schema,
+                &mut r,
+                w.0,
+                w.1,
+                &new_config,
+                &mut |bytes| {
+                    unsent += bytes;
+                    if unsent >= step {
+                        // the receiver is only dropped after a panic
+                        let _ = sender.send(unsent);
+                        unsent = 0;
+                    }
+                },
+            )
+            .unwrap();
+            if unsent > 0 {
+                let _ = sender.send(unsent);
+            }
+            columns
+        }));
+    }
+
+    // the receiver is exhausted once every thread has dropped its sender
+    drop(sender);
+    let mut done = 0;
+    for bytes in receiver {
+        done += bytes;
+        progress(done.min(total), total);
+    }
+
+    // initialize the resulting columnar data frame
+    let mut parsed_data: Vec<Column> = init_columnar(&schema, config);
+    // let all the threads finish then combine the parsed data into the
+    // columnar data frame
+    for t in threads {
+        let mut x: Vec<Column> = t.join().unwrap();
+        let iter = parsed_data.iter_mut().zip(x.iter_mut());
+        for (complete, partial) in iter {
+            complete.append(partial);
+        }
+    }
+
+    parsed_data
+}
+
+/// What [`from_file`] would do with the same arguments, as reported by
+/// [`dry_run`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct DryRunReport {
+    /// The (starting byte offset, number of bytes) read by each thread.
+    pub work: Vec<(usize, usize)>,
+    /// The schema inferred by [`infer_schema`](crate::schema::infer_schema).
+    pub schema: Vec<DataType>,
+    /// The number of lines that would be parsed, counted like in
+    /// [`count_rows`]. Blank and invalid lines are included, so this is an
+    /// upper bound on the number of rows.
+    pub estimated_rows: usize,
+}
+
+/// Reports the work split between threads, the inferred schema and an
+/// estimate of the number of rows that [`from_file`] would parse with the
+/// same arguments, without parsing any rows. This is much cheaper than
+/// parsing the file, so it can be used to check the arguments first.
+pub fn dry_run(
+    file_path: &str,
+    from: usize,
+    len: usize,
+    num_threads: usize,
+) -> io::Result<DryRunReport> {
+    let schema = crate::schema::infer_schema(file_path)?;
+    let file_len = std::fs::metadata(file_path)?.len() as usize;
+    let work = split_work(file_path, from, len, num_threads);
+
+    let end = from.saturating_add(len).min(file_len);
+    let mut file = File::open(file_path)?;
+    file.seek(SeekFrom::Start(from as u64))?;
+    let reader = BufReader::new(file.take(end.saturating_sub(from) as u64));
+    let (newlines, last_byte) = count_newlines(reader)?;
+    let mut estimated_rows = newlines;
+    // a final line without a newline is still parsed
+    if end == file_len && last_byte != b'\n' {
+        estimated_rows += 1;
+    }
+    // the first line is thrown away when not starting at the beginning
+    if from != 0 {
+        estimated_rows = estimated_rows.saturating_sub(1);
+    }
+
+    Ok(DryRunReport {
+        work,
+        schema,
+        estimated_rows,
+    })
+}
+
+// Splits the `len` bytes starting at `from` in the file at `file_path` into
+// the (starting index, number of bytes) work of each of `num_threads`
+// threads, so that every line is read by exactly one thread
+fn split_work(
+    file_path: &str,
+    from: usize,
+    len: usize,
+    num_threads: usize,
+) -> Vec<(usize, usize)> {
+    // nothing past the end of the file can be read, so both `from` and the
+    // end of the work are clamped to the length of the file
+    let file_len = std::fs::metadata(file_path).unwrap().len() as usize;
+    let from = from.min(file_len);
+    // no thread may read a line that ends past this offset
+    let end = from.saturating_add(len).min(file_len);
+    // the total number of bytes to read
+    let num_chars = end - from;
+    // each thread will parse this many characters +- some number
+    let step = num_chars.div_ceil(num_threads);
+
+    // setup the work array with the from / len for each thread
+    // each element in the work array is a tuple of (starting index, number of byte for this thread)
+    let f: File = File::open(file_path).unwrap();
+    let mut reader = BufReader::new(f);
+    let mut work: Vec<(usize, usize)> = Vec::with_capacity(num_threads + 1);
+
+    // add the first one separately since we want to access the previous thread's
+    // work when in the loop. Since the work of the first thread will call
+    // `read_file(schema, 0, step)` it will not throw away the first line
+    // since from is 0 and will throw away the last line since step > 0
+    work.push((from, step));
+
+    let mut so_far = from;
+    let mut buffer = Vec::new();
+
+    // This loop finds the byte offset for the start of a line
+    // by adding the length of the last line that a previous thread would've
+    // thrown away. The work gets added to the following thread so that
+    // each thread starts at a full line and reads only until the end of a line
+    for i in 1..num_threads {
+        so_far = (so_far + step).min(end);
+        // advance the reader to this threads starting index then
+        // find the next newline character
+        reader.seek(SeekFrom::Start(so_far as u64)).unwrap();
+        let (line_len, _) =
+            read_line_capped(&mut reader, &mut buffer, Some(0)).unwrap();
+        work.push((so_far, step.min(end - so_far)));
+
+        // Since this thread throws away its first line, add the length of
+        // that line to the work of the previous thread so that we read all
+        // lines. If that line ends past `end` neither thread reads it.
+        let previous = work.get_mut(i - 1).unwrap();
+        previous.1 = (so_far + line_len).min(end) - previous.0;
+        buffer.clear();
+    }
+    work
+}
+
+/// Parses the whole file at `file_path` according to `schema` like
+/// [`from_file`], but instead of waiting for every thread to finish and
+/// merging their columns, each thread sends the columns it parsed over the
+/// returned channel as soon as it is done. This lets the caller start
+/// processing parts of the file before all of it is parsed.
+///
+/// The parts arrive in the order the threads finish in, which is not
+/// necessarily the order of the rows in the file. Every thread sends exactly
+/// one part, which is an error if the thread could not read its part of the
+/// file, so the rows of the file are complete only if every part is `Ok`.
+/// The channel is closed once every thread is done.
+///
+/// # Panics
+/// Panics if the file can not be opened or its size can not be read, since
+/// the file is split into parts on the calling thread.
+pub fn from_file_streaming(
+    file_path: &str,
+    schema: Vec<DataType>,
+    num_threads: usize,
+) -> Receiver<io::Result<Vec<Column>>> {
+    let (sender, receiver) = channel();
+    let config = ParserConfig::default();
+    for w in split_work(file_path, 0, usize::MAX, num_threads) {
+        let new_schema = schema.clone();
+        let new_config = config.clone();
+        let sender = sender.clone();
+        let file_path = file_path.to_string();
+        thread::spawn(move || {
+            let columns = File::open(file_path).and_then(|f| {
+                read_chunk(
+                    new_schema,
+                    &mut BufReader::new(f),
+                    w.0,
+                    w.1,
+                    &new_config,
+                )
+            });
+            // the receiver may have been dropped, which is fine
+            let _ = sender.send(columns);
+        });
+    }
+    receiver
+}
+
+/// The same as [`from_file`](crate::dataframe::from_file), but for files
+/// encoded as latin-1 (ISO-8859-1) instead of `utf-8`. Every line is
+/// transcoded to `utf-8` before it is parsed, so `String` cells are always
+/// valid.
+///
+/// Note that [`infer_schema`](crate::schema::infer_schema) assumes `utf-8`,
+/// so the schema for a latin-1 file containing non-ascii bytes should be
+/// given explicitly.
+pub fn from_file_latin1(
+    file_path: &str,
+    schema: Vec<DataType>,
+    from: usize,
+    len: usize,
+    num_threads: usize,
+) -> Vec<Column> {
+    let config = ParserConfig {
+        latin1: true,
+        ..ParserConfig::default()
+    };
+    from_file_with_config(file_path, schema, from, len, num_threads, &config)
+}
+
+/// Reads every row from `reader` according to the given `schema` and
+/// `config`, such as from a decompressing reader. Unlike
+/// [`from_file_with_config`], the input does not need to be seekable, but it
+/// is read by a single thread from start to end.
+///
+/// # Examples
+/// ```
+/// use sorer::dataframe::{from_reader, Column};
+/// use sorer::parsers::ParserConfig;
+/// use sorer::schema::DataType;
+///
+/// let input = &b"<1> <hi>\n<2> <bye>\n"[..];
+/// let schema = vec![DataType::Int, DataType::String];
+/// let columns = from_reader(input, schema, &ParserConfig::default()).unwrap();
+/// assert_eq!(columns[0], Column::Int(vec![Some(1), Some(2)]));
+/// ```
+pub fn from_reader<R: Read>(
+    reader: R,
+    schema: Vec<DataType>,
+    config: &ParserConfig,
+) -> io::Result<Vec<Column>> {
+    let mut lines = LineReader::with_max_line(reader, config.max_line_bytes);
+    let mut joined = JoinedRow::new(config.max_line_bytes);
+    let mut parsed_data = init_columnar(&schema, config);
+    let mut stats = ParseStats::default();
+    while read_row(&mut lines, &mut joined, config, |row| {
+        push_line(row, &schema, config, &mut parsed_data, &mut stats)
+    })? {}
+    Ok(parsed_data)
+}
+
+/// The same as [`from_reader`], but reads the `zstd` compressed file at
+/// `file_path`. Since the compressed file can't be split at arbitrary byte
+/// offsets, it is decompressed and parsed by a single thread.
+///
+/// # Errors
+/// Returns an error if the file can not be opened or is not valid `zstd`.
+#[cfg(feature = "zstd")]
+pub fn from_zstd_file(
+    file_path: &str,
+    schema: Vec<DataType>,
+    config: &ParserConfig,
+) -> io::Result<Vec<Column>> {
+    let decoder = zstd::stream::read::Decoder::new(File::open(file_path)?)?;
+    from_reader(decoder, schema, config)
+}
+
+/// Get the (i,j) element from the DataFrame
+pub fn get(d: &[Column], col_idx: usize, row_idx: usize) -> Data {
+    match &d[col_idx] {
+        Column::Bool(b) => {
+            if let Some(val) = &b[row_idx] {
+                Data::Bool(*val)
+            } else {
+                Data::Null
+            }
+        }
+        Column::PackedBool(b) => b.get(row_idx).map_or(Data::Null, Data::Bool),
+        Column::Int(b) => {
+            if let Some(val) = &b[row_idx] {
+                Data::Int(*val)
+            } else {
+                Data::Null
+            }
+        }
+        Column::Int32(b) => {
+            if let Some(val) = &b[row_idx] {
+                Data::Int(i64::from(*val))
+            } else {
+                Data::Null
+            }
+        }
+        Column::Float(b) => {
+            if let Some(val) = &b[row_idx] {
+                Data::Float(*val)
+            } else {
+                Data::Null
+            }
+        }
+        Column::String(b) => {
+            if let Some(val) = &b[row_idx] {
+                Data::String(val.clone())
+            } else {
+                Data::Null
+            }
+        }
+    }
+}
+
+/// Returns the number of bytes used by all the columns of `d`, the sum of
+/// their [`Column::memory_usage`].
+pub fn dataframe_memory(d: &[Column]) -> usize {
+    d.iter().map(Column::memory_usage).sum()
+}
+
+/// Returns row `row_idx` of `d` as one `Data` per column, or `None` if
+/// `row_idx` is out of bounds for any of the columns or `d` has no columns.
+pub fn get_row(d: &[Column], row_idx: usize) -> Option<Vec<Data>> {
+    d.iter()
+        .map(|c| {
+            let reader = c.reader();
+            if row_idx < reader.len() {
+                Some(reader.get(row_idx))
+            } else {
+                None
+            }
+        })
+        .collect::<Option<Vec<Data>>>()
+        .filter(|row| !row.is_empty())
+}
+
+/// Returns an iterator over the rows of `d`, each as a `Vec<Data>` holding
+/// one cell per column.
+pub fn rows(d: &[Column]) -> Rows<'_> {
+    rows_in(d, 0..d.first().map_or(0, Column::len))
+}
+
+// The rows of `d` in `range`
+fn rows_in(d: &[Column], range: Range<usize>) -> Rows<'_> {
+    Rows {
+        readers: d.iter().map(Column::reader).collect(),
+        range,
+    }
+}
+
+/// An iterator over the rows of a DataFrame, created with [`rows`].
+#[derive(Clone, Debug)]
+pub struct Rows<'a> {
+    readers: Vec<ColumnReader<'a>>,
+    range: Range<usize>,
+}
+
+impl<'a> Rows<'a> {
+    fn row(&self, row_idx: usize) -> Vec<Data> {
+        self.readers.iter().map(|r| r.get(row_idx)).collect()
+    }
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = Vec<Data>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|row_idx| self.row(row_idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Rows<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().map(|row_idx| self.row(row_idx))
+    }
+}
+
+impl<'a> ExactSizeIterator for Rows<'a> {}
+
+/// Calls `f` on every row of `columns` using all available cores, returning
+/// the results in row order.
+///
+/// The rows are split into contiguous blocks, each of which is walked with a
+/// [`Rows`] iterator on a `rayon` thread, so `f` should be expensive enough
+/// to be worth the cost of building a `Vec<Data>` for every row.
+#[cfg(feature = "rayon")]
+pub fn par_map_rows<T, F>(columns: &[Column], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&[Data]) -> T + Sync,
+{
+    use rayon::prelude::*;
+
+    let len = columns.first().map_or(0, Column::len);
+    // a few blocks per thread so that uneven blocks still balance out
+    let blocks = rayon::current_num_threads() * 4;
+    let block_len = len.div_ceil(blocks).max(1);
+    (0..len)
+        .into_par_iter()
+        .step_by(block_len)
+        .flat_map_iter(|from| {
+            let to = (from + block_len).min(len);
+            rows_in(columns, from..to).map(|row| f(&row))
+        })
+        .collect()
+}
+
+/// Pads every column of `d` with missing cells so that they all have as
+/// many cells as the longest one.
+pub fn align_columns(d: &mut [Column]) {
+    let len = d.iter().map(Column::len).max().unwrap_or(0);
+    for col in d {
+        col.extend_nulls(len - col.len());
+    }
+}
+
+/// Returns a new DataFrame with copies of the columns of `d` at the given
+/// `columns` indices, in the given order.
+///
+/// # Panics
+/// If an index in `columns` is out of bounds.
+pub fn select(d: &[Column], columns: &[usize]) -> Vec<Column> {
+    columns.iter().map(|&i| d[i].clone()).collect()
+}
+
+/// Returns a new DataFrame with the rows of `d` from index `from`
+/// (inclusive) up to index `to` (exclusive). Both indices are clamped to the
+/// number of rows, so a range past the end returns fewer (or no) rows.
+pub fn slice_rows(d: &[Column], from: usize, to: usize) -> Vec<Column> {
+    d.iter().map(|col| col.slice(from, to)).collect()
+}
+
+/// Returns the rows of `d` in groups of `n` rows, as DataFrames with the
+/// same columns as `d`. The last group has fewer than `n` rows if `n` does
+/// not divide the number of rows. The number of rows is the length of the
+/// first column, see [`align_columns`] for DataFrames whose columns have
+/// different lengths.
+///
+/// # Panics
+/// Panics if `n` is 0.
+pub fn chunk_rows(
+    d: &[Column],
+    n: usize,
+) -> impl Iterator<Item = Vec<Column>> + '_ {
+    assert!(n != 0, "chunk size must be non-zero");
+    let len = d.first().map_or(0, Column::len);
+    (0..len)
+        .step_by(n)
+        .map(move |from| slice_rows(d, from, from + n))
+}
+
+/// Returns a new DataFrame with the first `n` rows of `d`, or all of its rows
+/// if it has fewer than `n`.
+pub fn head(d: &[Column], n: usize) -> Vec<Column> {
+    slice_rows(d, 0, n)
+}
+
+/// Returns a new DataFrame with the last `n` rows of `d`, or all of its rows
+/// if it has fewer than `n`.
+pub fn tail(d: &[Column], n: usize) -> Vec<Column> {
+    let len = d.first().map_or(0, Column::len);
+    slice_rows(d, len.saturating_sub(n), len)
+}
+
+/// Returns a new DataFrame with `n` rows of `d` chosen at random, or all of
+/// its rows if it has fewer than `n`, in their original order. The rows are
+/// chosen with [`reservoir_sample`], so the same `seed` always chooses the
+/// same rows.
+pub fn sample_rows(d: &[Column], n: usize, seed: u64) -> Vec<Column> {
+    let len = d.first().map_or(0, Column::len);
+    let mut mask = vec![false; len];
+    for row_idx in reservoir_sample(0..len, n, seed) {
+        mask[row_idx] = true;
+    }
+    apply_mask(d, &mask).expect("the mask has one entry per row")
+}
+
+/// Chooses `n` items of `iter` uniformly at random in a single pass, without
+/// knowing how many items there are upfront, so it can sample the rows of a
+/// [`SorTerator`] or of [`rows`]. Returns every item if there are fewer than
+/// `n`. The items are not returned in any particular order, and the same
+/// `seed` always chooses the same items.
+pub fn reservoir_sample<I: IntoIterator>(
+    iter: I,
+    n: usize,
+    seed: u64,
+) -> Vec<I::Item> {
+    let iter = iter.into_iter();
+    let mut rng = StdRng::seed_from_u64(seed);
+    // `n` may be far more than the number of items, so it can't be trusted
+    // as a capacity
+    let mut reservoir = Vec::with_capacity(n.min(iter.size_hint().0));
+    for (seen, item) in iter.enumerate() {
+        if seen < n {
+            reservoir.push(item);
+        } else {
+            let idx = rng.gen_range(0, seen + 1);
+            if idx < n {
+                reservoir[idx] = item;
+            }
+        }
+    }
+    reservoir
+}
+
+/// Returns a new DataFrame with only the rows of `d` whose entry in `mask` is
+/// `true`, in their original order. The mask can be built from any number of
+/// predicates, so this is the building block for filtering on several
+/// columns at once.
+///
+/// Returns an error if `mask` does not have exactly one entry per row.
+pub fn apply_mask(
+    d: &[Column],
+    mask: &[bool],
+) -> Result<Vec<Column>, &'static str> {
+    if mask.len() != d.first().map_or(0, Column::len) {
+        return Err("The mask must have one entry per row");
+    }
+    Ok(d.iter()
+        .map(|col| match col {
+            Column::Bool(c) => Column::Bool(mask_cells(c, mask)),
+            Column::PackedBool(c) => Column::PackedBool(
+                c.iter()
+                    .zip(mask)
+                    .filter(|(_, keep)| **keep)
+                    .map(|(cell, _)| cell)
+                    .collect(),
+            ),
+            Column::Int(c) => Column::Int(mask_cells(c, mask)),
+            Column::Int32(c) => Column::Int32(mask_cells(c, mask)),
+            Column::Float(c) => Column::Float(mask_cells(c, mask)),
+            Column::String(c) => Column::String(mask_cells(c, mask)),
+        })
+        .collect())
+}
+
+// Clones the cells whose entry in `mask` is `true`
+fn mask_cells<T: Clone>(cells: &[T], mask: &[bool]) -> Vec<T> {
+    cells
+        .iter()
+        .zip(mask)
+        .filter(|(_, keep)| **keep)
+        .map(|(cell, _)| cell.clone())
+        .collect()
+}
+
+/// Counts the rows of the file at `file_name` by counting its `\n` bytes,
+/// plus one for a final line that does not end in a newline. This is much
+/// cheaper than parsing the file, and is an upper bound on the number of
+/// rows that can be parsed from it since blank and invalid lines are also
+/// counted. It is useful for pre-sizing `Vec`s or picking a chunk size for a
+/// [`SorTerator`].
+pub fn count_rows(file_name: &str) -> io::Result<usize> {
+    let (newlines, last_byte) =
+        count_newlines(BufReader::new(File::open(file_name)?))?;
+    if last_byte != b'\n' {
+        Ok(newlines + 1)
+    } else {
+        Ok(newlines)
+    }
+}
+
+// Counts the `\n` bytes of `reader`, returning the count along with the last
+// byte that was read, or `\n` if nothing was read
+fn count_newlines<R: BufRead>(mut reader: R) -> io::Result<(usize, u8)> {
+    let mut newlines = 0;
+    let mut last_byte = b'\n';
+    loop {
+        let bytes_read = reader.fill_buf()?;
+        let len = bytes_read.len();
+        if len == 0 {
+            break;
+        }
+        newlines += bytecount::count(bytes_read, b'\n');
+        last_byte = bytes_read[len - 1];
+        reader.consume(len);
+    }
+    Ok((newlines, last_byte))
+}
+
+/// Returns the index of the row containing the byte at `offset` in the file
+/// at `file_name`, which is the number of `\n` bytes before `offset`.
+///
+/// Every line of the file counts as a row here, including blank and invalid
+/// lines that are discarded when parsing, so the result is the row index the
+/// byte would have when parsing with `keep_invalid_rows` (and without
+/// `comment_prefix` or `multi_line_strings`). Returns an `InvalidInput` error
+/// if `offset` is not within the file.
+pub fn byte_offset_to_row(file_name: &str, offset: u64) -> io::Result<usize> {
+    let file = File::open(file_name)?;
+    if offset >= file.metadata()?.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Byte offset {} is past the end of {}", offset, file_name),
+        ));
+    }
+    let mut reader = BufReader::new(file.take(offset));
+    let mut row = 0;
+    loop {
+        let bytes_read = reader.fill_buf()?;
+        let len = bytes_read.len();
+        if len == 0 {
+            break;
+        }
+        row += bytecount::count(bytes_read, b'\n');
+        reader.consume(len);
+    }
+    Ok(row)
+}
+
+/// Returns the byte offset of the start of the row at index `row` in the
+/// file at `file_name`. This is the inverse of [`byte_offset_to_row`] and
+/// counts rows the same way. Returns an `InvalidInput` error if the file has
+/// `row` or fewer rows.
+pub fn row_to_byte_offset(file_name: &str, row: usize) -> io::Result<u64> {
+    let file = File::open(file_name)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut offset = 0;
+    let mut newlines_left = row;
+    while newlines_left > 0 {
+        let bytes_read = reader.fill_buf()?;
+        let len = bytes_read.len();
+        if len == 0 {
+            break;
+        }
+        let newlines = bytecount::count(bytes_read, b'\n');
+        if newlines < newlines_left {
+            newlines_left -= newlines;
+            offset += len as u64;
+            reader.consume(len);
+        } else {
+            // the row starts right after the last newline we are looking for
+            let idx = memchr::memchr_iter(b'\n', bytes_read)
+                .nth(newlines_left - 1)
+                .unwrap();
+            offset += idx as u64 + 1;
+            newlines_left = 0;
+        }
+    }
+    if offset >= file_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Row {} is past the end of {}", row, file_name),
+        ));
+    }
+    Ok(offset)
+}
+
+/// A helper function to help with multi-threading in the top level `from_file`
+/// function. Does the heavy lifting of actually calling
+/// [parser functions](::crate::parsers). Parsers a chunk of the given `reader`
+/// up to `len` bytes starting at the `from` byte offset. Every line that ends
+/// within those `len` bytes is parsed, whether or not it ends in a newline.
+/// Returns an error if `reader` can not be read.
+fn read_chunk<T>(
+    schema: Vec<DataType>,
+    reader: &mut T,
+    from: usize,
+    len: usize,
+    config: &ParserConfig,
+) -> io::Result<Vec<Column>>
+where
+    T: BufRead + Seek,
+{
+    read_chunk_with_progress(schema, reader, from, len, config, &mut |_| ())
+}
+
+// The same as `read_chunk`, but calls `progress` with the number of bytes
+// read since the previous call after each row, and once at the end with the
+// rest of the `len` bytes
+fn read_chunk_with_progress<T>(
+    schema: Vec<DataType>,
+    reader: &mut T,
+    from: usize,
+    len: usize,
+    config: &ParserConfig,
+    progress: &mut dyn FnMut(usize),
+) -> io::Result<Vec<Column>>
+where
+    T: BufRead + Seek,
+{
+    reader.seek(SeekFrom::Start(from as u64))?;
+    let mut buffer = Vec::new();
+
+    let mut so_far = if from != 0 {
+        // throw away the first line
+        let l1_len = read_line_capped(reader, &mut buffer, Some(0))?.0;
+        buffer.clear();
+        l1_len
+    } else {
+        0
+    };
+
+    let mut parsed_data = init_columnar(&schema, config);
+    let mut reported = 0;
+    let mut joined = JoinedRow::new(config.max_line_bytes);
+    let mut next_line = Vec::new();
+
+    loop {
+        // a line that is too long is left out of `buffer`, so it is parsed
+        // as an empty (invalid) line
+        let (line_len, _) =
+            read_line_capped(reader, &mut buffer, config.max_line_bytes)?;
+        so_far += line_len;
+        // a line that ends exactly at `len` (or at the end of the file) is
+        // complete, so only stop once a line goes past `len`
+        if line_len == 0 || so_far > len {
+            break;
+        }
+        if is_comment(&buffer, config) {
+            buffer.clear();
+            continue;
+        }
+
+        let line = if config.multi_line_strings && has_unclosed_quote(&buffer) {
+            // keep reading lines until the quoted string is closed
+            joined.start(strip_newline(&buffer));
+            while joined.is_open() {
+                next_line.clear();
+                let (next_len, too_long) = read_line_capped(
+                    reader,
+                    &mut next_line,
+                    config.max_line_bytes,
+                )?;
+                if next_len == 0 {
+                    break;
+                }
+                so_far += next_len;
+                joined.push(strip_newline(&next_line), too_long);
+            }
+            joined.row()
+        } else {
+            &buffer
+        };
+
+        // parse line with schema and place into the columnar vec here
+        push_line(
+            line,
+            &schema,
+            config,
+            &mut parsed_data,
+            &mut ParseStats::default(),
+        );
+        buffer.clear();
+        let done = so_far.min(len);
+        progress(done - reported);
+        reported = done;
+    }
+    let done = so_far.min(len);
+    if done > reported {
+        progress(done - reported);
+    }
+    Ok(parsed_data)
+}
+
+// Returns `line` without its trailing newline
+fn strip_newline(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\n").unwrap_or(line)
+}
+
+// Reads a line into `buffer` like `read_until(b'\n', buffer)`, returning the
+// number of bytes read and whether the line was too long. Once `buffer` holds
+// more than `max_len` bytes, not counting the newline, it is cleared and the
+// rest of the line is read without being buffered.
+fn read_line_capped<R: BufRead + ?Sized>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    max_len: Option<usize>,
+) -> io::Result<(usize, bool)> {
+    let max_len = match max_len {
+        Some(max_len) => max_len,
+        None => return Ok((reader.read_until(b'\n', buffer)?, false)),
+    };
+    let mut read = 0;
+    let mut too_long = false;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok((read, too_long));
+        }
+        let (used, found_newline) = match memchr(b'\n', available) {
+            Some(idx) => (idx + 1, true),
+            None => (available.len(), false),
+        };
+        if !too_long {
+            buffer.extend_from_slice(&available[..used]);
+            if buffer.len() - usize::from(found_newline) > max_len {
+                too_long = true;
+                buffer.clear();
+            }
+        }
+        reader.consume(used);
+        read += used;
+        if found_newline {
+            return Ok((read, too_long));
+        }
+    }
+}
+
+/// Streams the lines of the file at `file_name`, parsing each according to
+/// `schema` and writing the rows for which `pred` returns `true` to `out` in
+/// `SoR` format. Only one line is held in memory at a time, so this works
+/// for files of any size. Rows that don't match the schema are skipped.
+/// Returns the number of rows written.
+///
+/// Rows are written with one space between fields, strings are always
+/// quoted and missing values are written as `<>`, so the output may differ
+/// byte-wise from the original lines.
+pub fn filter_sor_to<W, F>(
+    file_name: &str,
+    schema: &[DataType],
+    pred: F,
+    out: &mut W,
+) -> io::Result<usize>
+where
+    W: Write,
+    F: Fn(&[Data]) -> bool,
+{
+    let config = ParserConfig::default();
+    let mut lines = LineReader::new(File::open(file_name)?);
+    let mut written = 0;
+    while let Some(line) = lines.next_line()? {
+        if let Some(row) = parse_line_with_schema_opts(line, schema, &config) {
+            if pred(&row) {
+                write_sor_row(&row, out)?;
+                written += 1;
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Streams the lines of the file at `file_name`, parsing each according to
+/// `schema` and writing only the first occurrence of every distinct row to
+/// `out` in `SoR` format, in the same format as [`filter_sor_to`]. Rows that
+/// don't match the schema are skipped. Returns the number of rows written.
+///
+/// Rows are compared by their parsed values, so `<1>` and `< 1 >` are the
+/// same row, as are all rows that only differ by a `NaN` or by `0.0` and
+/// `-0.0`. Every distinct row is kept in memory, so memory grows with the
+/// number of distinct rows rather than with the size of the file.
+pub fn dedup_rows_to<W: Write>(
+    file_name: &str,
+    schema: &[DataType],
+    out: &mut W,
+) -> io::Result<usize> {
+    let config = ParserConfig::default();
+    let mut lines = LineReader::new(File::open(file_name)?);
+    let mut seen = HashSet::new();
+    let mut written = 0;
+    while let Some(line) = lines.next_line()? {
+        if let Some(row) = parse_line_with_schema_opts(line, schema, &config) {
+            let key: Vec<DataKey> =
+                row.iter().cloned().map(DataKey::from).collect();
+            if seen.insert(key) {
+                write_sor_row(&row, out)?;
+                written += 1;
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Merges the files at `file_a` and `file_b`, which must both already be
+/// sorted by the column at `key_col`, writing their rows to `out` in sorted
+/// order in the same format as [`filter_sor_to`]. Both files are parsed
+/// according to `schema` and rows that don't match it are skipped. Returns
+/// the number of rows written.
+///
+/// Only one line of each file is held in memory at a time. Missing keys sort
+/// before all other keys, `Float` keys are compared with `f64::total_cmp`
+/// and when both files have a row with an equal key, the row of `file_a` is
+/// written first. If the files are not sorted the rows are still all
+/// written, but not in sorted order.
+///
+/// # Panics
+/// If `key_col` is not a column of the `schema`.
+pub fn sorted_merge_to<W: Write>(
+    file_a: &str,
+    file_b: &str,
+    schema: &[DataType],
+    key_col: usize,
+    out: &mut W,
+) -> io::Result<usize> {
+    assert!(
+        key_col < schema.len(),
+        "Key column {} is out of bounds for {} columns",
+        key_col,
+        schema.len()
+    );
+    let config = ParserConfig::default();
+    let mut lines_a = LineReader::new(File::open(file_a)?);
+    let mut lines_b = LineReader::new(File::open(file_b)?);
+    let mut row_a = next_row(&mut lines_a, schema, &config)?;
+    let mut row_b = next_row(&mut lines_b, schema, &config)?;
+    let mut written = 0;
+    loop {
+        let take_a = match (&row_a, &row_b) {
+            (Some(a), Some(b)) => {
+                compare_keys(&a[key_col], &b[key_col]) != Ordering::Greater
+            }
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => return Ok(written),
+        };
+        if take_a {
+            write_sor_row(row_a.as_ref().unwrap(), out)?;
+            row_a = next_row(&mut lines_a, schema, &config)?;
+        } else {
+            write_sor_row(row_b.as_ref().unwrap(), out)?;
+            row_b = next_row(&mut lines_b, schema, &config)?;
+        }
+        written += 1;
+    }
+}
+
+// Returns the next row of `lines` that matches the `schema`
+fn next_row<R: Read>(
+    lines: &mut LineReader<R>,
+    schema: &[DataType],
+    config: &ParserConfig,
+) -> io::Result<Option<Vec<Data>>> {
+    while let Some(line) = lines.next_line()? {
+        if let Some(row) = parse_line_with_schema_opts(line, schema, config) {
+            return Ok(Some(row));
+        }
+    }
+    Ok(None)
+}
+
+// Orders two keys of the same column, with missing keys first
+fn compare_keys(a: &Data, b: &Data) -> Ordering {
+    match (a, b) {
+        (Data::Null, Data::Null) => Ordering::Equal,
+        (Data::Null, _) => Ordering::Less,
+        (_, Data::Null) => Ordering::Greater,
+        (Data::Bool(a), Data::Bool(b)) => a.cmp(b),
+        (Data::Int(a), Data::Int(b)) => a.cmp(b),
+        (Data::Float(a), Data::Float(b)) => a.total_cmp(b),
+        (Data::String(a), Data::String(b)) => a.cmp(b),
+        _ => unreachable!("keys of the same column have the same type"),
+    }
+}
+
+// Writes `row` as a line of `SoR` fields
+fn write_sor_row<W: Write>(row: &[Data], out: &mut W) -> io::Result<()> {
+    for (i, data) in row.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b" ")?;
+        }
+        match data {
+            Data::Null => write!(out, "<>")?,
+            data => write!(out, "<{}>", data)?,
+        }
+    }
+    out.write_all(b"\n")
+}
+
+/// Writes `d` to `out` as comma separated values, one line per row ending
+/// in `\n`, as described by `options`. Missing values are written as
+/// `options.null_token` without any quoting, `String`s are quoted according
+/// to `options.quote_style` and `Bool`s are written in `options.bool_style`.
+/// Numbers are never quoted.
+///
+/// With `QuoteStyle::Never`, a `String` containing a comma, a double quote or
+/// a line break can not be written unambiguously, so an `InvalidData` error is
+/// returned instead. The rows before it have already been written to `out`.
+pub fn write_csv<W: Write>(
+    d: &[Column],
+    out: &mut W,
+    options: &CsvOptions,
+) -> io::Result<()> {
+    let num_lines = d.first().map_or(0, Column::len);
+    for row_idx in 0..num_lines {
+        for col_idx in 0..d.len() {
+            if col_idx > 0 {
+                out.write_all(b",")?;
+            }
+            match get(d, col_idx, row_idx) {
+                Data::Null => out.write_all(options.null_token.as_bytes())?,
+                Data::String(s) => {
+                    write_csv_string(&s, out, options.quote_style)?
+                }
+                data => out.write_all(
+                    data.to_display_string(options.bool_style).as_bytes(),
+                )?,
+            }
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Writes a header line of column `names` to `out` for the comma separated
+/// values written by [`write_csv`], quoting the names according to
+/// `quote_style` the same way as `String` cells.
+pub fn write_csv_header<W: Write>(
+    names: &[String],
+    out: &mut W,
+    quote_style: QuoteStyle,
+) -> io::Result<()> {
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_csv_string(name, out, quote_style)?;
+    }
+    out.write_all(b"\n")
+}
+
+/// The maximum width of a cell printed by [`print_table`], longer cells are
+/// truncated and end in `...`.
+pub const MAX_TABLE_CELL_WIDTH: usize = 20;
+
+/// Writes the first `max_rows` rows of `columns` to `out` as a bordered ASCII
+/// table, with the type of each column from `schema` in the header. `Int` and
+/// `Float` cells are right aligned and all other cells are left aligned.
+/// Missing values are left empty, and if there are more than `max_rows` rows
+/// a line saying how many were left out follows the table.
+pub fn print_table<W: Write>(
+    columns: &[Column],
+    schema: &[DataType],
+    max_rows: usize,
+    out: &mut W,
+) -> io::Result<()> {
+    let num_lines = columns.first().map_or(0, Column::len);
+    let shown = head(columns, max_rows);
+    let headers: Vec<String> = schema
+        .iter()
+        .map(|t| format!("{:?}", t).to_uppercase())
+        .collect();
+    let cells: Vec<Vec<String>> = shown
+        .iter()
+        .map(|col| match col.to_string_column() {
+            Column::String(c) => c
+                .into_iter()
+                .map(|cell| truncate_cell(cell.unwrap_or_default()))
+                .collect(),
+            _ => unreachable!(),
+        })
+        .collect();
+    let right_aligned: Vec<bool> = schema
+        .iter()
+        .map(|t| matches!(t, DataType::Int | DataType::Float))
+        .collect();
+    write_table(&headers, &cells, &right_aligned, out)?;
+    if num_lines > max_rows {
+        writeln!(out, "({} more rows)", num_lines - max_rows)?;
+    }
+    Ok(())
+}
+
+// Writes a bordered ASCII table with the given `headers` and the `cells` of
+// every column, right aligning the cells of the columns marked in
+// `right_aligned`
+pub(crate) fn write_table<W: Write>(
+    headers: &[String],
+    cells: &[Vec<String>],
+    right_aligned: &[bool],
+    out: &mut W,
+) -> io::Result<()> {
+    let widths: Vec<usize> = headers
+        .iter()
+        .zip(cells)
+        .map(|(header, col)| {
+            col.iter()
+                .map(|cell| cell.chars().count())
+                .chain(std::iter::once(header.len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let border: String = widths
+        .iter()
+        .map(|w| format!("+{}", "-".repeat(w + 2)))
+        .collect::<String>()
+        + "+";
+    writeln!(out, "{}", border)?;
+    for (header, width) in headers.iter().zip(&widths) {
+        write!(out, "| {:<width$} ", header, width = width)?;
+    }
+    writeln!(out, "|")?;
+    writeln!(out, "{}", border)?;
+    for row_idx in 0..cells.first().map_or(0, Vec::len) {
+        for ((col, width), right) in
+            cells.iter().zip(&widths).zip(right_aligned)
+        {
+            if *right {
+                write!(out, "| {:>width$} ", col[row_idx], width = width)?
+            } else {
+                write!(out, "| {:<width$} ", col[row_idx], width = width)?
+            }
+        }
+        writeln!(out, "|")?;
+    }
+    writeln!(out, "{}", border)
+}
+
+// Shortens `cell` to `MAX_TABLE_CELL_WIDTH` characters
+pub(crate) fn truncate_cell(cell: String) -> String {
+    if cell.chars().count() <= MAX_TABLE_CELL_WIDTH {
+        return cell;
+    }
+    let mut truncated: String =
+        cell.chars().take(MAX_TABLE_CELL_WIDTH - 3).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+// Writes `s` as a csv field, doubling any double quotes inside quoted fields
+fn write_csv_string<W: Write>(
+    s: &str,
+    out: &mut W,
+    quote_style: QuoteStyle,
+) -> io::Result<()> {
+    let needs_quotes = s.is_empty()
+        || s.bytes().any(|b| matches!(b, b',' | b'"' | b'\n' | b'\r'));
+    let quote = match quote_style {
+        QuoteStyle::Always => true,
+        QuoteStyle::Minimal => needs_quotes,
+        QuoteStyle::Never if needs_quotes && !s.is_empty() => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} can not be written without quotes", s),
+            ));
+        }
+        QuoteStyle::Never => false,
+    };
+    if quote {
+        write!(out, "\"{}\"", s.replace('"', "\"\""))
+    } else {
+        out.write_all(s.as_bytes())
+    }
+}
+
+/// Parses `line` according to `schema` and appends its fields to
+/// `parsed_data`, adding any changed fields to `stats`. Invalid lines are
+/// skipped, or appended as a row of missing values if
+/// `config.keep_invalid_rows` is set.
+pub(crate) fn push_line(
+    line: &[u8],
+    schema: &[DataType],
+    config: &ParserConfig,
+    parsed_data: &mut [Column],
+    stats: &mut ParseStats,
+) {
+    let line = decode_line(line, config);
+    let parsed = match &config.projection {
+        Some(projection) => parse_line_with_schema_projected_stats(
+            &line, schema, projection, config, stats,
+        ),
+        None => parse_line_with_schema_stats(&line, schema, config, stats),
+    };
+    match parsed {
+        None if config.keep_invalid_rows => push_null_row(parsed_data),
+        None => (),
+        Some(data) => {
+            for (d, col) in data.into_iter().zip(parsed_data.iter_mut()) {
+                col.push_data(d).expect("Parser Failed");
+            }
+        }
+    }
+}
+
+/// Appends a missing value to every column in `columns`.
+fn push_null_row(columns: &mut [Column]) {
+    for col in columns {
+        col.push_null();
+    }
+}
+
+/// Splits a reader into lines by scanning its buffered bytes with `memchr`.
+/// Unlike `BufRead::split`, the lines are slices into an internal buffer, so
+/// no allocation is done per line. The buffer only grows when a single line
+/// is longer than it.
+pub(crate) struct LineReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    // the unread bytes are `buf[start..end]`
+    start: usize,
+    end: usize,
+    eof: bool,
+    // lines longer than this are returned as empty lines
+    max_line: Option<usize>,
+    // set while dropping the bytes of a line longer than `max_line`
+    skipping: bool,
+}
+
+impl<R: Read> LineReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        LineReader::with_max_line(reader, None)
+    }
+
+    /// Creates a `LineReader` that returns lines longer than `max_line` bytes
+    /// as empty lines. Such lines are never buffered whole, their bytes are
+    /// dropped as they are read until the next newline.
+    pub(crate) fn with_max_line(reader: R, max_line: Option<usize>) -> Self {
+        LineReader {
+            reader,
+            buf: vec![0; 8 * 1024],
+            start: 0,
+            end: 0,
+            eof: false,
+            max_line,
+            skipping: false,
+        }
+    }
+
+    /// Returns the next line without its trailing newline, or `None` once
+    /// the reader is exhausted.
+    pub(crate) fn next_line(&mut self) -> io::Result<Option<&[u8]>> {
+        Ok(self.next_line_capped()?.map(|(line, _)| line))
+    }
+
+    /// The same as [`next_line`](LineReader::next_line), but also returns
+    /// whether the line was longer than `max_line`, in which case it is
+    /// returned as an empty line.
+    fn next_line_capped(&mut self) -> io::Result<Option<(&[u8], bool)>> {
+        let (line_start, line_end) = loop {
+            let unread = &self.buf[self.start..self.end];
+            if let Some(idx) = memchr(b'\n', unread) {
+                let line = (self.start, self.start + idx);
+                self.start += idx + 1;
+                break line;
+            }
+            if self.eof {
+                if self.start == self.end && !self.skipping {
+                    return Ok(None);
+                }
+                let line = (self.start, self.end);
+                self.start = self.end;
+                break line;
+            }
+            if matches!(self.max_line, Some(max) if self.end - self.start > max)
+            {
+                self.skipping = true;
+                self.start = self.end;
+            }
+            // move the partial line to the front of the buffer, growing it
+            // if the partial line fills the whole buffer, then read more
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+            if self.end == self.buf.len() {
+                self.buf.resize(self.buf.len() * 2, 0);
+            }
+            let read = self.reader.read(&mut self.buf[self.end..])?;
+            if read == 0 {
+                self.eof = true;
+            }
+            self.end += read;
+        };
+        let too_long =
+            matches!(self.max_line, Some(max) if line_end - line_start > max);
+        if self.skipping || too_long {
+            self.skipping = false;
+            return Ok(Some((&[], true)));
+        }
+        Ok(Some((&self.buf[line_start..line_end], false)))
+    }
+}
+
+// Reads the next row from `lines` and hands it to `push`, skipping comment
+// lines and joining the lines of a multi-line string in `joined`. Returns
+// `false` once `lines` is exhausted.
+fn read_row<R: Read>(
+    lines: &mut LineReader<R>,
+    joined: &mut JoinedRow,
+    config: &ParserConfig,
+    push: impl FnOnce(&[u8]),
+) -> io::Result<bool> {
+    loop {
+        let line = match lines.next_line()? {
+            Some(line) => line,
+            None => return Ok(false),
+        };
+        if is_comment(line, config) {
+            continue;
+        }
+        if config.multi_line_strings && has_unclosed_quote(line) {
+            // keep reading lines until the quoted string is closed
+            joined.start(line);
+            while joined.is_open() {
+                match lines.next_line_capped()? {
+                    Some((next_line, too_long)) => {
+                        joined.push(next_line, too_long)
+                    }
+                    None => break,
+                }
+            }
+            push(joined.row());
+        } else {
+            push(line);
+        }
+        return Ok(true);
+    }
+}
+
+/// Joins the lines of a row whose quoted string spans several lines, see
+/// `ParserConfig::multi_line_strings`. Once the joined row is longer than
+/// `max_len` bytes, it is dropped and only the quotes of its remaining lines
+/// are counted to find where it ends, so such a row is never held in memory
+/// whole. A single line that was too long to be read counts as having no
+/// quotes.
+pub(crate) struct JoinedRow {
+    row: Vec<u8>,
+    max_len: Option<usize>,
+    // whether the row has a quoted string that was not closed yet
+    open: bool,
+    too_long: bool,
+}
+
+impl JoinedRow {
+    pub(crate) fn new(max_len: Option<usize>) -> Self {
+        JoinedRow {
+            row: Vec::new(),
+            max_len,
+            open: false,
+            too_long: false,
+        }
+    }
+
+    /// Starts a new row with its first `line`.
+    pub(crate) fn start(&mut self, line: &[u8]) {
+        self.row.clear();
+        self.open = false;
+        self.too_long = false;
+        self.add(line, false);
+    }
+
+    /// Appends the next `line` of the row, where `too_long` is whether the
+    /// line itself was too long to be read.
+    pub(crate) fn push(&mut self, line: &[u8], too_long: bool) {
+        self.row.push(b'\n');
+        self.add(line, too_long);
+    }
+
+    /// Whether the row still has an unclosed quoted string, so that the
+    /// next line belongs to it.
+    pub(crate) fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// The joined row, which is empty (so invalid) if it was too long.
+    pub(crate) fn row(&self) -> &[u8] {
+        &self.row
+    }
+
+    fn add(&mut self, line: &[u8], too_long: bool) {
+        self.open ^= has_unclosed_quote(line);
+        self.too_long |= too_long;
+        if !self.too_long {
+            self.row.extend_from_slice(line);
+            self.too_long =
+                matches!(self.max_len, Some(max) if self.row.len() > max);
+        }
+        if self.too_long {
+            self.row.clear();
+        }
+    }
+}
+
+/// Used for chunking `SoR` files.
+pub struct SorTerator {
+    lines: LineReader<File>,
+    // holds lines that were joined because of `config.multi_line_strings`
+    joined: JoinedRow,
+    chunk_size: usize,
+    schema: Vec<DataType>,
+    empty_col: Column,
+    config: ParserConfig,
+    stats: ParseStats,
+    // the chunk parsed by `peek`, returned by the following `next`
+    peeked: Option<Option<Vec<Column>>>,
+    #[cfg(feature = "rayon")]
+    parallel: bool,
+}
+
+/// A chunking iterator that can chunk `SoR` files into `Vec<Column>`s where
+/// all columns have the same length (number of rows) based an argument in
+/// the constructor. The last element returned by `next` may have less than
+/// `chunk_size` number of rows and it is up to the caller to verify the
+/// length if needed.
+impl SorTerator {
+    /// Creates a new [`SorTerator`](::crate::dataframe::SorTerator)
+    pub fn new(
+        file_name: &str,
+        schema: Vec<DataType>,
+        chunk_size: usize,
+    ) -> Self {
+        SorTerator::with_config(
+            file_name,
+            schema,
+            chunk_size,
+            ParserConfig::default(),
+        )
+    }
+
+    /// Creates a new [`SorTerator`](::crate::dataframe::SorTerator) that
+    /// reads the file according to the given
+    /// [`ParserConfig`](crate::parsers::ParserConfig).
+    ///
+    /// # Panics
+    /// Panics if `config.projection` has an index that is out of bounds for
+    /// `schema`.
+    pub fn with_config(
+        file_name: &str,
+        schema: Vec<DataType>,
+        chunk_size: usize,
+        config: ParserConfig,
+    ) -> Self {
+        check_projection(&schema, &config);
+        SorTerator {
+            lines: LineReader::with_max_line(
+                File::open(file_name).unwrap(),
+                config.max_line_bytes,
+            ),
+            joined: JoinedRow::new(config.max_line_bytes),
+            empty_col: Column::Bool(Vec::new()),
+            chunk_size,
+            schema,
+            config,
+            stats: ParseStats::default(),
+            peeked: None,
+            #[cfg(feature = "rayon")]
+            parallel: false,
+        }
+    }
+
+    /// Returns the chunk that the next call to `next` returns, without
+    /// consuming it, or `None` if the file has been completely parsed. The
+    /// chunk is parsed and cached the first time it is peeked, so peeking
+    /// again is free, and its fields are already counted by
+    /// [`stats`](SorTerator::stats).
+    pub fn peek(&mut self) -> Option<&Vec<Column>> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.next_chunk());
+        }
+        self.peeked.as_ref().and_then(Option::as_ref)
+    }
+
+    /// Chooses `n` of the remaining rows at random with [`reservoir_sample`],
+    /// returning them in the order they appear in the file, or all of them
+    /// if there are fewer than `n`. Only the chosen rows and the chunk being
+    /// parsed are held in memory, and the same `seed` chooses the same rows
+    /// as [`sample_rows`] does on the parsed file.
+    pub fn sample(self, n: usize, seed: u64) -> Vec<Column> {
+        let mut sampled = init_columnar(&self.schema, &self.config);
+        let all_rows = self
+            .flat_map(|chunk| rows(&chunk).collect::<Vec<Vec<Data>>>())
+            .enumerate();
+        let mut chosen = reservoir_sample(all_rows, n, seed);
+        chosen.sort_unstable_by_key(|(row_idx, _)| *row_idx);
+        for (_, row) in chosen {
+            for (column, cell) in sampled.iter_mut().zip(row) {
+                column
+                    .push_data(cell)
+                    .expect("the row was parsed with the same schema");
+            }
+        }
+        sampled
+    }
+
+    /// Returns the counts of the fields that were changed while parsing the
+    /// chunks returned so far, see [`ParseStats`].
+    pub fn stats(&self) -> ParseStats {
+        self.stats
+    }
+
+    // Parses the next chunk, see `next`
+    fn next_chunk(&mut self) -> Option<Vec<Column>> {
+        #[cfg(feature = "rayon")]
+        {
+            if self.parallel {
+                return self.next_parallel();
+            }
+        }
+        let mut parsed_data = init_columnar(&self.schema, &self.config);
+        let (schema, config, stats) =
+            (&self.schema, &self.config, &mut self.stats);
+        while let Ok(true) =
+            read_row(&mut self.lines, &mut self.joined, config, |row| {
+                push_line(row, schema, config, &mut parsed_data, stats)
+            })
+        {
+            if let Some(column) = parsed_data.first() {
+                if column.len() == self.chunk_size {
+                    return Some(parsed_data);
+                }
+            }
+        }
+        if parsed_data.first().unwrap_or(&self.empty_col).is_empty() {
+            None
+        } else {
+            Some(parsed_data)
+        }
+    }
+
+    /// Sets whether each chunk is parsed using all available cores. When
+    /// set, the raw lines of a chunk are collected first, then split into
+    /// groups of rows that are parsed in parallel, and finally the cells of
+    /// each column are gathered in parallel across columns. The chunks are
+    /// the same as when parsing serially, so this only pays off for wide
+    /// files where parsing a chunk outweighs copying its lines.
+    #[cfg(feature = "rayon")]
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    // The parallel version of `next`, see `SorTerator::parallel`
+    #[cfg(feature = "rayon")]
+    fn next_parallel(&mut self) -> Option<Vec<Column>> {
+        use rayon::prelude::*;
+
+        let mut parsed_data = init_columnar(&self.schema, &self.config);
+        let mut lines = Vec::new();
+        loop {
+            // invalid lines may be skipped, so keep collecting lines until
+            // the chunk is full just like the serial version
+            let missing =
+                self.chunk_size - parsed_data.first().map_or(0, Column::len);
+            lines.clear();
+            let mut done = false;
+            while lines.len() < missing {
+                let read = read_row(
+                    &mut self.lines,
+                    &mut self.joined,
+                    &self.config,
+                    |row| lines.push(row.to_vec()),
+                );
+                if !matches!(read, Ok(true)) {
+                    done = true;
+                    break;
+                }
+            }
+
+            let group_size =
+                (lines.len() / rayon::current_num_threads()).max(1);
+            let (schema, config) = (&self.schema, &self.config);
+            let groups: Vec<(Vec<Column>, ParseStats)> = lines
+                .par_chunks(group_size)
+                .map(|group| {
+                    let mut columns = init_columnar(schema, config);
+                    let mut stats = ParseStats::default();
+                    for line in group {
+                        push_line(
+                            line,
+                            schema,
+                            config,
+                            &mut columns,
+                            &mut stats,
+                        );
+                    }
+                    (columns, stats)
+                })
+                .collect();
+
+            // regroup the parsed groups by column so that every column can
+            // append its cells independently
+            let mut by_column: Vec<Vec<Column>> = parsed_data
+                .iter()
+                .map(|_| Vec::with_capacity(groups.len()))
+                .collect();
+            for (group, stats) in groups {
+                self.stats.nulled_bools += stats.nulled_bools;
+                for (cells, column) in by_column.iter_mut().zip(group) {
+                    cells.push(column);
+                }
+            }
+            parsed_data.par_iter_mut().zip(by_column).for_each(
+                |(column, cells)| {
+                    for mut c in cells {
+                        column.append(&mut c);
+                    }
+                },
+            );
+
+            let len = parsed_data.first().map_or(0, Column::len);
+            if done || len == self.chunk_size {
+                break;
+            }
+        }
+        if parsed_data.first().unwrap_or(&self.empty_col).is_empty() {
+            None
+        } else {
+            Some(parsed_data)
+        }
+    }
+}
+
+/// Implementation for an `Iterator` that chunks a `SoR` file
+impl Iterator for SorTerator {
+    type Item = Vec<Column>;
+
+    /// Advances this iterator until `self.chunk_size` rows have been parsed,
+    /// returning `Some(Vec<Column>)` of the parsed rows when done, or `None`
+    /// or the file has been completely parsed. The last element returned by
+    /// `next` may have less than `chunk_size` number of rows and it is up to
+    /// the caller to verify the length if needed.
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(chunk) => chunk,
+            None => self.next_chunk(),
+        }
+    }
+}
+
+/// Calls `f` on every chunk produced by `terator`, using all available cores.
+///
+/// Chunks are parsed one at a time on a separate thread and handed to a
+/// `rayon` thread pool, so different chunks are processed in parallel and in
+/// no particular order. Memory stays bounded by the chunk size: at most one
+/// chunk per pool thread is being processed, plus one buffered chunk per pool
+/// thread waiting to be processed and the one currently being parsed.
+#[cfg(feature = "rayon")]
+pub fn par_for_each_chunk<F>(terator: SorTerator, f: F)
+where
+    F: Fn(&[Column]) + Send + Sync,
+{
+    use rayon::iter::{ParallelBridge, ParallelIterator};
+    use std::sync::mpsc::sync_channel;
+
+    let (sender, receiver) = sync_channel(rayon::current_num_threads());
+    let producer = thread::spawn(move || {
+        for chunk in terator {
+            if sender.send(chunk).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+        .into_iter()
+        .par_bridge()
+        .for_each(|chunk| f(&chunk));
+    producer.join().unwrap();
+}
+
+/// Writes `columns` to the file at `path` in `bincode`'s binary format, so
+/// that it can be reloaded with [`load_dataframe`] much faster than parsing
+/// the original `SoR` file again. Overwrites the file if it already exists.
+#[cfg(feature = "bincode")]
+pub fn save_dataframe(columns: &[Column], path: &str) -> bincode::Result<()> {
+    use std::io::BufWriter;
+
+    let writer = BufWriter::new(File::create(path)?);
+    bincode::serialize_into(writer, columns)
+}
+
+/// Reads a dataframe that was written by [`save_dataframe`] from the file at
+/// `path`.
+#[cfg(feature = "bincode")]
+pub fn load_dataframe(path: &str) -> bincode::Result<Vec<Column>> {
+    let reader = BufReader::new(File::open(path)?);
+    bincode::deserialize_from(reader)
+}
+
+/// Converts `columns` to an Arrow `RecordBatch` whose fields are named by
+/// `field_names`. Every field is nullable, and missing cells become Arrow
+/// nulls. Columns become arrays of the matching 64 bit or `Utf8` type.
+/// `Int32` columns are widened to `Int64` arrays like `Int` columns, so the
+/// Arrow schema only depends on the schema of the file and not on
+/// [`ParserConfig::narrow_ints`](crate::parsers::ParserConfig::narrow_ints).
+///
+/// Returns an error if there is not exactly one name per column, or if the
+/// columns are not all of the same length.
+#[cfg(feature = "parquet")]
+pub fn to_record_batch(
+    columns: &[Column],
+    field_names: &[String],
+) -> Result<arrow_array::RecordBatch, arrow_schema::ArrowError> {
+    use arrow_array::{
+        ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray,
+    };
+    use arrow_schema::{ArrowError, Field, Schema};
+    use std::sync::Arc;
+
+    if columns.len() != field_names.len() {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "{} field names were given for {} columns",
+            field_names.len(),
+            columns.len()
+        )));
+    }
+    let arrays: Vec<ArrayRef> = columns
+        .iter()
+        .map(|col| -> ArrayRef {
+            match col {
+                Column::Bool(c) => Arc::new(BooleanArray::from(c.clone())),
+                Column::PackedBool(c) => {
+                    Arc::new(BooleanArray::from(c.to_vec()))
+                }
+                Column::Int(c) => Arc::new(Int64Array::from(c.clone())),
+                Column::Int32(c) => Arc::new(
+                    c.iter().map(|v| v.map(i64::from)).collect::<Int64Array>(),
+                ),
+                Column::Float(c) => Arc::new(Float64Array::from(c.clone())),
+                Column::String(c) => Arc::new(StringArray::from(c.clone())),
+            }
+        })
+        .collect();
+    let fields: Vec<Field> = field_names
+        .iter()
+        .zip(&arrays)
+        .map(|(name, array)| Field::new(name, array.data_type().clone(), true))
+        .collect();
+    arrow_array::RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+}
+
+/// Writes `columns` to a Parquet file at `path`, with the columns named by
+/// `field_names`. The columns are converted with [`to_record_batch`], so
+/// missing cells are written as Parquet nulls. Overwrites the file if it
+/// already exists.
+#[cfg(feature = "parquet")]
+pub fn to_parquet(
+    columns: &[Column],
+    field_names: &[String],
+    path: &str,
+) -> parquet::errors::Result<()> {
+    use parquet::arrow::ArrowWriter;
+
+    let batch = to_record_batch(columns, field_names)?;
+    let mut writer =
+        ArrowWriter::try_new(File::create(path)?, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+impl From<Vec<Option<bool>>> for Column {
+    fn from(v: Vec<Option<bool>>) -> Column {
+        Column::Bool(v)
+    }
+}
+
+impl From<PackedBools> for Column {
+    fn from(v: PackedBools) -> Column {
+        Column::PackedBool(v)
+    }
+}
+
+impl From<Vec<Option<i64>>> for Column {
+    fn from(v: Vec<Option<i64>>) -> Column {
+        Column::Int(v)
+    }
+}
+
+impl From<Vec<Option<f64>>> for Column {
+    fn from(v: Vec<Option<f64>>) -> Column {
+        Column::Float(v)
+    }
+}
+
+impl From<Vec<Option<String>>> for Column {
+    fn from(v: Vec<Option<String>>) -> Column {
+        Column::String(v)
+    }
+}
+
+impl TryFrom<Column> for Vec<Option<bool>> {
+    type Error = WrongType;
+
+    fn try_from(c: Column) -> Result<Self, Self::Error> {
+        match c {
+            Column::Bool(col) => Ok(col),
+            Column::PackedBool(col) => Ok(col.to_vec()),
+            c => Err(WrongType::new(&c, "bool")),
+        }
+    }
+}
+
+impl TryFrom<Column> for Vec<Option<i64>> {
+    type Error = WrongType;
+
+    fn try_from(c: Column) -> Result<Self, Self::Error> {
+        match c {
+            Column::Int(col) => Ok(col),
+            Column::Int32(col) => {
+                Ok(col.into_iter().map(|v| v.map(i64::from)).collect())
+            }
+            c => Err(WrongType::new(&c, "int")),
+        }
+    }
+}
+
+impl TryFrom<Column> for Vec<Option<f64>> {
+    type Error = WrongType;
+
+    fn try_from(c: Column) -> Result<Self, Self::Error> {
+        match c {
+            Column::Float(col) => Ok(col),
+            c => Err(WrongType::new(&c, "float")),
+        }
+    }
+}
+
+impl TryFrom<Column> for Vec<Option<String>> {
+    type Error = WrongType;
+
+    fn try_from(c: Column) -> Result<Self, Self::Error> {
+        match c {
+            Column::String(col) => Ok(col),
+            c => Err(WrongType::new(&c, "string")),
+        }
+    }
+}
+
+/// Print the `Data` of a `Data` cell.
+/// The number for `Int`s and `float`s.
+/// 0 for `false`.
+/// 1 for `true`.
+/// A double quote delimited `String`.
+/// and "Missing Value" for missing data.
+impl fmt::Display for Data {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Data::String(s) => write!(f, "\"{}\"", s),
+            Data::Int(n) => write!(f, "{}", n),
+            Data::Float(fl) => write!(f, "{}", fl),
+            Data::Bool(true) => write!(f, "1"),
+            Data::Bool(false) => write!(f, "0"),
+            Data::Null => write!(f, "Missing Value"),
+        }
+    }
+}
+
+/// How a `Data::Bool` is written by [`Data::to_display_string`] and
+/// [`write_csv`].
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum BoolStyle {
+    /// `1` for `true` and `0` for `false`, the same as the `Display` impl.
+    #[default]
+    ZeroOne,
+    /// `true` and `false`.
+    TrueFalse,
+}
+
+/// How [`write_csv`] writes the cells of a DataFrame. The default writes
+/// missing values as empty fields, quotes `String`s only when needed and
+/// writes `Bool`s as `1` or `0`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct CsvOptions {
+    /// When `String`s are wrapped in double quotes.
+    pub quote_style: QuoteStyle,
+    /// What is written for a missing value, as it is without any quoting.
+    pub null_token: String,
+    /// How `Bool`s are written.
+    pub bool_style: BoolStyle,
+}
+
+/// When [`write_csv`] wraps `String`s in double quotes.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum QuoteStyle {
+    /// Only strings that contain a comma, a double quote or a line break, and
+    /// empty strings so they can be told apart from missing values.
+    #[default]
+    Minimal,
+    /// Every string.
+    Always,
+    /// No strings. Strings containing a comma, a double quote or a line break
+    /// are rejected, and empty strings look the same as missing values.
+    Never,
+}
+
+/// An error for when a `Data` value does not match the type of the `Column`
+/// it is used with.
+#[derive(PartialEq, Clone, Debug)]
+pub struct TypeMismatch {
+    /// The type of the `Column`.
+    pub expected: DataType,
+    /// The value that did not match it.
+    pub found: Data,
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Expected a value of type {:?} but found {:?}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for TypeMismatch {}
+
+/// An error for when a `Column` is converted into values of another type,
+/// such as by `Vec::<Option<bool>>::try_from` or
+/// [`Column::into_vec_int_filtered`].
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct WrongType {
+    /// The type of the `Column`, as named by [`Column::type_name`].
+    pub found: &'static str,
+    /// The type the `Column` was converted into, named the same way.
+    pub expected: &'static str,
+}
+
+impl WrongType {
+    fn new(c: &Column, expected: &'static str) -> Self {
+        WrongType {
+            found: c.type_name(),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for WrongType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The given column was of type {} and not {}",
+            self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for WrongType {}
+
+/// An error for when a `Data` value can not be converted to a `DataType` by
+/// [`Data::coerce_to`].
+#[derive(PartialEq, Clone, Debug)]
+pub struct CoerceError {
+    /// The type the value was converted to.
+    pub target: DataType,
+    /// The value that could not be converted.
+    pub value: Data,
+}
+
+impl fmt::Display for CoerceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Can not convert {:?} to {:?}", self.value, self.target)
+    }
+}
+
+impl std::error::Error for CoerceError {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_file() {
+        let schema = vec![DataType::String, DataType::Bool];
+        let config = ParserConfig::default();
+
+        let expected_col1 = Column::String(vec![
+            Some("1".to_string()),
+            Some("a".to_string()),
+            Some("1.2".to_string()),
+        ]);
+        let expected_col2 = Column::Bool(vec![Some(true), Some(false), None]);
+        let expected = vec![expected_col1, expected_col2];
+
+        // Simple case : first nd last line are not discarded
+        let mut input = Cursor::new(b"<1><1>\n<a><0>\n<1.2><>");
+        let parsed1: Vec<Column> =
+            read_chunk(schema.clone(), &mut input, 0, 26, &config).unwrap();
+        assert_eq!(parsed1, expected.clone());
+
+        // last line is discarded
+        let mut larger_input = Cursor::new(b"<1><1>\n<a><0>\n<1.2><>\n<no><1>");
+        let parsed2: Vec<Column> =
+            read_chunk(schema.clone(), &mut larger_input, 0, 27, &config)
+                .unwrap();
+        assert_eq!(parsed2, expected.clone());
+
+        // first line is discarded
+        let mut input_skipped_l1 =
+            Cursor::new(b"<b><1>\n<1><1>\n<a><0>\n<1.2><>");
+        let parsed3: Vec<Column> =
+            read_chunk(schema.clone(), &mut input_skipped_l1, 3, 26, &config)
+                .unwrap();
+        assert_eq!(parsed3, expected.clone());
+
+        // Invalid line is discarded
+        // Note since parsed lines with schema is correctly tested we do not
+        // need to test every possible way a line can be invalid here
+        let mut input_with_invalid =
+            Cursor::new(b"<1><1>\n<a><0>\n<c><1.2>\n<1.2><>");
+        let parsed4: Vec<Column> =
+            read_chunk(schema.clone(), &mut input_with_invalid, 0, 32, &config)
+                .unwrap();
+        assert_eq!(parsed4, expected.clone());
+    }
+
+    #[test]
+    fn test_sor_terator() {
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let mut sor_terator =
+            SorTerator::new("tests/sor_terator.sor", schema, 10);
+        let mut chunk = sor_terator.next();
+        assert_eq!(chunk.unwrap().first().unwrap().len(), 10);
+        chunk = sor_terator.next();
+        assert_eq!(chunk.unwrap().first().unwrap().len(), 5);
+        chunk = sor_terator.next();
         assert!(chunk.is_none());
     }
+
+    #[test]
+    fn test_sor_terator_sample() {
+        let file = "tests/sor_terator.sor";
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let parsed = from_file(file, schema.clone(), 0, usize::MAX, 1);
+        // the same rows are chosen no matter the chunk size
+        for &chunk_size in &[1, 4, 100] {
+            let sampled =
+                SorTerator::new(file, schema.clone(), chunk_size).sample(6, 3);
+            assert_eq!(sampled, sample_rows(&parsed, 6, 3));
+        }
+        // an oversized sample returns every row
+        let all = SorTerator::new(file, schema, 4).sample(usize::MAX, 3);
+        assert_eq!(all, parsed);
+    }
+
+    #[test]
+    fn test_sor_terator_peek() {
+        let file = "tests/sor_terator.sor";
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let expected: Vec<Vec<Column>> =
+            SorTerator::new(file, schema.clone(), 4).collect();
+        assert_eq!(expected.len(), 4);
+
+        let mut sor_terator = SorTerator::new(file, schema, 4);
+        let mut chunks = Vec::new();
+        loop {
+            let peeked = sor_terator.peek().cloned();
+            // peeking again returns the same chunk
+            assert_eq!(sor_terator.peek().cloned(), peeked);
+            let next = sor_terator.next();
+            assert_eq!(next, peeked);
+            match next {
+                Some(chunk) => chunks.push(chunk),
+                None => break,
+            }
+        }
+        assert_eq!(chunks, expected);
+        assert!(sor_terator.peek().is_none());
+    }
+
+    #[test]
+    fn test_multi_line_strings() {
+        let schema = vec![DataType::Int, DataType::String];
+        let input = b"<1><\"one\">\n<2><\"two\nlines\">\n<3><\"three\">\n";
+
+        // by default the split string is discarded
+        let config = ParserConfig::default();
+        let parsed = read_chunk(
+            schema.clone(),
+            &mut Cursor::new(input),
+            0,
+            input.len() + 1,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(parsed[0], Column::Int(vec![Some(1), Some(3)]));
+
+        let config = ParserConfig {
+            multi_line_strings: true,
+            ..ParserConfig::default()
+        };
+        let expected = vec![
+            Column::Int(vec![Some(1), Some(2), Some(3)]),
+            Column::String(vec![
+                Some("one".to_string()),
+                Some("two\nlines".to_string()),
+                Some("three".to_string()),
+            ]),
+        ];
+        let parsed = read_chunk(
+            schema.clone(),
+            &mut Cursor::new(input),
+            0,
+            input.len() + 1,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(parsed, expected);
+
+        let mut sor_terator =
+            SorTerator::with_config("tests/multi_line.sor", schema, 10, config);
+        assert_eq!(sor_terator.next(), Some(expected));
+        assert!(sor_terator.next().is_none());
+    }
+
+    #[test]
+    fn test_write_csv_quote_styles() {
+        let d = vec![
+            Column::String(vec![
+                Some("a,b".to_string()),
+                Some("c".to_string()),
+            ]),
+            Column::Int(vec![Some(1), None]),
+            Column::String(vec![Some("say \"hi\"".to_string()), None]),
+        ];
+        let csv = |quote_style| {
+            let mut out = Vec::new();
+            let options = CsvOptions {
+                quote_style,
+                ..CsvOptions::default()
+            };
+            write_csv(&d, &mut out, &options).map(|_| out)
+        };
+        assert_eq!(
+            String::from_utf8(csv(QuoteStyle::Minimal).unwrap()).unwrap(),
+            "\"a,b\",1,\"say \"\"hi\"\"\"\nc,,\n"
+        );
+        assert_eq!(
+            String::from_utf8(csv(QuoteStyle::Always).unwrap()).unwrap(),
+            "\"a,b\",1,\"say \"\"hi\"\"\"\n\"c\",,\n"
+        );
+        let err = csv(QuoteStyle::Never).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let d = vec![Column::String(vec![Some("".to_string()), None])];
+        let mut out = Vec::new();
+        write_csv(&d, &mut out, &CsvOptions::default()).unwrap();
+        assert_eq!(out, b"\"\"\n\n");
+    }
+
+    #[test]
+    fn test_write_csv_bool_style() {
+        let d = vec![
+            Column::Bool(vec![Some(true), None, Some(false)]),
+            Column::Int(vec![Some(1), Some(0), None]),
+        ];
+        let csv = |options: &CsvOptions| {
+            let mut out = Vec::new();
+            write_csv(&d, &mut out, options).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let null_as_na = |bool_style| CsvOptions {
+            null_token: "NA".to_string(),
+            bool_style,
+            ..CsvOptions::default()
+        };
+        assert_eq!(csv(&null_as_na(BoolStyle::ZeroOne)), "1,1\nNA,0\n0,NA\n");
+        assert_eq!(
+            csv(&null_as_na(BoolStyle::TrueFalse)),
+            "true,1\nNA,0\nfalse,NA\n"
+        );
+        assert_eq!(csv(&CsvOptions::default()), "1,1\n,0\n0,\n");
+    }
+
+    #[test]
+    fn test_to_string_column() {
+        let strings = |v: &[Option<&str>]| {
+            Column::String(v.iter().map(|s| s.map(String::from)).collect())
+        };
+        assert_eq!(
+            Column::Int(vec![Some(-12), None, Some(3)]).to_string_column(),
+            strings(&[Some("-12"), None, Some("3")])
+        );
+        assert_eq!(
+            Column::Int32(vec![Some(7)]).to_string_column(),
+            strings(&[Some("7")])
+        );
+        assert_eq!(
+            Column::Float(vec![Some(1.5), Some(2.0), None]).to_string_column(),
+            strings(&[Some("1.5"), Some("2"), None])
+        );
+        assert_eq!(
+            Column::Bool(vec![Some(true), None, Some(false)])
+                .to_string_column(),
+            strings(&[Some("1"), None, Some("0")])
+        );
+        let c = strings(&[Some("hi"), None]);
+        assert_eq!(c.to_string_column(), c);
+    }
+
+    #[test]
+    fn test_print_table() {
+        let columns = vec![
+            Column::Bool(vec![Some(true), None, Some(false)]),
+            Column::Int(vec![Some(12), Some(-3), None]),
+            Column::String(vec![
+                Some("hi".to_string()),
+                None,
+                Some("a string that is much too long".to_string()),
+            ]),
+        ];
+        let schema = vec![DataType::Bool, DataType::Int, DataType::String];
+        let mut out = Vec::new();
+        print_table(&columns, &schema, 10, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "+------+-----+----------------------+
+| BOOL | INT | STRING               |
++------+-----+----------------------+
+| 1    |  12 | hi                   |
+|      |  -3 |                      |
+| 0    |     | a string that is ... |
++------+-----+----------------------+
+"
+        );
+
+        let mut out = Vec::new();
+        print_table(&columns, &schema, 1, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "+------+-----+--------+
+| BOOL | INT | STRING |
++------+-----+--------+
+| 1    |  12 | hi     |
++------+-----+--------+
+(2 more rows)
+"
+        );
+    }
+
+    #[test]
+    fn test_sor_terator_lenient_bools() {
+        let file = "tests/lenient_bool.sor";
+        let schema = vec![DataType::Bool, DataType::String];
+        let mut strict = SorTerator::new(file, schema.clone(), 10);
+        assert_eq!(
+            strict.next().unwrap()[0],
+            Column::Bool(vec![Some(true), Some(false)])
+        );
+        assert_eq!(strict.stats().nulled_bools, 0);
+
+        let config = ParserConfig {
+            lenient_bools: true,
+            ..ParserConfig::default()
+        };
+        let mut lenient = SorTerator::with_config(file, schema, 10, config);
+        assert_eq!(
+            lenient.next().unwrap()[0],
+            Column::Bool(vec![Some(true), None, Some(false)])
+        );
+        assert_eq!(lenient.stats().nulled_bools, 1);
+    }
+
+    #[test]
+    fn test_from_file_streaming() {
+        // formats every row of `columns` so rows can be compared as a
+        // multiset regardless of which thread parsed them
+        fn rows(columns: &[Column]) -> Vec<String> {
+            (0..columns.first().map_or(0, Column::len))
+                .map(|row_idx| {
+                    (0..columns.len())
+                        .map(|col_idx| {
+                            format!("{:?}", get(columns, col_idx, row_idx))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect()
+        }
+
+        let file = "tests/sor_terator.sor";
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let mut expected =
+            rows(&from_file(file, schema.clone(), 0, usize::MAX, 1));
+        let mut streamed = Vec::new();
+        let mut parts = 0;
+        for part in from_file_streaming(file, schema, 4) {
+            streamed.extend(rows(&part.unwrap()));
+            parts += 1;
+        }
+        assert_eq!(parts, 4);
+        expected.sort();
+        streamed.sort();
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn test_apply_mask() {
+        let d = vec![
+            Column::Int(vec![Some(1), None, Some(3), Some(4)]),
+            Column::String(vec![
+                Some(String::from("a")),
+                Some(String::from("b")),
+                None,
+                Some(String::from("d")),
+            ]),
+        ];
+        let masked = apply_mask(&d, &[true, false, true, false]).unwrap();
+        assert_eq!(
+            masked,
+            vec![
+                Column::Int(vec![Some(1), Some(3)]),
+                Column::String(vec![Some(String::from("a")), None]),
+            ]
+        );
+        assert_eq!(apply_mask(&d, &[false; 4]).unwrap()[0].len(), 0);
+        assert_eq!(apply_mask(&d, &[true; 4]).unwrap(), d);
+        assert!(apply_mask(&d, &[true; 3]).is_err());
+        assert!(apply_mask(&d, &[true; 5]).is_err());
+    }
+
+    #[test]
+    fn test_projection() {
+        let file = "tests/sor_terator.sor";
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let full = from_file(file, schema.clone(), 0, usize::MAX, 1);
+        for projection in &[vec![0, 3], vec![3, 1], vec![2], vec![1, 1]] {
+            let config = ParserConfig {
+                projection: Some(projection.clone()),
+                ..ParserConfig::default()
+            };
+            let expected = select(&full, projection);
+            for num_threads in 1..4 {
+                let projected = from_file_with_config(
+                    file,
+                    schema.clone(),
+                    0,
+                    usize::MAX,
+                    num_threads,
+                    &config,
+                );
+                assert_eq!(projected, expected);
+            }
+            let chunks: Vec<Vec<Column>> =
+                SorTerator::with_config(file, schema.clone(), 4, config)
+                    .collect();
+            assert_eq!(chunks[0], select(&head(&full, 4), projection));
+        }
+    }
+
+    #[test]
+    fn test_from_data_iter() {
+        let ints = vec![Data::Null, Data::Int(1), Data::Null, Data::Int(-3)];
+        assert_eq!(
+            Column::from_data_iter(ints.into_iter()),
+            Ok(Column::Int(vec![None, Some(1), None, Some(-3)]))
+        );
+        let strings = vec![Data::String(String::from("a")), Data::Null];
+        assert_eq!(
+            Column::from_data_iter(strings.into_iter()),
+            Ok(Column::String(vec![Some(String::from("a")), None]))
+        );
+
+        let nulls = vec![Data::Null, Data::Null];
+        assert_eq!(
+            Column::from_data_iter(nulls.into_iter()),
+            Ok(Column::Bool(vec![None, None]))
+        );
+        assert_eq!(
+            Column::from_data_iter(std::iter::empty()),
+            Ok(Column::Bool(vec![]))
+        );
+
+        let mixed = vec![Data::Null, Data::Float(1.5), Data::Int(2)];
+        assert_eq!(
+            Column::from_data_iter(mixed.into_iter()),
+            Err(TypeMismatch {
+                expected: DataType::Float,
+                found: Data::Int(2),
+            })
+        );
+    }
+
+    #[test]
+    fn test_len_ends_mid_line() {
+        // the rows of tests/offsets.sor end at these byte offsets, the last
+        // one at the end of the file without a newline
+        let row_ends = [9, 28, 35];
+        let schema = vec![DataType::Int, DataType::String];
+        for len in 0..=40 {
+            let expected = row_ends.iter().filter(|&&end| end <= len).count();
+            for num_threads in 1..=4 {
+                let parsed = from_file(
+                    "tests/offsets.sor",
+                    schema.clone(),
+                    0,
+                    len,
+                    num_threads,
+                );
+                assert_eq!(
+                    parsed[0].len(),
+                    expected,
+                    "len {} with {} threads",
+                    len,
+                    num_threads
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_type_name() {
+        assert_eq!(Column::Bool(vec![]).type_name(), "bool");
+        assert_eq!(Column::Int(vec![]).type_name(), "int");
+        assert_eq!(Column::Int32(vec![]).type_name(), "int");
+        assert_eq!(Column::Float(vec![]).type_name(), "float");
+        assert_eq!(Column::String(vec![]).type_name(), "string");
+        let err = Vec::<Option<bool>>::try_from(Column::Float(vec![]));
+        assert_eq!(
+            err,
+            Err(WrongType {
+                found: "float",
+                expected: "bool"
+            })
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "The given column was of type float and not bool"
+        );
+    }
+
+    #[test]
+    fn test_truncate_and_extend_nulls() {
+        let mut col = Column::Int(vec![Some(1), None, Some(3)]);
+        col.truncate(2);
+        assert_eq!(col, Column::Int(vec![Some(1), None]));
+        col.truncate(5);
+        assert_eq!(col.len(), 2);
+        col.truncate(0);
+        assert!(col.is_empty());
+
+        let mut col = Column::String(vec![Some(String::from("a"))]);
+        col.extend_nulls(2);
+        assert_eq!(
+            col,
+            Column::String(vec![Some(String::from("a")), None, None])
+        );
+        col.extend_nulls(0);
+        assert_eq!(col.len(), 3);
+
+        let mut d = vec![
+            Column::Bool(vec![Some(true)]),
+            Column::Float(vec![Some(1.0), Some(2.0), None]),
+            Column::Int32(vec![]),
+        ];
+        align_columns(&mut d);
+        assert_eq!(
+            d,
+            vec![
+                Column::Bool(vec![Some(true), None, None]),
+                Column::Float(vec![Some(1.0), Some(2.0), None]),
+                Column::Int32(vec![None, None, None]),
+            ]
+        );
+        align_columns(&mut []);
+    }
+
+    #[test]
+    fn test_get_row() {
+        let schema = crate::schema::infer_schema("tests/2.sor").unwrap();
+        let d = from_file("tests/2.sor", schema, 0, usize::MAX, 1);
+        let len = d[0].len();
+        for row_idx in 0..len {
+            let expected: Vec<Data> =
+                (0..d.len()).map(|col| get(&d, col, row_idx)).collect();
+            assert_eq!(get_row(&d, row_idx), Some(expected));
+        }
+        assert_eq!(get_row(&d, len), None);
+        assert_eq!(get_row(&[], 0), None);
+
+        let ragged = vec![
+            Column::Int(vec![Some(1), Some(2)]),
+            Column::Bool(vec![None]),
+        ];
+        assert_eq!(get_row(&ragged, 0), Some(vec![Data::Int(1), Data::Null]));
+        assert_eq!(get_row(&ragged, 1), None);
+    }
+
+    #[test]
+    fn test_iter_indices_where() {
+        let c = Column::Int(vec![Some(3), None, Some(10), Some(-2), Some(7)]);
+        let threshold = 5;
+        let above = |d: &Data| matches!(d, Data::Int(n) if *n > threshold);
+        let indices: Vec<usize> = c.iter_indices_where(above).collect();
+        assert_eq!(indices, vec![2, 4]);
+
+        let nulls: Vec<usize> =
+            c.iter_indices_where(|d: &Data| *d == Data::Null).collect();
+        assert_eq!(nulls, vec![1]);
+
+        let mut mask = vec![false; c.len()];
+        for i in c.iter_indices_where(above) {
+            mask[i] = true;
+        }
+        assert_eq!(
+            apply_mask(&[c], &mask).unwrap(),
+            vec![Column::Int(vec![Some(10), Some(7)])]
+        );
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let bools = Column::Bool(vec![Some(true); 100]);
+        let strings = Column::String(vec![Some(String::from("hello")); 100]);
+        assert!(strings.memory_usage() > bools.memory_usage());
+        assert!(
+            bools.memory_usage() >= 100 * std::mem::size_of::<Option<bool>>()
+        );
+        assert_eq!(
+            dataframe_memory(&[bools.clone(), strings.clone()]),
+            bools.memory_usage() + strings.memory_usage()
+        );
+        assert_eq!(dataframe_memory(&[]), 0);
+    }
+
+    #[test]
+    fn test_sample_rows() {
+        let d = vec![
+            Column::Int((0..100).map(Some).collect()),
+            Column::Bool((0..100).map(|n| Some(n % 2 == 0)).collect()),
+        ];
+        let sampled = sample_rows(&d, 10, 7);
+        assert_eq!(sampled, sample_rows(&d, 10, 7));
+        assert_ne!(sampled, sample_rows(&d, 10, 8));
+        let ints: Vec<i64> = match &sampled[0] {
+            Column::Int(c) => c.iter().map(|n| n.unwrap()).collect(),
+            _ => unreachable!(),
+        };
+        assert_eq!(ints.len(), 10);
+        // rows stay in order and both columns come from the same rows
+        assert!(ints.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            sampled[1],
+            Column::Bool(ints.iter().map(|n| Some(n % 2 == 0)).collect())
+        );
+
+        assert_eq!(sample_rows(&d, 1000, 7), d);
+        assert_eq!(sample_rows(&d, usize::MAX, 7), d);
+        assert_eq!(reservoir_sample(0..10, usize::MAX, 7).len(), 10);
+        assert_eq!(sample_rows(&d, 0, 7)[0].len(), 0);
+
+        let mut counts = [0; 10];
+        for seed in 0..2000 {
+            for n in reservoir_sample(0..10, 3, seed) {
+                counts[n] += 1;
+            }
+        }
+        // every item is chosen about 600 times
+        assert!(counts.iter().all(|&c| c > 450 && c < 750), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_values_eq_ignoring_nulls() {
+        let original = Column::Int(vec![Some(1), None, Some(3), None]);
+        let filled = original.fill_null(&Data::Int(0)).unwrap();
+        assert_ne!(original, filled);
+        assert!(original.values_eq_ignoring_nulls(&filled));
+        assert!(filled.values_eq_ignoring_nulls(&original));
+
+        // nulls in different places still match
+        let other = Column::Int32(vec![None, Some(2), Some(3), None]);
+        assert!(original.values_eq_ignoring_nulls(&other));
+
+        let different = Column::Int(vec![Some(1), None, Some(4), None]);
+        assert!(!original.values_eq_ignoring_nulls(&different));
+        let shorter = Column::Int(vec![Some(1), None, Some(3)]);
+        assert!(!original.values_eq_ignoring_nulls(&shorter));
+        let floats = Column::Float(vec![Some(1.0), None, Some(3.0), None]);
+        assert!(!original.values_eq_ignoring_nulls(&floats));
+    }
+
+    #[test]
+    fn test_byte_offset_row_round_trip() {
+        let file = "tests/offsets.sor";
+        // rows start at these offsets, including the blank and invalid rows
+        let starts = [0, 9, 10, 15, 28];
+        for (row, start) in starts.iter().enumerate() {
+            assert_eq!(row_to_byte_offset(file, row).unwrap(), *start);
+            assert_eq!(byte_offset_to_row(file, *start).unwrap(), row);
+        }
+        // the newline ending a row and bytes in the middle of a row
+        assert_eq!(byte_offset_to_row(file, 8).unwrap(), 0);
+        assert_eq!(byte_offset_to_row(file, 20).unwrap(), 3);
+        assert_eq!(byte_offset_to_row(file, 34).unwrap(), 4);
+
+        assert!(byte_offset_to_row(file, 35).is_err());
+        assert!(row_to_byte_offset(file, 5).is_err());
+
+        // rows past the first buffer of a large file
+        let offset = row_to_byte_offset("tests/3.sor", 100_000).unwrap();
+        assert_eq!(byte_offset_to_row("tests/3.sor", offset).unwrap(), 100_000);
+        assert_eq!(
+            byte_offset_to_row("tests/3.sor", offset - 1).unwrap(),
+            99_999
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_map_rows() {
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let columns =
+            from_file("tests/sor_terator.sor", schema, 0, usize::MAX, 1);
+        let f = |row: &[Data]| format!("{:?}", row);
+        let sequential: Vec<String> = rows(&columns).map(|r| f(&r)).collect();
+        assert_eq!(sequential.len(), 15);
+        assert_eq!(par_map_rows(&columns, f), sequential);
+        assert_eq!(par_map_rows(&[], f), Vec::<String>::new());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_for_each_chunk() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let schema = vec![
+            DataType::Bool,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+        ];
+        let sequential: usize =
+            SorTerator::new("tests/sor_terator.sor", schema.clone(), 4)
+                .map(|chunk| chunk[0].len())
+                .sum();
+
+        let parallel = AtomicUsize::new(0);
+        par_for_each_chunk(
+            SorTerator::new("tests/sor_terator.sor", schema, 4),
+            |chunk| {
+                parallel.fetch_add(chunk[0].len(), Ordering::SeqCst);
+            },
+        );
+        assert_eq!(parallel.into_inner(), sequential);
+        assert_eq!(sequential, 15);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_sor_terator_parallel() {
+        let configs = vec![
+            ("tests/sor_terator.sor", ParserConfig::default()),
+            ("tests/invalid_row.sor", ParserConfig::default()),
+            (
+                "tests/invalid_row.sor",
+                ParserConfig {
+                    keep_invalid_rows: true,
+                    ..ParserConfig::default()
+                },
+            ),
+            (
+                "tests/multi_line.sor",
+                ParserConfig {
+                    multi_line_strings: true,
+                    ..ParserConfig::default()
+                },
+            ),
+            (
+                "tests/comments.sor",
+                ParserConfig {
+                    comment_prefix: Some(b'#'),
+                    ..ParserConfig::default()
+                },
+            ),
+        ];
+        for (file, config) in configs {
+            let schema =
+                crate::schema::infer_schema_with_config(file, &config).unwrap();
+            for chunk_size in &[1, 2, 3, 10] {
+                let serial: Vec<Vec<Column>> = SorTerator::with_config(
+                    file,
+                    schema.clone(),
+                    *chunk_size,
+                    config.clone(),
+                )
+                .collect();
+                let parallel: Vec<Vec<Column>> = SorTerator::with_config(
+                    file,
+                    schema.clone(),
+                    *chunk_size,
+                    config.clone(),
+                )
+                .parallel(true)
+                .collect();
+                assert!(!serial.is_empty());
+                assert_eq!(serial, parallel, "{} {}", file, chunk_size);
+            }
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_to_parquet() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::{Float64Type, Int64Type};
+        use arrow_schema::DataType as ArrowType;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let columns = vec![
+            Column::Bool(vec![None, Some(true), Some(false)]),
+            Column::Int(vec![Some(-1), None, Some(i64::MAX)]),
+            Column::Int32(vec![Some(7), Some(-7), None]),
+            Column::Float(vec![None, Some(2.5), Some(-0.5)]),
+            Column::String(vec![Some("hi".to_string()), None, Some("".into())]),
+        ];
+        let names: Vec<String> = ["b", "i", "n", "f", "s"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("columns.parquet");
+        let path = path.to_str().unwrap();
+        to_parquet(&columns, &names, path).unwrap();
+
+        let mut reader =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap();
+        let batch = reader.next().unwrap().unwrap();
+        assert!(reader.next().is_none());
+        let read_names: Vec<String> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(read_names, names);
+        // narrow ints are widened like the rest of the ints
+        assert_eq!(batch.column(2).data_type(), &ArrowType::Int64);
+        let read = vec![
+            Column::Bool(batch.column(0).as_boolean().iter().collect()),
+            Column::Int(
+                batch.column(1).as_primitive::<Int64Type>().iter().collect(),
+            ),
+            Column::Int(
+                batch.column(2).as_primitive::<Int64Type>().iter().collect(),
+            ),
+            Column::Float(
+                batch
+                    .column(3)
+                    .as_primitive::<Float64Type>()
+                    .iter()
+                    .collect(),
+            ),
+            Column::String(
+                batch
+                    .column(4)
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|s| s.map(String::from))
+                    .collect(),
+            ),
+        ];
+        assert_eq!(read, columns);
+
+        assert!(to_parquet(&columns, &names[..4], path).is_err());
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_save_load_dataframe() {
+        let file = "tests/2.sor";
+        let schema = crate::schema::infer_schema(file).unwrap();
+        let dataframe = from_file(file, schema, 0, usize::MAX, 8);
+        assert_eq!(get(&dataframe, 1, 0), Data::Null);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("2.bin");
+        let path = path.to_str().unwrap();
+        save_dataframe(&dataframe, path).unwrap();
+        assert_eq!(load_dataframe(path).unwrap(), dataframe);
+
+        // nulls in every column type survive the round trip
+        let nulls = vec![
+            Column::Bool(vec![None, Some(true)]),
+            Column::Int(vec![Some(-1), None]),
+            Column::Float(vec![None, Some(2.5)]),
+            Column::String(vec![Some("hi".to_string()), None]),
+        ];
+        save_dataframe(&nulls, path).unwrap();
+        assert_eq!(load_dataframe(path).unwrap(), nulls);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_load_original_variant_order() {
+        // `Column` as it was before any variants were added
+        #[derive(Serialize)]
+        enum OriginalColumn {
+            Int(Vec<Option<i64>>),
+            Bool(Vec<Option<bool>>),
+            Float(Vec<Option<f64>>),
+            String(Vec<Option<String>>),
+        }
+        let original = vec![
+            OriginalColumn::Int(vec![Some(3), None]),
+            OriginalColumn::Bool(vec![None, Some(true)]),
+            OriginalColumn::Float(vec![Some(2.5), None]),
+            OriginalColumn::String(vec![None, Some("hi".to_string())]),
+        ];
+        let bytes = bincode::serialize(&original).unwrap();
+        let loaded: Vec<Column> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(
+            loaded,
+            vec![
+                Column::Int(vec![Some(3), None]),
+                Column::Bool(vec![None, Some(true)]),
+                Column::Float(vec![Some(2.5), None]),
+                Column::String(vec![None, Some("hi".to_string())]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let c1 = Column::Float(vec![Some(1.0), None, Some(f64::NAN)]);
+        let within = Column::Float(vec![Some(1.05), None, Some(f64::NAN)]);
+        let beyond = Column::Float(vec![Some(1.2), None, Some(f64::NAN)]);
+        assert!(c1.approx_eq(&within, 0.1));
+        assert!(!c1.approx_eq(&beyond, 0.1));
+        assert!(!c1.approx_eq(&Column::Float(vec![Some(1.0), None]), 0.1));
+        assert!(!c1.approx_eq(&Column::Int(vec![Some(1), None, None]), 0.1));
+
+        let i1 = Column::Int(vec![Some(1), None]);
+        assert!(i1.approx_eq(&Column::Int(vec![Some(1), None]), 0.1));
+        assert!(!i1.approx_eq(&Column::Int(vec![Some(2), None]), 5.0));
+    }
+
+    #[test]
+    fn test_keep_invalid_rows() {
+        let schema = vec![DataType::String, DataType::Bool];
+        let config = ParserConfig {
+            keep_invalid_rows: true,
+            ..ParserConfig::default()
+        };
+        let expected = vec![
+            Column::String(vec![
+                Some("1".to_string()),
+                Some("a".to_string()),
+                None,
+                Some("1.2".to_string()),
+            ]),
+            Column::Bool(vec![Some(true), Some(false), None, None]),
+        ];
+
+        let mut input = Cursor::new(b"<1><1>\n<a><0>\n<c><1.2>\n<1.2><>");
+        let parsed =
+            read_chunk(schema.clone(), &mut input, 0, 33, &config).unwrap();
+        assert_eq!(parsed, expected);
+
+        let mut sor_terator = SorTerator::with_config(
+            "tests/invalid_row.sor",
+            schema,
+            10,
+            config,
+        );
+        assert_eq!(sor_terator.next(), Some(expected));
+        assert!(sor_terator.next().is_none());
+    }
+
+    #[test]
+    fn test_from_file_latin1() {
+        let schema = vec![DataType::Int, DataType::String];
+        let parsed =
+            from_file_latin1("tests/latin1.sor", schema, 0, usize::MAX, 8);
+        assert_eq!(
+            parsed,
+            vec![
+                Column::Int(vec![Some(1), Some(2)]),
+                Column::String(vec![
+                    Some("café".to_string()),
+                    Some("plain".to_string())
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_sorted() {
+        let sorted = Column::Int(vec![Some(-1), None, Some(2), Some(2), None]);
+        assert!(sorted.is_sorted());
+        let unsorted = Column::Int(vec![Some(3), None, Some(2)]);
+        assert!(!unsorted.is_sorted());
+        let with_nan = Column::Float(vec![Some(1.0), Some(f64::NAN)]);
+        assert!(!with_nan.is_sorted());
+        assert!(Column::Float(vec![Some(1.0), None, Some(1.5)]).is_sorted());
+        assert!(Column::String(vec![None, None]).is_sorted());
+    }
+
+    #[test]
+    fn test_fill_null() {
+        let ints = Column::Int(vec![Some(1), None, Some(3), None]);
+        assert_eq!(
+            ints.fill_null(&Data::Int(0)),
+            Ok(Column::Int(vec![Some(1), Some(0), Some(3), Some(0)]))
+        );
+        assert_eq!(ints.fill_null(&Data::Null), Ok(ints.clone()));
+        assert_eq!(
+            ints.fill_null(&Data::String("0".to_string())),
+            Err(TypeMismatch {
+                expected: DataType::Int,
+                found: Data::String("0".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_as_f64() {
+        assert_eq!(Data::Int(-3).as_f64(), Some(-3.0));
+        assert_eq!(Data::Float(2.5).as_f64(), Some(2.5));
+        assert_eq!(Data::String("1".to_string()).as_f64(), None);
+        assert_eq!(Data::Bool(true).as_f64(), None);
+        assert_eq!(Data::Null.as_f64(), None);
+    }
+
+    #[test]
+    fn test_line_reader() {
+        let input = b"<1>\n\n<2><3>\n<4>";
+        let mut lines = LineReader::new(Cursor::new(&input[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"<1>"[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b""[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"<2><3>"[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"<4>"[..]));
+        assert_eq!(lines.next_line().unwrap(), None);
+
+        // lines longer than the buffer are still returned whole
+        let long_line = vec![b'a'; 200 * 1024];
+        let mut input = long_line.clone();
+        input.extend_from_slice(b"\n<1>\n");
+        let mut lines = LineReader::new(Cursor::new(input));
+        assert_eq!(lines.next_line().unwrap(), Some(&long_line[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"<1>"[..]));
+        assert_eq!(lines.next_line().unwrap(), None);
+    }
+
+    #[test]
+    fn test_max_line_bytes() {
+        let long_line = vec![b'a'; 200 * 1024];
+        let mut input = b"<1>\n".to_vec();
+        input.extend_from_slice(&long_line);
+        input.extend_from_slice(b"\n<2>\n");
+
+        let mut lines =
+            LineReader::with_max_line(Cursor::new(input.clone()), Some(1024));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"<1>"[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b""[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"<2>"[..]));
+        assert_eq!(lines.next_line().unwrap(), None);
+
+        let schema = vec![DataType::Int];
+        let config = ParserConfig {
+            max_line_bytes: Some(1024),
+            ..ParserConfig::default()
+        };
+        let len = input.len();
+        let parsed = read_chunk(
+            schema.clone(),
+            &mut Cursor::new(input.clone()),
+            0,
+            len,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(parsed, vec![Column::Int(vec![Some(1), Some(2)])]);
+
+        let config = ParserConfig {
+            keep_invalid_rows: true,
+            ..config
+        };
+        let parsed =
+            read_chunk(schema, &mut Cursor::new(input), 0, len, &config)
+                .unwrap();
+        assert_eq!(parsed, vec![Column::Int(vec![Some(1), None, Some(2)])]);
+
+        // the limit applies to the joined lines of a multi-line string, even
+        // though each of its lines is short
+        let mut input = b"<\"a\nb\"> <1>\n<\"x".to_vec();
+        for _ in 0..100 {
+            input.extend_from_slice(b"\nyyyyyyyyyyyyyyyyyyyy");
+        }
+        input.extend_from_slice(b"\"> <2>\n<z> <3>\n");
+        let schema = vec![DataType::String, DataType::Int];
+        let config = ParserConfig {
+            max_line_bytes: Some(1024),
+            multi_line_strings: true,
+            ..ParserConfig::default()
+        };
+        let expected = vec![
+            Column::String(vec![
+                Some(String::from("a\nb")),
+                Some(String::from("z")),
+            ]),
+            Column::Int(vec![Some(1), Some(3)]),
+        ];
+        let len = input.len();
+        let parsed = read_chunk(
+            schema.clone(),
+            &mut Cursor::new(input.clone()),
+            0,
+            len,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(from_reader(&input[..], schema, &config).unwrap(), expected);
+
+        // a limit the line fits in parses it as usual
+        let mut buffer = Vec::new();
+        let mut reader = Cursor::new(&b"<1>\n<2>"[..]);
+        assert_eq!(
+            read_line_capped(&mut reader, &mut buffer, Some(3)).unwrap(),
+            (4, false)
+        );
+        assert_eq!(buffer, b"<1>\n");
+        buffer.clear();
+        assert_eq!(
+            read_line_capped(&mut reader, &mut buffer, Some(2)).unwrap(),
+            (3, true)
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let file = "tests/long_rows.sor";
+        let schema = crate::schema::infer_schema(file).unwrap();
+        let config = ParserConfig::default();
+        let expected = from_file_with_config(
+            file,
+            schema.clone(),
+            0,
+            usize::MAX,
+            1,
+            &config,
+        );
+        let parsed =
+            from_reader(File::open(file).unwrap(), schema.clone(), &config)
+                .unwrap();
+        assert_eq!(parsed, expected);
+
+        let config = ParserConfig {
+            multi_line_strings: true,
+            comment_prefix: Some(b'#'),
+            ..ParserConfig::default()
+        };
+        let input = &b"# a comment\n<1> <\"a\nb\">\n<2> <c>"[..];
+        let parsed =
+            from_reader(input, vec![DataType::Int, DataType::String], &config)
+                .unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                Column::Int(vec![Some(1), Some(2)]),
+                Column::String(vec![
+                    Some("a\nb".to_string()),
+                    Some("c".to_string())
+                ]),
+            ]
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_from_zstd_file() {
+        let schema =
+            crate::schema::infer_schema("tests/long_rows.sor").unwrap();
+        let config = ParserConfig::default();
+        let expected = from_file_with_config(
+            "tests/long_rows.sor",
+            schema.clone(),
+            0,
+            usize::MAX,
+            1,
+            &config,
+        );
+        let parsed =
+            from_zstd_file("tests/long_rows.sor.zst", schema.clone(), &config)
+                .unwrap();
+        assert_eq!(parsed, expected);
+        assert!(!parsed[0].is_empty());
+
+        // the plaintext file is not valid zstd
+        assert!(from_zstd_file("tests/long_rows.sor", schema, &config).is_err());
+    }
+
+    #[test]
+    fn test_coerce_to() {
+        let ok = |value: Data, target: DataType| value.coerce_to(&target);
+        assert_eq!(ok(Data::Int(5), DataType::Float), Ok(Data::Float(5.0)));
+        assert_eq!(ok(Data::Int(5), DataType::Int), Ok(Data::Int(5)));
+        assert_eq!(ok(Data::Int(1), DataType::Bool), Ok(Data::Bool(true)));
+        assert_eq!(ok(Data::Float(2.9), DataType::Int), Ok(Data::Int(2)));
+        assert_eq!(ok(Data::Float(-2.9), DataType::Int), Ok(Data::Int(-2)));
+        assert_eq!(ok(Data::Bool(true), DataType::Int), Ok(Data::Int(1)));
+        assert_eq!(
+            ok(Data::Bool(false), DataType::Float),
+            Ok(Data::Float(0.0))
+        );
+        assert_eq!(ok(Data::string(" 12 "), DataType::Int), Ok(Data::Int(12)));
+        assert_eq!(
+            ok(Data::string("1.5"), DataType::Float),
+            Ok(Data::Float(1.5))
+        );
+        assert_eq!(
+            ok(Data::string("0"), DataType::Bool),
+            Ok(Data::Bool(false))
+        );
+        assert_eq!(ok(Data::Int(-3), DataType::String), Ok(Data::string("-3")));
+        assert_eq!(
+            ok(Data::Bool(true), DataType::String),
+            Ok(Data::string("1"))
+        );
+        assert_eq!(
+            ok(Data::string("hi"), DataType::String),
+            Ok(Data::string("hi"))
+        );
+        for target in &[DataType::String, DataType::Float, DataType::Int] {
+            assert_eq!(Data::Null.coerce_to(target), Ok(Data::Null));
+        }
+
+        assert_eq!(
+            ok(Data::string("twelve"), DataType::Int),
+            Err(CoerceError {
+                target: DataType::Int,
+                value: Data::string("twelve"),
+            })
+        );
+        assert!(ok(Data::Int(2), DataType::Bool).is_err());
+        assert!(ok(Data::Float(1.0), DataType::Bool).is_err());
+        assert!(ok(Data::Float(f64::NAN), DataType::Int).is_err());
+        assert!(ok(Data::Float(1e19), DataType::Int).is_err());
+        assert!(ok(Data::string("2"), DataType::Bool).is_err());
+        for s in &["nan", "NaN", "inf", "-infinity", "1e400"] {
+            assert!(ok(Data::string(s), DataType::Float).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_split_work_bounds() {
+        let file = "tests/sor_terator.sor";
+        let file_len = std::fs::metadata(file).unwrap().len() as usize;
+        // a huge `len` is the same as reading to the end of the file
+        assert_eq!(
+            split_work(file, 10, usize::MAX - 1, 3),
+            split_work(file, 10, usize::MAX, 3)
+        );
+        // and starting past the end of the file reads nothing
+        for from in &[file_len, file_len + 10] {
+            let work = split_work(file, *from, usize::MAX, 2);
+            assert!(work.iter().all(|w| w.1 == 0));
+            let schema = vec![DataType::Bool];
+            assert_eq!(
+                from_file(file, schema, *from, usize::MAX, 2),
+                vec![Column::Bool(Vec::new())]
+            );
+        }
+    }
+
+    #[test]
+    fn test_dry_run() {
+        for file in &["tests/sor_terator.sor", "tests/no_trailing_newline.sor"]
+        {
+            let schema = crate::schema::infer_schema(file).unwrap();
+            let report = dry_run(file, 0, usize::MAX, 3).unwrap();
+            assert_eq!(report.schema, schema);
+            assert_eq!(report.work, split_work(file, 0, usize::MAX, 3));
+            let parsed = from_file(file, schema, 0, usize::MAX, 3);
+            assert_eq!(report.estimated_rows, parsed[0].len());
+        }
+
+        // the first lines of tests/sor_terator.sor are 19 bytes long, so
+        // this starts in the third line, which is thrown away, and reads the
+        // next four lines, since the fifth ends past byte 145
+        let file = "tests/sor_terator.sor";
+        let report = dry_run(file, 45, 100, 2).unwrap();
+        assert_eq!(report.work.len(), 2);
+        assert_eq!(report.estimated_rows, 4);
+        let parsed = from_file(file, report.schema, 45, 100, 2);
+        assert_eq!(parsed[0].len(), 4);
+
+        assert!(dry_run("tests/missing.sor", 0, usize::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let col = Column::Int((0..7).map(Some).collect());
+        let chunks: Vec<Column> = col.chunks(3).collect();
+        let lens: Vec<usize> = chunks.iter().map(Column::len).collect();
+        assert_eq!(lens, vec![3, 3, 1]);
+        assert_eq!(chunks[2], Column::Int(vec![Some(6)]));
+        let packed = Column::PackedBool(
+            vec![Some(true), None, Some(false)].into_iter().collect(),
+        );
+        assert_eq!(packed.chunks(2).nth(1), Some(packed.slice(2, 3)));
+        assert_eq!(Column::Float(vec![]).chunks(3).count(), 0);
+
+        let d = vec![
+            col,
+            Column::String((0..7).map(|i| Some(i.to_string())).collect()),
+        ];
+        let chunks: Vec<Vec<Column>> = chunk_rows(&d, 3).collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[1], slice_rows(&d, 3, 6));
+        assert_eq!(
+            chunks[2],
+            vec![
+                Column::Int(vec![Some(6)]),
+                Column::String(vec![Some("6".to_string())])
+            ]
+        );
+        assert_eq!(chunk_rows(&[], 3).count(), 0);
+    }
+
+    #[test]
+    fn test_from_file_with_progress() {
+        // tests/long_rows.sor has 50 rows and 48680 bytes
+        let file = "tests/long_rows.sor";
+        let schema = crate::schema::infer_schema(file).unwrap();
+        let config = ParserConfig::default();
+        let expected = from_file(file, schema.clone(), 0, usize::MAX, 3);
+
+        // a step of 1 byte reports after every row
+        let mut calls = Vec::new();
+        let work = split_work(file, 0, usize::MAX, 1);
+        let parsed = parse_work(
+            file,
+            schema.clone(),
+            work,
+            &config,
+            1,
+            |done, total| calls.push((done, total)),
+        );
+        assert_eq!(parsed, expected);
+        assert_eq!(calls.len(), 50);
+        assert_eq!(calls.last(), Some(&(48680, 48680)));
+        assert!(calls.windows(2).all(|w| w[0].0 < w[1].0));
+
+        // the step is larger than the file, so each thread reports once
+        let mut calls = Vec::new();
+        let parsed = from_file_with_progress(
+            file,
+            schema,
+            0,
+            usize::MAX,
+            3,
+            &config,
+            |done, total| calls.push((done, total)),
+        );
+        assert_eq!(parsed, expected);
+        assert_eq!(calls.len(), 3);
+        let (done, total) = calls[2];
+        assert_eq!(done, total);
+    }
+
+    #[test]
+    fn test_to_split_repr() {
+        fn join<T>(values: Vec<T>, validity: Vec<bool>) -> Vec<Option<T>> {
+            values
+                .into_iter()
+                .zip(validity)
+                .map(|(v, valid)| if valid { Some(v) } else { None })
+                .collect()
+        }
+
+        let ints = vec![Some(-3), None, Some(i64::MAX)];
+        let (values, validity) =
+            Column::Int(ints.clone()).to_split_repr_int().unwrap();
+        assert_eq!(values, vec![-3, 0, i64::MAX]);
+        assert_eq!(validity, vec![true, false, true]);
+        assert_eq!(Column::from(join(values, validity)), Column::Int(ints));
+        let (values, validity) = Column::Int32(vec![None, Some(7)])
+            .to_split_repr_int()
+            .unwrap();
+        assert_eq!(join(values, validity), vec![None, Some(7)]);
+
+        let floats = Column::Float(vec![None, Some(1.5), Some(-0.25)]);
+        let (values, validity) = floats.to_split_repr_float().unwrap();
+        assert_eq!(values, vec![0.0, 1.5, -0.25]);
+        assert_eq!(Column::from(join(values, validity)), floats);
+
+        let bools = vec![Some(true), None, Some(false)];
+        let (values, validity) =
+            Column::Bool(bools.clone()).to_split_repr_bool().unwrap();
+        assert_eq!(values, vec![true, false, false]);
+        assert_eq!(Column::from(join(values, validity)), Column::Bool(bools));
+        let packed =
+            Column::PackedBool(PackedBools::from(vec![None, Some(true)]));
+        let (values, validity) = packed.to_split_repr_bool().unwrap();
+        assert_eq!(join(values, validity), vec![None, Some(true)]);
+
+        assert!(Column::String(vec![]).to_split_repr_int().is_err());
+        assert!(Column::Int(vec![]).to_split_repr_float().is_err());
+        assert!(Column::Float(vec![]).to_split_repr_bool().is_err());
+    }
+
+    #[test]
+    fn test_read_chunk_without_trailing_newline() {
+        let schema = vec![DataType::String, DataType::Bool];
+        let config = ParserConfig::default();
+        let expected = vec![
+            Column::String(vec![Some("1".to_string()), Some("a".to_string())]),
+            Column::Bool(vec![Some(true), Some(false)]),
+        ];
+
+        let input = b"<1><1>\n<a><0>";
+        let parsed =
+            read_chunk(schema.clone(), &mut Cursor::new(input), 0, 13, &config)
+                .unwrap();
+        assert_eq!(parsed, expected);
+
+        let input = b"<1><1>\n<a><0>\n";
+        let parsed =
+            read_chunk(schema.clone(), &mut Cursor::new(input), 0, 14, &config)
+                .unwrap();
+        assert_eq!(parsed, expected);
+
+        // the same files with a single thread
+        for file in &["tests/2.sor", "tests/schema1.sor"] {
+            let schema = crate::schema::infer_schema(file).unwrap();
+            let one_thread = from_file(file, schema.clone(), 0, usize::MAX, 1);
+            let many_threads = from_file(file, schema, 0, usize::MAX, 8);
+            assert_eq!(one_thread, many_threads);
+            assert_eq!(one_thread[0].len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_push() {
+        let mut bools = Column::Bool(Vec::new());
+        bools.push_bool(true).unwrap();
+        bools.push_null();
+        assert_eq!(bools, Column::Bool(vec![Some(true), None]));
+
+        let mut ints = Column::Int(Vec::new());
+        ints.push_int(-3).unwrap();
+        ints.push_null();
+        assert_eq!(ints, Column::Int(vec![Some(-3), None]));
+
+        let mut floats = Column::Float(Vec::new());
+        floats.push_float(1.5).unwrap();
+        floats.push_null();
+        assert_eq!(floats, Column::Float(vec![Some(1.5), None]));
+
+        let mut strings = Column::String(Vec::new());
+        strings.push_string("hi".to_string()).unwrap();
+        strings.push_null();
+        strings.push_data(Data::String("bye".to_string())).unwrap();
+        strings.push_data(Data::Null).unwrap();
+        assert_eq!(
+            strings,
+            Column::String(vec![
+                Some("hi".to_string()),
+                None,
+                Some("bye".to_string()),
+                None
+            ])
+        );
+    }
+
+    #[test]
+    fn test_push_mismatch() {
+        let mut ints = Column::Int(vec![Some(1)]);
+        assert_eq!(
+            ints.push_bool(true),
+            Err(TypeMismatch {
+                expected: DataType::Int,
+                found: Data::Bool(true),
+            })
+        );
+        assert_eq!(
+            ints.push_float(1.5),
+            Err(TypeMismatch {
+                expected: DataType::Int,
+                found: Data::Float(1.5),
+            })
+        );
+        assert_eq!(
+            ints.push_string("1".to_string()),
+            Err(TypeMismatch {
+                expected: DataType::Int,
+                found: Data::String("1".to_string()),
+            })
+        );
+        let mut bools = Column::Bool(Vec::new());
+        assert_eq!(
+            bools.push_int(1),
+            Err(TypeMismatch {
+                expected: DataType::Bool,
+                found: Data::Int(1),
+            })
+        );
+        // a failed push leaves the column unchanged
+        assert_eq!(ints, Column::Int(vec![Some(1)]));
+        assert!(bools.is_empty());
+    }
+
+    #[test]
+    fn test_count_rows() {
+        // ends in a newline
+        assert_eq!(count_rows("tests/2.sor").unwrap(), 2);
+        assert_eq!(count_rows("tests/blank_preamble.sor").unwrap(), 1205);
+        // does not end in a newline
+        let file = "tests/no_trailing_newline.sor";
+        assert_eq!(count_rows(file).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_to_display_string() {
+        let t = Data::Bool(true);
+        let f = Data::Bool(false);
+        assert_eq!(t.to_display_string(BoolStyle::ZeroOne), "1");
+        assert_eq!(f.to_display_string(BoolStyle::ZeroOne), "0");
+        assert_eq!(t.to_display_string(BoolStyle::TrueFalse), "true");
+        assert_eq!(f.to_display_string(BoolStyle::TrueFalse), "false");
+        assert_eq!(t.to_display_string(BoolStyle::default()), t.to_string());
+
+        // other types are not affected by the style
+        for style in &[BoolStyle::ZeroOne, BoolStyle::TrueFalse] {
+            let s = Data::String("hi".to_string());
+            assert_eq!(s.to_display_string(*style), "\"hi\"");
+            assert_eq!(Data::Int(-2).to_display_string(*style), "-2");
+            assert_eq!(Data::Float(1.5).to_display_string(*style), "1.5");
+            assert_eq!(Data::Null.to_display_string(*style), "Missing Value");
+        }
+    }
+
+    #[test]
+    fn test_head_tail() {
+        let d = vec![
+            Column::Int((0..5).map(Some).collect()),
+            Column::String(vec![
+                Some("a".to_string()),
+                None,
+                Some("c".to_string()),
+                Some("d".to_string()),
+                None,
+            ]),
+        ];
+        assert_eq!(
+            head(&d, 3),
+            vec![
+                Column::Int(vec![Some(0), Some(1), Some(2)]),
+                Column::String(vec![
+                    Some("a".to_string()),
+                    None,
+                    Some("c".to_string())
+                ]),
+            ]
+        );
+        assert_eq!(
+            tail(&d, 3),
+            vec![
+                Column::Int(vec![Some(2), Some(3), Some(4)]),
+                Column::String(vec![
+                    Some("c".to_string()),
+                    Some("d".to_string()),
+                    None
+                ]),
+            ]
+        );
+        // clamped to the number of rows
+        assert_eq!(head(&d, 10), d);
+        assert_eq!(tail(&d, 10), d);
+        assert_eq!(slice_rows(&d, 4, 2)[0], Column::Int(vec![]));
+        assert_eq!(head(&d, 0)[1], Column::String(vec![]));
+    }
+
+    #[test]
+    fn test_into_vec_filtered_and_or() {
+        let ints = Column::Int(vec![Some(1), None, Some(-3)]);
+        assert_eq!(ints.clone().into_vec_int_filtered(), Ok(vec![1, -3]));
+        assert_eq!(ints.clone().into_vec_int_or(0), Ok(vec![1, 0, -3]));
+        assert!(ints.into_vec_float_filtered().is_err());
+
+        let bools = Column::Bool(vec![None, Some(true)]);
+        assert_eq!(bools.clone().into_vec_bool_filtered(), Ok(vec![true]));
+        assert_eq!(bools.into_vec_bool_or(false), Ok(vec![false, true]));
+
+        let floats = Column::Float(vec![Some(1.5), None]);
+        assert_eq!(floats.clone().into_vec_float_filtered(), Ok(vec![1.5]));
+        assert_eq!(floats.clone().into_vec_float_or(0.5), Ok(vec![1.5, 0.5]));
+        assert!(floats.into_vec_int_or(0).is_err());
+
+        let strings = Column::String(vec![None, Some("hi".to_string())]);
+        assert_eq!(
+            strings.clone().into_vec_string_filtered(),
+            Ok(vec!["hi".to_string()])
+        );
+        assert_eq!(
+            strings.into_vec_string_or("none".to_string()),
+            Ok(vec!["none".to_string(), "hi".to_string()])
+        );
+
+        // an all null column
+        let nulls = Column::Int(vec![None, None]);
+        assert_eq!(nulls.clone().into_vec_int_filtered(), Ok(vec![]));
+        assert_eq!(nulls.into_vec_int_or(7), Ok(vec![7, 7]));
+    }
+
+    #[test]
+    fn test_packed_bools() {
+        let cells: Vec<Option<bool>> = (0..200)
+            .map(|n| match n % 3 {
+                0 => None,
+                1 => Some(true),
+                _ => Some(n % 2 == 0),
+            })
+            .collect();
+        let packed = PackedBools::from(cells.clone());
+        assert_eq!(packed.len(), 200);
+        assert_eq!(packed.to_vec(), cells);
+        assert!(packed.iter().eq(cells.iter().copied()));
+
+        // truncating clears the bits past the new length, so the result
+        // equals a column that was never longer
+        let mut shortened = packed.clone();
+        shortened.truncate(70);
+        assert_eq!(shortened, PackedBools::from(&cells[..70]));
+        shortened.extend_nulls(60);
+        assert_eq!(shortened.len(), 130);
+        assert_eq!(shortened.get(69), cells[69]);
+        assert_eq!(shortened.get(129), None);
+
+        let d = vec![Column::PackedBool(packed), Column::Bool(cells.clone())];
+        assert_eq!(d[0].len(), 200);
+        assert_eq!(d[0].data_type(), DataType::Bool);
+        for row_idx in 0..200 {
+            assert_eq!(get(&d, 0, row_idx), get(&d, 1, row_idx));
+        }
+        // the storage differs, but the cells are the same
+        assert_eq!(d[0], d[1]);
+        assert_eq!(d[1], d[0]);
+        let mut other = cells.clone();
+        other[0] = Some(false);
+        assert_ne!(d[0], Column::Bool(other));
+        assert_ne!(d[0], Column::Bool(cells[..199].to_vec()));
+        assert_eq!(Vec::<Option<bool>>::try_from(d[0].clone()), Ok(cells));
+
+        // two bits per cell instead of a byte
+        let cells = vec![Some(true); 10_000];
+        let packed = Column::PackedBool(cells.iter().copied().collect());
+        let unpacked = Column::Bool(cells);
+        assert!(packed.memory_usage() * 3 < unpacked.memory_usage());
+
+        let schema = vec![DataType::Bool, DataType::Int];
+        let config = ParserConfig {
+            pack_bools: true,
+            ..ParserConfig::default()
+        };
+        let input = b"<1> <2>\n<> <3>\n<0> <4>\n";
+        let parsed =
+            read_chunk(schema, &mut Cursor::new(input), 0, 100, &config)
+                .unwrap();
+        assert!(matches!(parsed[0], Column::PackedBool(_)));
+        assert_eq!(
+            parsed[0],
+            Column::PackedBool(vec![Some(true), None, Some(false)].into())
+        );
+
+        // the chunks of every thread are appended into one packed column
+        let file = "tests/1.sor";
+        let schema = vec![DataType::Bool];
+        let packed = from_file_with_config(
+            file,
+            schema.clone(),
+            0,
+            usize::MAX,
+            4,
+            &config,
+        );
+        let unpacked = from_file(file, schema, 0, usize::MAX, 4);
+        assert!(matches!(packed[0], Column::PackedBool(_)));
+        assert_eq!(packed, unpacked);
+    }
+
+    #[test]
+    fn test_narrow_ints() {
+        let schema = vec![DataType::Int];
+        let config = ParserConfig {
+            narrow_ints: true,
+            ..ParserConfig::default()
+        };
+        let input = b"<1>\n<-2147483648>\n<>\n<2147483647>\n";
+        let parsed = read_chunk(
+            schema.clone(),
+            &mut Cursor::new(input),
+            0,
+            100,
+            &config,
+        )
+        .unwrap();
+        assert!(matches!(parsed[0], Column::Int32(_)));
+        assert_eq!(
+            parsed,
+            vec![Column::Int32(vec![
+                Some(1),
+                Some(i32::MIN),
+                None,
+                Some(i32::MAX)
+            ])]
+        );
+        // equal to the same cells stored as `i64`s
+        assert_eq!(
+            parsed,
+            vec![Column::Int(vec![
+                Some(1),
+                Some(-2147483648),
+                None,
+                Some(2147483647)
+            ])]
+        );
+        assert_ne!(parsed, vec![Column::Int(vec![Some(1)])]);
+        assert_eq!(get(&parsed, 0, 1), Data::Int(-2147483648));
+        assert_eq!(parsed[0].data_type(), DataType::Int);
+
+        // a value that does not fit widens the whole column
+        let input = b"<1>\n<>\n<2147483648>\n<-3>\n";
+        let parsed = read_chunk(
+            schema.clone(),
+            &mut Cursor::new(input),
+            0,
+            100,
+            &config,
+        )
+        .unwrap();
+        assert!(matches!(parsed[0], Column::Int(_)));
+        assert_eq!(
+            parsed,
+            vec![Column::Int(vec![Some(1), None, Some(2147483648), Some(-3)])]
+        );
+
+        // chunks of different widths are merged into a `Column::Int`
+        let file = "tests/wide_int.sor";
+        let schema = vec![DataType::Int, DataType::Int];
+        let narrow = from_file_with_config(
+            file,
+            schema.clone(),
+            0,
+            usize::MAX,
+            8,
+            &config,
+        );
+        let wide = from_file(file, schema, 0, usize::MAX, 8);
+        assert!(matches!(narrow[0], Column::Int(_)));
+        assert!(matches!(narrow[1], Column::Int32(_)));
+        assert_eq!(narrow, wide);
+    }
+
+    #[test]
+    fn test_column_reader() {
+        let d = vec![
+            Column::Bool(vec![Some(true), None]),
+            Column::Int(vec![None, Some(-1)]),
+            Column::Int32(vec![Some(3), None]),
+            Column::Float(vec![Some(1.5), None]),
+            Column::String(vec![None, Some("hi".to_string())]),
+        ];
+        for (col_idx, column) in d.iter().enumerate() {
+            let reader = column.reader();
+            assert_eq!(reader.len(), 2);
+            for row_idx in 0..reader.len() {
+                assert_eq!(reader.get(row_idx), get(&d, col_idx, row_idx));
+            }
+        }
+        assert!(Column::Int(vec![]).reader().is_empty());
+    }
+
+    #[test]
+    fn test_unique() {
+        let ints = Column::Int(vec![Some(3), None, Some(1), Some(3), None]);
+        assert_eq!(ints.unique(), Column::Int(vec![Some(3), None, Some(1)]));
+
+        let strings = Column::String(vec![
+            Some("b".to_string()),
+            Some("a".to_string()),
+            Some("b".to_string()),
+            None,
+            None,
+        ]);
+        assert_eq!(
+            strings.unique(),
+            Column::String(vec![
+                Some("b".to_string()),
+                Some("a".to_string()),
+                None
+            ])
+        );
+
+        let floats = Column::Float(vec![
+            Some(f64::NAN),
+            Some(1.5),
+            None,
+            Some(-f64::NAN),
+            Some(0.0),
+            Some(-0.0),
+            Some(1.5),
+            None,
+        ]);
+        let unique = floats.unique();
+        assert!(unique.approx_eq(
+            &Column::Float(vec![Some(f64::NAN), Some(1.5), None, Some(0.0)]),
+            0.0
+        ));
+
+        let nulls = Column::Bool(vec![None, None, None]);
+        assert_eq!(nulls.unique(), Column::Bool(vec![None]));
+        assert_eq!(Column::Int(vec![]).unique(), Column::Int(vec![]));
+    }
+
+    #[test]
+    fn test_filter_sor_to() {
+        let file = "tests/filter.sor";
+        let schema = vec![DataType::Bool, DataType::String, DataType::Float];
+        let mut out = Vec::new();
+        let written =
+            filter_sor_to(file, &schema, |row| row[0] != Data::Null, &mut out)
+                .unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "<1> <\"a\"> <1.5>\n<0> <\"c d\"> <>\n<1> <\"e\"> <-2>\n"
+        );
+
+        // the output is valid `SoR` that parses to the same rows
+        let rows: Vec<Vec<Data>> = out
+            .split(|&b| b == b'\n')
+            .filter_map(|line| {
+                crate::parsers::parse_line_with_schema(line, &schema)
+            })
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1][1], Data::String("c d".to_string()));
+
+        let mut out = Vec::new();
+        assert_eq!(
+            filter_sor_to(file, &schema, |_| false, &mut out).unwrap(),
+            0
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_dedup_rows_to() {
+        let schema = vec![DataType::Int, DataType::String, DataType::Float];
+        let mut out = Vec::new();
+        let written =
+            dedup_rows_to("tests/dup_rows.sor", &schema, &mut out).unwrap();
+        assert_eq!(written, 4);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<1> <\"a\"> <0>\n<2> <\"b c\"> <1.5>\n<2> <\"b c\"> <>\n\
+             <1> <\"b\"> <0>\n"
+        );
+    }
+
+    #[test]
+    fn test_sorted_merge_to() {
+        let schema = vec![DataType::Int, DataType::String];
+        let mut out = Vec::new();
+        let written = sorted_merge_to(
+            "tests/merge_a.sor",
+            "tests/merge_b.sor",
+            &schema,
+            0,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(written, 7);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<> <\"a0\">\n<1> <\"a1\">\n<2> <\"b2\">\n<3> <\"a3\">\n\
+             <3> <\"b3\">\n<5> <\"a5\">\n<8> <\"b8\">\n"
+        );
+
+        // merging a file with itself writes every row twice in a row
+        let mut out = Vec::new();
+        let written = sorted_merge_to(
+            "tests/merge_b.sor",
+            "tests/merge_b.sor",
+            &schema,
+            0,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(written, 6);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<2> <\"b2\">\n<2> <\"b2\">\n<3> <\"b3\">\n<3> <\"b3\">\n\
+             <8> <\"b8\">\n<8> <\"b8\">\n"
+        );
+    }
 }
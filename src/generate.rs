@@ -0,0 +1,114 @@
+//! A module for generating `SoR` files of random data that conform to a
+//! given schema, which is useful for setting up tests and benchmarks.
+//!
+//! The data is generated with a seeded random number generator, so the same
+//! seed always generates the same file.
+
+use crate::schema::DataType;
+use rand::distributions::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io::{self, Write};
+
+/// Writes `rows` rows of random data conforming to `schema` to `writer`,
+/// using `seed` to seed the random number generator. Every field has a
+/// value, see [`generate_sor_with_nulls`] to also generate missing values.
+pub fn generate_sor<W: Write>(
+    schema: &[DataType],
+    rows: usize,
+    writer: &mut W,
+    seed: u64,
+) -> io::Result<()> {
+    generate_sor_with_nulls(schema, rows, writer, seed, 0.0)
+}
+
+/// The same as [`generate_sor`], but each field is missing with a
+/// probability of `null_fraction`.
+///
+/// Values are generated so that the schema inferred from the output matches
+/// `schema` given enough rows: `Bool`s are `0` or `1`, `Int`s are random
+/// `i32`s, `Float`s always have a fractional part and `String`s are always
+/// quoted so that they are never inferred as a number.
+///
+/// # Panics
+/// If `null_fraction` is not between `0.0` and `1.0`.
+pub fn generate_sor_with_nulls<W: Write>(
+    schema: &[DataType],
+    rows: usize,
+    writer: &mut W,
+    seed: u64,
+    null_fraction: f64,
+) -> io::Result<()> {
+    assert!(
+        (0.0..=1.0).contains(&null_fraction),
+        "null_fraction must be between 0.0 and 1.0, got {}",
+        null_fraction
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..rows {
+        for (i, data_type) in schema.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b" ")?;
+            }
+            if rng.gen_bool(null_fraction) {
+                writer.write_all(b"<>")?;
+                continue;
+            }
+            match data_type {
+                DataType::Bool => write!(writer, "<{}>", rng.gen_range(0, 2))?,
+                DataType::Int => write!(writer, "<{}>", rng.gen::<i32>())?,
+                DataType::Float => {
+                    write!(writer, "<{:.3}>", rng.gen_range(-100.0, 100.0))?
+                }
+                DataType::String => {
+                    let len = rng.gen_range(1, 13);
+                    let s: String = (&mut rng)
+                        .sample_iter(&Alphanumeric)
+                        .take(len)
+                        .collect();
+                    write!(writer, "<\"{}\">", s)?
+                }
+            }
+        }
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::infer_schema;
+
+    #[test]
+    fn test_generated_data_infers_to_schema() {
+        let schema = vec![
+            DataType::Int,
+            DataType::Float,
+            DataType::Bool,
+            DataType::String,
+            DataType::Float,
+            DataType::Int,
+        ];
+        for null_fraction in &[0.0, 0.3] {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            generate_sor_with_nulls(
+                &schema,
+                1000,
+                &mut file,
+                7,
+                *null_fraction,
+            )
+            .unwrap();
+            file.flush().unwrap();
+            let inferred = infer_schema(file.path().to_str().unwrap()).unwrap();
+            assert_eq!(inferred, schema);
+        }
+
+        // the same seed generates the same data
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        generate_sor(&schema, 10, &mut a, 42).unwrap();
+        generate_sor(&schema, 10, &mut b, 42).unwrap();
+        assert_eq!(a, b);
+    }
+}
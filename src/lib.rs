@@ -54,14 +54,26 @@
 //! | -from  | \<uint\>  | no  | starting position in file (in bytes)  |
 //! | -len  | \<uint\>  |  no | number of bytes to read  |
 //! | -print_col_type  | \<uint\>  | depends  | print the type of a column: BOOL, INT, FLOAT, STRING |
-//! | -print_col_idx  | \<uint\> \<uint\>  | depends  | the first argument is the column, the second is the offset   |
+//! | -print_col_idx  | \<uint\> \<uint\>  | depends  | the first argument is the column, the second is the offset. Prints `<>` if the value is missing   |
 //! | -is_missing_idx  | \<uint\> \<uint\>  | depends  | is there a missing field in the specified column offset  |
+//! | --infer-only  | none  | depends  | print the inferred schema without parsing the file  |
+//! | --head  | \<uint\>  | depends  | print the first n rows as comma separated values, after a header line of column names  |
+//! | --sample  | \<uint\>  | depends  | print n rows chosen at random as a table, in the order they appear in the file  |
+//! | --seed  | \<uint\>  | no  | the seed for choosing the rows of `--sample`, so the same rows are printed every time. Defaults to a random seed  |
+//! | --quote-strings  | \<string\>  | no  | when `--head` quotes strings: `minimal` (default, only strings containing a comma, quote or line break), `always` or `never` (strings that need quotes are an error)  |
+//! | --null-as  | \<string\>  | no  | how missing values are printed by `-print_col_idx` (default `<>`) and `--head` (default empty)  |
+//! | --names  | \<string\>  | no  | comma separated column names for the `--head` header and `--describe` table. Defaults to the names in a `# name1 name2` first line, or `col0` to `colN`  |
+//! | --threads  | \<uint\>  | no  | the number of threads to parse with. Defaults to the `SORER_THREADS` environment variable, or the number of CPUs  |
+//! | --describe  | none  | depends  | print a table of the type and number of missing values of every column, with the min, max and mean of numeric columns and the number of distinct values of `String` columns  |
+//! | --schema-diff  | \<string\>  | depends  | print the differences between the inferred schemas of both files  |
 //!
 //! When `<val>` in `-from <val>` is greater than 0, then the file is read
 //! starting from the first complete line after `<val>`.
 //!
 //! When `<val>` in `-len <val>` is greater than 0, then the file is read
-//! up until the last complete line.
+//! up until the last line that ends within those `<val>` bytes. A line that
+//! is cut off by `-len` is not read, so a `-len` shorter than the first line
+//! reads no rows.
 //!
 //! After running `make build`, running `make bash` will mount the current
 //! the current directory to the docker container and start bash. If you
@@ -76,6 +88,9 @@
 //! Each row is a sequence of fields, each field starting with "<" and ending
 //! with ">". Spaces around delimiters are ignored.
 //!
+//! Files that use other delimiters, such as `{1} {hi}`, can be parsed by
+//! setting `delimiters` in a `ParserConfig`.
+//!
 //! # SoR Fields
 //!  A field can be either missing a value, or contain a value of one of four
 //!  SoR types:
@@ -92,6 +107,32 @@
 //! |bool   | {1, 0}  |
 //! | Missing (aka Null)  | must be empty, ie "<>"  |
 //!
+//! Other tokens can be parsed as `Bool`s, such as `<T>` and `<F>`, by adding
+//! them to `true_tokens` and `false_tokens` in a `ParserConfig`. Schema
+//! inference with `infer_schema_with_config` then infers such columns as
+//! `Bool`.
+//!
+//! Flag columns that use an integer such as `<9>` for "unknown" can list it
+//! in `bool_null_sentinels`. Such fields are then missing values in `Bool`
+//! columns instead of making their column an `Int` column, while any other
+//! integer, such as `<2>`, still does.
+//!
+//! A field that is only a sign, `<->` or `<+>`, is not an `Integer` and is
+//! parsed as a `String`, so it makes its column a `String` column. Setting
+//! `reject_lone_signs` in a `ParserConfig` makes such fields invalid instead.
+//!
+//! Integers with grouped digits, such as `<1,000,000>`, are `String`s by
+//! default. Setting `thousands_separator` in a `ParserConfig` parses and
+//! infers them as `Integer`s.
+//!
+//! Floats written with a comma as their decimal separator, such as `<3,14>`,
+//! are also `String`s by default. Setting `decimal_separator` in a
+//! `ParserConfig` parses and infers them as `Float`s.
+//!
+//! Octal and binary integers, such as `<0o755>` and `<0b1010>`, are `String`s
+//! by default as well. Setting `radix_ints` in a `ParserConfig` parses and
+//! infers them as `Integer`s.
+//!
 //!
 //! ## Valid Examples of SoR Fields
 //!
@@ -120,6 +161,18 @@
 //! NOTE: If a SoR file contains an invalid field, the row will be discarded
 //! for both schema inference and data parsing.
 //!
+//! Unquoted strings with spaces, such as `<bye world>`, can be accepted by
+//! setting `spaced_bare_strings` in a `ParserConfig`. The string then runs up
+//! to the closing `>`, without any whitespace around it.
+//!
+//! Line breaks inside quoted strings can be allowed by setting
+//! `multi_line_strings` in a `ParserConfig`. A line with an unclosed quoted
+//! string is then joined with the following line(s) until the quote is closed,
+//! instead of both halves being discarded.
+//!
+//! Comment lines, such as lines starting with `#`, can be skipped entirely by
+//! setting `comment_prefix` in a `ParserConfig`.
+//!
 //! # Schema Inference
 //! The schema that `SoRer` generates depends on the data types contained in
 //! the row with the most number of fields in the first 100 rows, followed by
@@ -130,6 +183,28 @@
 //! data type that was seen in all the rows that were equal to the width of
 //! the widest row.
 //!
+//! If the first 100 rows are all blank or invalid, `SoRer` keeps reading
+//! from the beginning of the file (up to 1000 rows) until it finds a row with
+//! at least one field.
+//!
+//! Pipelines that infer the schema of the same files repeatedly can use
+//! `infer_schema_cached`, which stores the schema in a `<file>.schema` sidecar
+//! file (or any path with `infer_schema_cached_at`) and only infers it again
+//! when the file's size or modification time changes.
+//!
+//! Files that are appended to over time, such as logs, may start in a stale
+//! format. `infer_schema_tail` infers their schema from only their last N
+//! lines instead.
+//!
+//! Setting `inference_stable_rows` in a `ParserConfig` lets inference stop
+//! reading early, once the schema has not changed for that many consecutive
+//! rows. This saves reading wide, uniform files, at the risk of missing a
+//! wider or differently typed row later on.
+//!
+//! A single garbled line with many fields can make the schema far wider than
+//! the real number of columns. Setting `max_columns` in a `ParserConfig`
+//! ignores rows with more fields than that during inference.
+//!
 //! The Data Type precedence is as follows:
 //! 1. `String`
 //! 2. `Float`
@@ -149,6 +224,9 @@
 //! An example is if a schema is parsed as `<int> <int>`,  but a line coming
 //! after the first 500 has `<string> <int>`, then it will be discarded.
 //!
+//! Setting `keep_invalid_rows` in a `ParserConfig` keeps such rows as a row of
+//! missing values instead, so that row indices stay aligned with the file.
+//!
 //! **Note** however, that it is valid for two rows in the same file to have a
 //! different number of fields and still be considered to match the schema.
 //! For rows with more fields than the schema, the extra fields will be
@@ -166,7 +244,24 @@
 //!
 //! E.g. The schema: `<int> <bool> <string>` and a row: `<12>`
 //! parses to `<12><><>`
+//!
+//! Setting `strict_width` in a `ParserConfig` makes such rows invalid
+//! instead, so that only rows with at least as many fields as the schema
+//! are parsed.
+//!
+//! Anything after the fields of the schema that isn't a well formed field,
+//! such as the `garbage` in `<12> <0> garbage`, is also ignored. Setting
+//! `strict_trailing` in a `ParserConfig` makes such rows invalid instead.
+//!
+//! Lines of any length are parsed by default. Setting `max_line_bytes` in a
+//! `ParserConfig` treats longer lines as invalid and skips past them without
+//! reading them into memory, guarding against corrupt files with huge lines.
 
+pub mod aggregate;
 pub mod dataframe;
+pub mod generate;
 pub mod parsers;
 pub mod schema;
+pub mod spill;
+#[cfg(feature = "tokio")]
+pub mod stream;
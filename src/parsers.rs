@@ -2,12 +2,13 @@
 
 use nom::error::ErrorKind;
 
-use std::str::from_utf8_unchecked;
+use std::borrow::Cow;
+use std::str::{from_utf8, from_utf8_unchecked};
 
 use nom::branch::alt;
-use nom::bytes::complete::{is_not, tag};
-use nom::character::complete::{digit1, multispace0};
-use nom::combinator::{map, opt};
+use nom::bytes::complete::{is_not, tag, take_till1};
+use nom::character::complete::{char, digit1, multispace0};
+use nom::combinator::{map, map_res, opt};
 use nom::multi::many0;
 use nom::number::complete::double;
 use nom::sequence::{delimited, preceded, terminated, tuple};
@@ -15,15 +16,298 @@ use nom::IResult;
 
 use crate::dataframe::Data;
 use crate::schema::DataType;
+use memchr::memchr;
+
+/// Options that change how `SoR` files are read and parsed. The `Default`
+/// configuration follows the `SoR` spec exactly, so none of these need to be
+/// set unless a file deviates from it.
+#[derive(Debug, Clone, Default)]
+pub struct ParserConfig {
+    /// When `true`, a line that ends inside an unclosed quoted string is
+    /// joined with the following line(s) until the quote is closed, so that
+    /// quoted strings may contain newlines. A line is considered to have an
+    /// unclosed quote when it contains an odd number of `"` bytes.
+    ///
+    /// When `false` (the default), such a string is split into two lines,
+    /// neither of which is valid, and both are discarded.
+    pub multi_line_strings: bool,
+    /// When `true`, a row that does not match the schema is kept as a row of
+    /// missing values rather than being discarded, so that row indices line
+    /// up with the line numbers of the file. This also applies to blank lines.
+    ///
+    /// When `false` (the default), such rows are discarded.
+    pub keep_invalid_rows: bool,
+    /// When `true`, every line is transcoded from latin-1 (ISO-8859-1) to
+    /// `utf-8` before it is parsed, so that files which are not valid `utf-8`
+    /// can be parsed safely.
+    ///
+    /// When `false` (the default), lines are assumed to be valid `utf-8`.
+    pub latin1: bool,
+    /// When `true`, a `>` inside a bare (unquoted) string only closes the
+    /// field if it is followed by whitespace, a `<` or the end of the line,
+    /// so `<a>b>` parses as the string `a>b`. Bare strings still end at
+    /// whitespace.
+    ///
+    /// When `false` (the default), a bare string ends at the first space or
+    /// `>`, so `<a>b>` is invalid.
+    pub greedy_bare_strings: bool,
+    /// When `true`, a bare (unquoted) string may contain spaces and runs up
+    /// to the close delimiter, without the whitespace before it, so
+    /// `<bye world>` parses as the string `bye world` instead of being
+    /// invalid.
+    ///
+    /// When `false` (the default), a bare string ends at the first space, as
+    /// the spec requires.
+    pub spaced_bare_strings: bool,
+    /// A transformation applied to every `String` cell as it is parsed.
+    ///
+    /// Defaults to [`StringTransform::None`].
+    pub string_transform: StringTransform,
+    /// Tokens that are parsed as a `true` `Bool` in addition to `1`, such as
+    /// `T` or `Y`. A token must make up the whole field, so with a token of
+    /// `T`, `<T>` is a `Bool` but `<Tea>` is still a `String`. These are also
+    /// used during [schema inference](crate::schema::infer_schema_with_config).
+    ///
+    /// Defaults to no extra tokens, so only `1` is `true`.
+    pub true_tokens: Vec<String>,
+    /// Tokens that are parsed as a `false` `Bool` in addition to `0`, the
+    /// counterpart of `true_tokens`.
+    ///
+    /// Defaults to no extra tokens, so only `0` is `false`.
+    pub false_tokens: Vec<String>,
+    /// Integers that stand for a missing value in a column of `0`/`1` flags,
+    /// such as `9` for "unknown". These are parsed as `Data::Null` when
+    /// parsing without a schema, so they don't make schema inference choose
+    /// `Int` for the column, and in `Bool` columns when parsing with a
+    /// schema. They are still parsed as `Int`s in `Int` columns.
+    ///
+    /// Defaults to no sentinels.
+    pub bool_null_sentinels: Vec<i64>,
+    /// When `true`, a field in a `Bool` column that is not a valid `Bool`,
+    /// such as a stray `<2>`, is parsed as `Data::Null` instead of causing
+    /// the whole row to be discarded. How often this happened is counted in
+    /// [`ParseStats::nulled_bools`].
+    ///
+    /// When `false` (the default), such rows are discarded.
+    pub lenient_bools: bool,
+    /// When `true`, a row with fewer fields than the schema is invalid and is
+    /// discarded (or kept as a row of missing values with
+    /// `keep_invalid_rows`). Rows with more fields than the schema are still
+    /// valid.
+    ///
+    /// When `false` (the default), the missing fields at the end of such a
+    /// row are filled in with missing values.
+    pub strict_width: bool,
+    /// When `true`, a row is invalid if anything other than whitespace and
+    /// well formed fields follows the fields of the schema, such as the
+    /// `garbage` in `<12> <1> garbage`. Extra well formed fields are still
+    /// discarded as usual.
+    ///
+    /// When `false` (the default), everything after the fields of the schema
+    /// is ignored.
+    pub strict_trailing: bool,
+    /// When `true`, a field that is only a sign, `<->` or `<+>`, is invalid
+    /// and its row is discarded. A quoted sign, such as `<"-">`, is still a
+    /// `String`.
+    ///
+    /// When `false` (the default), a lone sign is not an `Int` (which needs at
+    /// least one digit) and is parsed as a `String`, so it makes schema
+    /// inference infer its column as `String`.
+    pub reject_lone_signs: bool,
+    /// When set, integers may group their digits in threes with this byte,
+    /// such as `Some(b',')` for `<1,000,000>`. The separators are removed
+    /// before the integer is parsed, so such fields are `Int`s both when
+    /// parsing and during schema inference. Every group after the first must
+    /// have exactly three digits, so `<1,00>` is still a `String`. Since
+    /// `SoR` fields are delimited by the [`delimiters`](Self::delimiters),
+    /// the separator can not be confused with a field separator.
+    ///
+    /// Defaults to `None`, so `<1,000>` is a `String`.
+    pub thousands_separator: Option<u8>,
+    /// When set, floats use this byte as their decimal separator instead of
+    /// `.`, such as `Some(b',')` for `<3,14>`. The separator is replaced with
+    /// a `.` before the float is parsed, and a `.` is then no longer a
+    /// decimal separator, so `<3.14>` is a `String`. If it is the same byte
+    /// as the [`thousands_separator`](Self::thousands_separator), the byte is
+    /// only a decimal separator, so `<3,140>` is the float `3.14` and not the
+    /// integer `3140`.
+    ///
+    /// Defaults to `None`, so `<3,14>` is a `String`.
+    pub decimal_separator: Option<u8>,
+    /// When `true`, integers may also be written in octal with a `0o` prefix,
+    /// such as `<0o755>`, or in binary with a `0b` prefix, such as
+    /// `<0b1010>`, optionally after a sign. Such fields are `Int`s both when
+    /// parsing and during schema inference. A digit that is not valid in the
+    /// base, such as the `9` in `<0o9>`, makes the field invalid in an `Int`
+    /// column, so its row is discarded.
+    ///
+    /// When `false` (the default), `<0o755>` is a `String`.
+    pub radix_ints: bool,
+    /// When set, only the columns at these indices of the schema are kept,
+    /// in the given order, by the readers that return a `Vec<Column>` such
+    /// as [`from_file_with_config`](crate::dataframe::from_file_with_config)
+    /// and [`SorTerator`](crate::dataframe::SorTerator). The other fields
+    /// are still checked against the schema, so the same rows are kept as
+    /// without a projection. See
+    /// [`parse_line_with_schema_projected`].
+    ///
+    /// Every index must be a column of the schema, otherwise the readers
+    /// panic before any of the file is parsed.
+    ///
+    /// Defaults to `None`, so every column is kept.
+    pub projection: Option<Vec<usize>>,
+    /// When `true`, `Int` columns are stored as a
+    /// [`Column::Int32`](crate::dataframe::Column::Int32), using half the
+    /// memory, for as long as every value fits in an `i32`. A column is
+    /// widened to a `Column::Int` as soon as a value that does not fit is
+    /// parsed.
+    ///
+    /// When `false` (the default), `Int` columns are always stored as a
+    /// `Column::Int`.
+    pub narrow_ints: bool,
+    /// When `true`, `Bool` columns are stored as a
+    /// [`Column::PackedBool`](crate::dataframe::Column::PackedBool), using
+    /// two bits per cell instead of a byte.
+    ///
+    /// When `false` (the default), `Bool` columns are stored as a
+    /// `Column::Bool`.
+    pub pack_bools: bool,
+    /// When set, lines whose first non-whitespace byte is this byte (such as
+    /// `Some(b'#')`) are comments. Comments are skipped by schema inference
+    /// and by the readers, and are never kept as a row of missing values by
+    /// `keep_invalid_rows`.
+    ///
+    /// Defaults to `None`, so there are no comment lines.
+    pub comment_prefix: Option<u8>,
+    /// When set, lines longer than this many bytes (not counting the
+    /// newline) are invalid, like blank lines, so they are discarded or kept
+    /// as a row of missing values with `keep_invalid_rows`. Such lines are
+    /// never read into memory whole, so a corrupt file with a huge line can't
+    /// exhaust memory. With `multi_line_strings`, the limit applies to the
+    /// joined lines of a row. Schema inference skips such lines as well.
+    ///
+    /// Defaults to `None`, so lines of any length are parsed.
+    pub max_line_bytes: Option<usize>,
+    /// When set, schema inference ignores rows with more than this many
+    /// fields, so that a single garbled line can't make the schema wider
+    /// than the real number of columns. Rows are still parsed as usual, so
+    /// the extra fields of such rows are ignored.
+    ///
+    /// Defaults to `None`, so the widest row decides the width of the schema.
+    pub max_columns: Option<usize>,
+    /// When set, schema inference stops reading the file early once the
+    /// inferred schema has not changed for this many consecutive lines. This
+    /// saves reading files whose schema settles quickly, at the risk of
+    /// missing a wider or differently typed row further into the file. Even a
+    /// schema of only `String`s can still be widened by a later row, so such
+    /// a schema has to be stable for as long as any other.
+    ///
+    /// Defaults to `None`, so inference always samples every line it would
+    /// otherwise read.
+    pub inference_stable_rows: Option<usize>,
+    /// The bytes that open and close every field.
+    ///
+    /// Defaults to `<` and `>`.
+    pub delimiters: Delimiters,
+}
+
+/// The pair of bytes that open and close a field, such as `{` and `}` for
+/// rows like `{1} {hi}`.
+///
+/// Like with the default `<` and `>`, a delimiter byte may still appear in
+/// a field: an open delimiter inside a field is part of its value (so `{{}`
+/// is the string `{`), while a close delimiter is only allowed inside a
+/// quoted string or a [greedy bare string](ParserConfig::greedy_bare_strings).
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Delimiters {
+    /// The byte that opens a field.
+    pub open: u8,
+    /// The byte that closes a field.
+    pub close: u8,
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Delimiters {
+            open: b'<',
+            close: b'>',
+        }
+    }
+}
+
+/// Counts of the fields that were changed while parsing instead of causing
+/// their row to be discarded.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct ParseStats {
+    /// The number of invalid `Bool` fields that were parsed as `Data::Null`
+    /// because of [`ParserConfig::lenient_bools`].
+    pub nulled_bools: usize,
+}
+
+/// A transformation applied to `String` cells while parsing, to avoid a
+/// second pass over the data.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum StringTransform {
+    /// Strings are kept as they are in the file.
+    #[default]
+    None,
+    /// Strings are converted to lowercase.
+    Lowercase,
+    /// Leading and trailing whitespace is removed from strings, including
+    /// whitespace inside of the quotes of a quoted string.
+    Trim,
+}
+
+/// Transcodes `i` from latin-1 to `utf-8` if `config.latin1` is set,
+/// otherwise returns `i` as is. Lines that are pure ascii are never copied.
+pub(crate) fn decode_line<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> Cow<'a, [u8]> {
+    if !config.latin1 || i.is_ascii() {
+        Cow::Borrowed(i)
+    } else {
+        // every latin-1 byte maps to the unicode code point of the same value
+        Cow::Owned(
+            i.iter()
+                .map(|&b| b as char)
+                .collect::<String>()
+                .into_bytes(),
+        )
+    }
+}
+
+/// Returns `true` if `i` is a comment line according to
+/// `config.comment_prefix`.
+pub(crate) fn is_comment(i: &[u8], config: &ParserConfig) -> bool {
+    match config.comment_prefix {
+        Some(prefix) => {
+            i.iter().find(|b| !b.is_ascii_whitespace()) == Some(&prefix)
+        }
+        None => false,
+    }
+}
+
+/// Returns `true` if `i` contains an odd number of `"` bytes, meaning that a
+/// quoted string was opened but never closed.
+pub(crate) fn has_unclosed_quote(i: &[u8]) -> bool {
+    i.iter().filter(|&&b| b == b'"').count() % 2 == 1
+}
 
 #[inline(always)]
-fn left_angle_bracket(i: &[u8]) -> IResult<&[u8], &[u8]> {
-    terminated(tag("<"), multispace0)(i)
+fn open_delimiter<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], char> {
+    terminated(char(config.delimiters.open as char), multispace0)(i)
 }
 
 #[inline(always)]
-fn right_angle_bracket(i: &[u8]) -> IResult<&[u8], &[u8]> {
-    preceded(multispace0, tag(">"))(i)
+fn close_delimiter<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], char> {
+    preceded(multispace0, char(config.delimiters.close as char))(i)
 }
 
 #[inline(always)]
@@ -37,8 +321,38 @@ fn parse_bool(i: &[u8]) -> IResult<&[u8], Data> {
 }
 
 #[inline(always)]
-fn parse_delimited_bool(i: &[u8]) -> IResult<&[u8], Data> {
-    delimited(left_angle_bracket, parse_bool, right_angle_bracket)(i)
+fn parse_delimited_bool<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
+    let result = delimited(
+        |i| open_delimiter(i, config),
+        parse_bool,
+        |i| close_delimiter(i, config),
+    )(i);
+    if result.is_ok()
+        || (config.true_tokens.is_empty() && config.false_tokens.is_empty())
+    {
+        return result;
+    }
+    // each token is tried along with the closing bracket, so that a token
+    // that is a prefix of another one does not hide it
+    let (rest, _) = open_delimiter(i, config)?;
+    let tokens = config
+        .true_tokens
+        .iter()
+        .map(|t| (t, true))
+        .chain(config.false_tokens.iter().map(|t| (t, false)));
+    for (token, value) in tokens {
+        let closed: IResult<&[u8], &[u8]> = terminated(
+            tag(token.as_bytes()),
+            |i| close_delimiter(i, config),
+        )(rest);
+        if let Ok((rest, _)) = closed {
+            return Ok((rest, Data::Bool(value)));
+        }
+    }
+    result
 }
 
 #[inline(always)]
@@ -51,7 +365,7 @@ fn parse_int(i: &[u8]) -> IResult<&[u8], Data> {
         Some(b"-") => -1,
         _ => unreachable!(),
     };
-    // not unsafe because the spec guarantees only ascii characters in any field
+    // not unsafe because `digit1` only matches ascii digits
     let num = unsafe { from_utf8_unchecked(number) }.parse::<i64>();
     match num {
         Ok(n) => Ok((remaining_input, Data::Int(n * multiplier))),
@@ -59,38 +373,259 @@ fn parse_int(i: &[u8]) -> IResult<&[u8], Data> {
     }
 }
 
+// Parses an integer whose digits may be grouped in threes by `separator`,
+// such as `-1,000,000`
+fn parse_grouped_int(i: &[u8], separator: u8) -> IResult<&[u8], Data> {
+    let (mut rest, (sign, first)) =
+        tuple((opt(alt((tag("+"), tag("-")))), digit1))(i)?;
+    let mut digits = first.to_vec();
+    // a group is exactly three digits, so it can't be followed by a fourth
+    while let [s, a, b, c, after @ ..] = rest {
+        let group = [*a, *b, *c];
+        if *s != separator
+            || !group.iter().all(u8::is_ascii_digit)
+            || matches!(after.first(), Some(d) if d.is_ascii_digit())
+        {
+            break;
+        }
+        digits.extend_from_slice(&group);
+        rest = after;
+    }
+    if first.len() > 3 && digits.len() > first.len() {
+        return Err(nom::Err::Error((i, ErrorKind::Digit)));
+    }
+    // not unsafe because only ascii digits were collected
+    let num = unsafe { from_utf8_unchecked(&digits) }.parse::<i64>();
+    match (num, sign) {
+        (Ok(n), Some(b"-")) => Ok((rest, Data::Int(-n))),
+        (Ok(n), _) => Ok((rest, Data::Int(n))),
+        (Err(_), _) => Err(nom::Err::Error((i, ErrorKind::Digit))),
+    }
+}
+
+// Parses an integer written in octal with a `0o` prefix or in binary with a
+// `0b` prefix, such as `-0b1010`
+fn parse_radix_int(i: &[u8]) -> IResult<&[u8], Data> {
+    let (rest, (sign, prefix, digits)) = tuple((
+        opt(alt((tag("+"), tag("-")))),
+        alt((tag("0o"), tag("0b"))),
+        digit1,
+    ))(i)?;
+    let radix = if prefix == b"0o" { 8 } else { 2 };
+    let mut number = String::with_capacity(digits.len() + 1);
+    if sign == Some(b"-") {
+        number.push('-');
+    }
+    // not unsafe because `digit1` only matches ascii digits
+    number.push_str(unsafe { from_utf8_unchecked(digits) });
+    match i64::from_str_radix(&number, radix) {
+        Ok(n) => Ok((rest, Data::Int(n))),
+        Err(_) => Err(nom::Err::Error((i, ErrorKind::Digit))),
+    }
+}
+
+#[inline(always)]
+fn parse_delimited_int<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
+    if config.radix_ints {
+        let radix_int = delimited(
+            |i| open_delimiter(i, config),
+            parse_radix_int,
+            |i| close_delimiter(i, config),
+        )(i);
+        if radix_int.is_ok() {
+            return radix_int;
+        }
+    }
+    delimited(
+        |i| open_delimiter(i, config),
+        |i| match config.thousands_separator {
+            Some(separator) if config.decimal_separator != Some(separator) => {
+                parse_grouped_int(i, separator)
+            }
+            _ => parse_int(i),
+        },
+        |i| close_delimiter(i, config),
+    )(i)
+}
+
+// Takes a bare string up to the first whitespace (or line break if `spaced`),
+// or up to the first close delimiter that is followed by whitespace, an open
+// delimiter or the end of the input.
+fn greedy_bare_string(
+    i: &[u8],
+    delimiters: Delimiters,
+    spaced: bool,
+) -> IResult<&[u8], &[u8]> {
+    let closes_field = |idx: usize| match i.get(idx + 1) {
+        None => true,
+        Some(next) => next.is_ascii_whitespace() || *next == delimiters.open,
+    };
+    let ends_string = |b: u8| {
+        if spaced {
+            matches!(b, b'\n' | b'\r')
+        } else {
+            b.is_ascii_whitespace()
+        }
+    };
+    let end = (0..i.len())
+        .find(|&idx| {
+            ends_string(i[idx])
+                || (i[idx] == delimiters.close && closes_field(idx))
+        })
+        .unwrap_or(i.len());
+    if end == 0 {
+        Err(nom::Err::Error((i, ErrorKind::IsNot)))
+    } else {
+        Ok((&i[end..], &i[..end]))
+    }
+}
+
+#[inline(always)]
+fn bare_string<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], &'a [u8]> {
+    let (mut rest, mut s) = if config.greedy_bare_strings {
+        greedy_bare_string(i, config.delimiters, config.spaced_bare_strings)?
+    } else {
+        // a bare string can't span lines, even if a caller passes several
+        // lines to the parser at once
+        let close = config.delimiters.close;
+        let spaced = config.spaced_bare_strings;
+        take_till1(move |b| {
+            matches!(b, b'\n' | b'\r') || b == close || (b == b' ' && !spaced)
+        })(i)?
+    };
+    if config.spaced_bare_strings {
+        // leave the whitespace before the close delimiter for it to skip
+        let trailing = s.iter().rev().take_while(|b| b.is_ascii_whitespace());
+        let len = s.len() - trailing.count();
+        if len == 0 {
+            return Err(nom::Err::Error((i, ErrorKind::IsNot)));
+        }
+        rest = &i[len..];
+        s = &s[..len];
+    }
+    if config.reject_lone_signs && matches!(s, b"-" | b"+") {
+        return Err(nom::Err::Error((i, ErrorKind::Digit)));
+    }
+    Ok((rest, s))
+}
+
+// Recognizes a quoted or bare string, without its quotes
 #[inline(always)]
-fn parse_delimited_int(i: &[u8]) -> IResult<&[u8], Data> {
-    delimited(left_angle_bracket, parse_int, right_angle_bracket)(i)
+fn string_body<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], &'a [u8]> {
+    alt((delimited(tag("\""), is_not("\""), tag("\"")), |i| {
+        bare_string(i, config)
+    }))(i)
 }
 
 #[inline(always)]
-fn parse_string(i: &[u8]) -> IResult<&[u8], Data> {
-    // not unsafe because the spec guarantees only ascii characters in any field
-    map(
-        alt((delimited(tag("\""), is_not("\""), tag("\"")), is_not(" >"))),
+fn parse_string<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
+    // strings that aren't valid utf-8 make the field invalid, so that
+    // arbitrary bytes never end up in a `String`
+    map_res(
+        |i| string_body(i, config),
         |s: &[u8]| {
-            Data::String(match s {
-                b"\"\"" => String::from(""),
-                _ => String::from(unsafe { from_utf8_unchecked(s) }),
-            })
+            let s = match s {
+                b"\"\"" => "",
+                _ => from_utf8(s)?,
+            };
+            Ok::<Data, std::str::Utf8Error>(Data::String(
+                match config.string_transform {
+                    StringTransform::None => String::from(s),
+                    StringTransform::Lowercase => s.to_lowercase(),
+                    StringTransform::Trim => String::from(s.trim()),
+                },
+            ))
         },
     )(i)
 }
 
 #[inline(always)]
-fn parse_delimited_string(i: &[u8]) -> IResult<&[u8], Data> {
-    delimited(left_angle_bracket, parse_string, right_angle_bracket)(i)
+fn parse_delimited_string<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
+    delimited(
+        |i| open_delimiter(i, config),
+        |i| parse_string(i, config),
+        |i| close_delimiter(i, config),
+    )(i)
+}
+
+// Recognizes the same fields as `parse_delimited_string` without building a
+// `String`, for fields that are not kept
+#[inline(always)]
+fn skip_delimited_string<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], ()> {
+    map_res(
+        delimited(
+            |i| open_delimiter(i, config),
+            |i| string_body(i, config),
+            |i| close_delimiter(i, config),
+        ),
+        |s| from_utf8(s).map(|_| ()),
+    )(i)
 }
 
+// NOTE: nom's `double` also accepts bare integers, so `123` parses to
+// `Data::Float(123.0)`. This is what we want in `parse_line_with_schema`,
+// where a Float column may contain integer-looking values. In `parse_field`
+// the int parser is tried before this one, so schema inference only sees a
+// `Data::Float` for tokens with a `.` or an exponent (or integers that don't
+// fit in an `i64`). A column of bare integers therefore never infers as
+// Float, while a column mixing `1` and `1.0` does.
 #[inline(always)]
 fn parse_float(i: &[u8]) -> IResult<&[u8], Data> {
     map(double, Data::Float)(i)
 }
 
+// Parses a float whose decimal separator is `separator` instead of `.`, such
+// as `3,14`
+fn parse_float_with_separator(i: &[u8], separator: u8) -> IResult<&[u8], Data> {
+    let len = i
+        .iter()
+        .take_while(|&&b| {
+            b.is_ascii_digit()
+                || matches!(b, b'+' | b'-' | b'e' | b'E')
+                || b == separator
+        })
+        .count();
+    let normalized: Vec<u8> = i[..len]
+        .iter()
+        .map(|&b| if b == separator { b'.' } else { b })
+        .collect();
+    match double::<_, (&[u8], ErrorKind)>(&normalized[..]) {
+        Ok((rest, f)) => Ok((&i[len - rest.len()..], Data::Float(f))),
+        Err(_) => Err(nom::Err::Error((i, ErrorKind::Float))),
+    }
+}
+
 #[inline(always)]
-fn parse_delimited_float(i: &[u8]) -> IResult<&[u8], Data> {
-    delimited(left_angle_bracket, parse_float, right_angle_bracket)(i)
+fn parse_delimited_float<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
+    delimited(
+        |i| open_delimiter(i, config),
+        |i| match config.decimal_separator {
+            Some(separator) => parse_float_with_separator(i, separator),
+            None => parse_float(i),
+        },
+        |i| close_delimiter(i, config),
+    )(i)
 }
 
 #[inline(always)]
@@ -99,20 +634,49 @@ fn parse_null(i: &[u8]) -> IResult<&[u8], Data> {
 }
 
 #[inline(always)]
-fn parse_delimited_null(i: &[u8]) -> IResult<&[u8], Data> {
-    delimited(left_angle_bracket, parse_null, right_angle_bracket)(i)
+fn parse_delimited_null<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
+    delimited(
+        |i| open_delimiter(i, config),
+        parse_null,
+        |i| close_delimiter(i, config),
+    )(i)
 }
 
-fn parse_field(i: &[u8]) -> IResult<&[u8], Data> {
+fn parse_field<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
     alt((
-        parse_delimited_null,
-        parse_delimited_bool,
-        parse_delimited_int,
-        parse_delimited_float,
-        parse_delimited_string,
+        |i| parse_delimited_null(i, config),
+        |i| parse_delimited_bool(i, config),
+        |i| parse_delimited_sentinel(i, config),
+        |i| parse_delimited_int(i, config),
+        |i| parse_delimited_float(i, config),
+        |i| parse_delimited_string(i, config),
     ))(i)
 }
 
+// Parses an integer that is one of `config.bool_null_sentinels` as a
+// `Data::Null`
+#[inline(always)]
+fn parse_delimited_sentinel<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Data> {
+    if config.bool_null_sentinels.is_empty() {
+        return Err(nom::Err::Error((i, ErrorKind::Digit)));
+    }
+    match parse_delimited_int(i, config)? {
+        (rest, Data::Int(n)) if config.bool_null_sentinels.contains(&n) => {
+            Ok((rest, Data::Null))
+        }
+        _ => Err(nom::Err::Error((i, ErrorKind::Digit))),
+    }
+}
+
 /// Parses a row of `SoR` data, `i` (as a `&[u8]`), into a `Option<Vec<Data>>`
 /// Returning `Some` if `i` was a valid sor row, `None` otherwise. It parses
 /// using the most conservative precedence possible. Types `bool`  are parsed
@@ -131,17 +695,27 @@ fn parse_field(i: &[u8]) -> IResult<&[u8], Data> {
 ///            parse_line(i));
 /// ```
 ///
-/// # Safety
-/// This function calls `std::str::from_utf8_unchecked`, meaning that it does not check that the
-/// bytes passed to it are valid UTF-8. If this constraint is violated, undefined behavior results,
-/// as the rest of Rust assumes that &strs are valid UTF-8.
-///
-/// Since `SoR` files are guaranteed to only contain valid C++ strings, and thus only valid `utf-8`,
-/// then this constraint only applies to consumers of the crate and not users of the `SoRer`
-/// executable.
+/// This never panics, whatever the bytes in `i`. A `String` field that is not
+/// valid `utf-8` is invalid, so its row is discarded.
 pub fn parse_line(i: &[u8]) -> Option<Vec<Data>> {
-    let (remaining_input, data) =
-        many0(delimited(multispace0, parse_field, multispace0))(i).unwrap();
+    parse_line_opts(i, &ParserConfig::default())
+}
+
+/// The same as [`parse_line`](crate::parsers::parse_line), but parses
+/// according to the given [`ParserConfig`](crate::parsers::ParserConfig).
+pub fn parse_line_opts(i: &[u8], config: &ParserConfig) -> Option<Vec<Data>> {
+    // a greedy bare string may contain a close delimiter, so a row can only
+    // be rejected up front when bare strings end at the first one
+    if !config.greedy_bare_strings && !delimiters_balanced(i, config.delimiters)
+    {
+        return None;
+    }
+    let (remaining_input, data) = many0(delimited(
+        multispace0,
+        |i| parse_field(i, config),
+        multispace0,
+    ))(i)
+    .ok()?;
     if remaining_input != b"" {
         None
     } else {
@@ -149,6 +723,75 @@ pub fn parse_line(i: &[u8]) -> Option<Vec<Data>> {
     }
 }
 
+/// The same as [`parse_line`](crate::parsers::parse_line), but also returns
+/// the source text of every field next to its value, without its delimiters
+/// and surrounding whitespace, such as `01` for a field `< 01 >` parsed as
+/// `Data::Int(1)`. Quoted strings keep their quotes. This is meant for
+/// debugging how fields are parsed, so it is slower than `parse_line`.
+///
+/// # Examples
+/// ```
+/// use sorer::parsers::parse_line_raw;
+/// use sorer::dataframe::Data;
+///
+/// assert_eq!(parse_line_raw(b"< 01 > <>"),
+///            Some(vec![(Data::Int(1), String::from("01")),
+///                      (Data::Null, String::new())]));
+/// ```
+pub fn parse_line_raw(i: &[u8]) -> Option<Vec<(Data, String)>> {
+    let config = ParserConfig::default();
+    if !delimiters_balanced(i, config.delimiters) {
+        return None;
+    }
+    let mut fields = Vec::new();
+    let (mut remaining_input, _) = my_multispace(i).ok()?;
+    while !remaining_input.is_empty() {
+        let (rest, data) = parse_field(remaining_input, &config).ok()?;
+        // the field without its open and close delimiters
+        let field = &remaining_input[1..remaining_input.len() - rest.len() - 1];
+        let raw = String::from_utf8_lossy(field).trim().to_string();
+        fields.push((data, raw));
+        remaining_input = my_multispace(rest).ok()?.0;
+    }
+    Some(fields)
+}
+
+// A quick check that every field of `i` is opened and then closed, without
+// parsing the values of the fields, so that rows with a missing or stray
+// delimiter are rejected before running the parsers. Delimiters inside a
+// quoted string don't count. This never rejects a row that `parse_line_opts`
+// would accept with non-greedy bare strings, since there a field always ends
+// at the first close delimiter after its quoted string (if any).
+fn delimiters_balanced(i: &[u8], delimiters: Delimiters) -> bool {
+    let is_space = |b: &u8| matches!(b, b' ' | b'\t' | b'\r' | b'\n');
+    let skip_spaces =
+        |idx: usize| idx + i[idx..].iter().take_while(|b| is_space(b)).count();
+    let mut idx = 0;
+    loop {
+        idx = skip_spaces(idx);
+        match i.get(idx) {
+            None => return true,
+            Some(&b) if b == delimiters.open => idx = skip_spaces(idx + 1),
+            Some(_) => return false,
+        }
+        // like in `parse_string`, a quoted string only counts as one if it
+        // is followed by the close delimiter
+        if i.get(idx) == Some(&b'"') {
+            if let Some(len) = memchr(b'"', &i[idx + 1..]) {
+                let after = skip_spaces(idx + len + 2);
+                if i.get(after) == Some(&delimiters.close) {
+                    idx = after + 1;
+                    continue;
+                }
+            }
+        }
+        match memchr(delimiters.close, &i[idx..]) {
+            Some(len) => idx += len + 1,
+            None => return false,
+        }
+    }
+}
+
 // NOTE: this is required since:
 // the trait bound `&[u8]: nom::error::ParseError<&[u8]>` is not satisfied
 // the trait `nom::error::ParseError<&[u8]>` is not implemented for `&[u8]`
@@ -161,7 +804,8 @@ fn my_multispace(i: &[u8]) -> IResult<&[u8], &[u8]> {
 /// returning `Some` if the data types in `i` matches the `schema`. If the data
 /// types match, but `i` contains fewer fields than `schema`, than
 /// `Data::Null` is inserted. If the row has more fields than `schema`, then
-/// the extra fields are discarded.
+/// the extra fields are discarded. Empty or whitespace only rows return
+/// `None`.
 ///
 /// Further information on how parsing with [schemas](crate::schema) can
 /// be found [here](../index.html#sor-fields) and
@@ -181,71 +825,249 @@ fn my_multispace(i: &[u8]) -> IResult<&[u8], &[u8]> {
 ///            parse_line_with_schema(i, &s));
 /// ```
 ///
-/// # Safety
-/// This function calls `std::str::from_utf8_unchecked`, meaning that it does not check that the
-/// bytes passed to it are valid UTF-8. If this constraint is violated, undefined behavior results,
-/// as the rest of Rust assumes that &strs are valid UTF-8.
-///
-/// Since `SoR` files are guaranteed to only contain valid C++ strings, and thus only valid `utf-8`,
-/// then this constraint only applies to consumers of the crate and not users of the `SoRer`
-/// executable.
+/// This never panics, whatever the bytes in `i`. A `String` field that is not
+/// valid `utf-8` is invalid, so its row is discarded.
 pub fn parse_line_with_schema(
     i: &[u8],
     schema: &[DataType],
 ) -> Option<Vec<Data>> {
-    if i.is_empty() {
+    parse_line_with_schema_opts(i, schema, &ParserConfig::default())
+}
+
+/// The same as
+/// [`parse_line_with_schema`](crate::parsers::parse_line_with_schema), but
+/// parses according to the given
+/// [`ParserConfig`](crate::parsers::ParserConfig).
+pub fn parse_line_with_schema_opts(
+    i: &[u8],
+    schema: &[DataType],
+    config: &ParserConfig,
+) -> Option<Vec<Data>> {
+    parse_line_with_schema_stats(i, schema, config, &mut ParseStats::default())
+}
+
+/// The same as
+/// [`parse_line_with_schema_opts`](crate::parsers::parse_line_with_schema_opts),
+/// but also adds the fields that were changed while parsing the line to
+/// `stats`. Nothing is added if the line is discarded.
+pub fn parse_line_with_schema_stats(
+    i: &[u8],
+    schema: &[DataType],
+    config: &ParserConfig,
+    stats: &mut ParseStats,
+) -> Option<Vec<Data>> {
+    let mut result: Vec<Data> = Vec::with_capacity(schema.len() + 1);
+    if parse_fields(i, schema, config, stats, |_| true, |_, d| result.push(d)) {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+/// The same as
+/// [`parse_line_with_schema`](crate::parsers::parse_line_with_schema), but
+/// only returns the fields at the given `columns` indices of the `schema`, in
+/// the order they are given in.
+///
+/// Every field is still checked against the `schema`, so the same rows are
+/// discarded as by `parse_line_with_schema`, but no `String` is built for
+/// the fields of `String` columns that are skipped.
+///
+/// # Panics
+/// If an index in `columns` is out of bounds of the `schema`.
+///
+/// # Examples
+/// ```
+/// use sorer::schema::DataType;
+/// use sorer::parsers::parse_line_with_schema_projected;
+/// use sorer::dataframe::Data;
+///
+/// let i = b"<1> <hi> <2.5>";
+/// let s = vec![DataType::Bool, DataType::String, DataType::Float];
+///
+/// assert_eq!(Some(vec![Data::Float(2.5), Data::Bool(true)]),
+///            parse_line_with_schema_projected(i, &s, &[2, 0]));
+/// ```
+pub fn parse_line_with_schema_projected(
+    i: &[u8],
+    schema: &[DataType],
+    columns: &[usize],
+) -> Option<Vec<Data>> {
+    parse_line_with_schema_projected_stats(
+        i,
+        schema,
+        columns,
+        &ParserConfig::default(),
+        &mut ParseStats::default(),
+    )
+}
+
+/// The same as
+/// [`parse_line_with_schema_projected`](crate::parsers::parse_line_with_schema_projected),
+/// but parses according to the given
+/// [`ParserConfig`](crate::parsers::ParserConfig) and adds the fields that
+/// were changed while parsing the line to `stats`, like
+/// [`parse_line_with_schema_stats`](crate::parsers::parse_line_with_schema_stats).
+pub fn parse_line_with_schema_projected_stats(
+    i: &[u8],
+    schema: &[DataType],
+    columns: &[usize],
+    config: &ParserConfig,
+    stats: &mut ParseStats,
+) -> Option<Vec<Data>> {
+    let mut keep = vec![false; schema.len()];
+    for &col_idx in columns {
+        assert!(
+            col_idx < schema.len(),
+            "Column index {} is out of bounds for {} columns",
+            col_idx,
+            schema.len()
+        );
+        keep[col_idx] = true;
+    }
+    let mut fields: Vec<Option<Data>> = vec![None; schema.len()];
+    let parsed = parse_fields(
+        i,
+        schema,
+        config,
+        stats,
+        |col_idx| keep[col_idx],
+        |col_idx, d| fields[col_idx] = Some(d),
+    );
+    if !parsed {
         return None;
+    }
+    let mut result: Vec<Data> = Vec::with_capacity(columns.len());
+    for (n, &col_idx) in columns.iter().enumerate() {
+        // a column that is asked for more than once was taken the first time
+        let d = match fields[col_idx].take() {
+            Some(d) => d,
+            None => {
+                let first = columns.iter().position(|&c| c == col_idx).unwrap();
+                debug_assert!(first < n);
+                result[first].clone()
+            }
+        };
+        result.push(d);
+    }
+    Some(result)
+}
+
+// Parses every field of `i` according to `schema`, calling `push` with the
+// index and value of every field whose index is accepted by `keep`. Returns
+// `false` if the line is discarded, in which case nothing is added to `stats`
+fn parse_fields<K, P>(
+    i: &[u8],
+    schema: &[DataType],
+    config: &ParserConfig,
+    stats: &mut ParseStats,
+    keep: K,
+    mut push: P,
+) -> bool
+where
+    K: Fn(usize) -> bool,
+    P: FnMut(usize, Data),
+{
+    // lines of only whitespace are treated the same as empty lines
+    if i.iter().all(u8::is_ascii_whitespace) {
+        return false;
     };
-    let mut result: Vec<Data> = Vec::with_capacity(schema.len() + 1);
     let mut remaining_input = i;
-    for column_type in schema {
-        let (x, _) = my_multispace(remaining_input).unwrap();
-        remaining_input = x;
+    let mut nulled_bools = 0;
+    for (col_idx, column_type) in schema.iter().enumerate() {
+        remaining_input = match my_multispace(remaining_input) {
+            Ok((x, _)) => x,
+            Err(_) => return false,
+        };
         if remaining_input == b"" {
-            result.push(Data::Null);
+            if config.strict_width {
+                return false;
+            }
+            if keep(col_idx) {
+                push(col_idx, Data::Null);
+            }
             continue;
         }
-        match parse_delimited_null(remaining_input) {
-            Ok((rem, d)) => {
-                remaining_input = rem;
-                result.push(d);
-            }
+        let parsed = match parse_delimited_null(remaining_input, config) {
+            Ok(parsed) => parsed,
             _ => match &column_type {
+                DataType::String if !keep(col_idx) => {
+                    match skip_delimited_string(remaining_input, config) {
+                        Ok((x, _)) => (x, Data::Null),
+                        _ => return false,
+                    }
+                }
                 DataType::String => {
-                    match parse_delimited_string(remaining_input) {
-                        Ok((x, d)) => {
-                            result.push(d);
-                            remaining_input = x;
-                        }
-                        _ => return None,
+                    match parse_delimited_string(remaining_input, config) {
+                        Ok(parsed) => parsed,
+                        _ => return false,
                     }
                 }
-                DataType::Float => match parse_delimited_float(remaining_input)
-                {
-                    Ok((x, d)) => {
-                        result.push(d);
-                        remaining_input = x;
+                DataType::Float => {
+                    match parse_delimited_float(remaining_input, config) {
+                        Ok(parsed) => parsed,
+                        _ => return false,
                     }
-                    _ => return None,
-                },
-                DataType::Int => match parse_delimited_int(remaining_input) {
-                    Ok((x, d)) => {
-                        result.push(d);
-                        remaining_input = x;
+                }
+                DataType::Int => {
+                    match parse_delimited_int(remaining_input, config) {
+                        Ok(parsed) => parsed,
+                        _ => return false,
                     }
-                    _ => return None,
-                },
-                DataType::Bool => match parse_delimited_bool(remaining_input) {
-                    Ok((x, d)) => {
-                        result.push(d);
-                        remaining_input = x;
+                }
+                DataType::Bool => {
+                    match parse_delimited_bool(remaining_input, config).or_else(
+                        |_| parse_delimited_sentinel(remaining_input, config),
+                    ) {
+                        Ok(parsed) => parsed,
+                        // any other well formed field becomes a null
+                        Err(_) if config.lenient_bools => {
+                            match skip_delimited_string(remaining_input, config)
+                            {
+                                Ok((x, _)) => {
+                                    nulled_bools += 1;
+                                    (x, Data::Null)
+                                }
+                                _ => return false,
+                            }
+                        }
+                        _ => return false,
                     }
-                    _ => return None,
-                },
+                }
             },
+        };
+        remaining_input = parsed.0;
+        if keep(col_idx) {
+            push(col_idx, parsed.1);
         }
     }
-    Some(result)
+    if config.strict_trailing && !only_fields(remaining_input, config) {
+        return false;
+    }
+    stats.nulled_bools += nulled_bools;
+    true
+}
+
+// Returns `true` if `i` is only whitespace and well formed fields, of any
+// type
+fn only_fields(i: &[u8], config: &ParserConfig) -> bool {
+    let mut remaining_input = i;
+    loop {
+        remaining_input = match my_multispace(remaining_input) {
+            Ok((x, _)) => x,
+            Err(_) => return false,
+        };
+        if remaining_input == b"" {
+            return true;
+        }
+        remaining_input = match parse_delimited_null(remaining_input, config) {
+            Ok((x, _)) => x,
+            _ => match skip_delimited_string(remaining_input, config) {
+                Ok((x, _)) => x,
+                _ => return false,
+            },
+        };
+    }
 }
 
 #[cfg(test)]
@@ -254,15 +1076,16 @@ mod tests {
 
     #[test]
     fn test_parse_string() {
-        let x = parse_string(b"\"hello world\"");
+        let config = ParserConfig::default();
+        let x = parse_string(b"\"hello world\"", &config);
         assert_eq!(x.unwrap().1, Data::String("hello world".to_string()));
-        let x = parse_string(b"\" \"");
+        let x = parse_string(b"\" \"", &config);
         assert_eq!(x.unwrap().1, Data::String(" ".to_string()));
-        let x = parse_string(b"\"\"");
+        let x = parse_string(b"\"\"", &config);
         assert_eq!(x.unwrap().1, Data::String("".to_string()));
-        let x = parse_string(b"hello");
+        let x = parse_string(b"hello", &config);
         assert_eq!(x.unwrap().1, Data::String("hello".to_string()));
-        let x = parse_string(b"hello world");
+        let x = parse_string(b"hello world", &config);
         assert_eq!(x.unwrap().1, Data::String("hello".to_string()));
     }
 
@@ -300,17 +1123,18 @@ mod tests {
 
     #[test]
     fn test_parse_field() {
-        let s = parse_field(b"< hello >");
+        let config = ParserConfig::default();
+        let s = parse_field(b"< hello >", &config);
         assert_eq!(s.unwrap().1, Data::String("hello".to_string()));
-        let i = parse_field(b"<123>");
+        let i = parse_field(b"<123>", &config);
         assert_eq!(i.unwrap().1, Data::Int(123));
-        let f = parse_field(b"< 123.123 >");
+        let f = parse_field(b"< 123.123 >", &config);
         assert_eq!(f.unwrap().1, Data::Float(123.123));
-        let b = parse_field(b"< 1 >");
+        let b = parse_field(b"< 1 >", &config);
         assert_eq!(b.unwrap().1, Data::Bool(true));
-        let n = parse_field(b"< >");
+        let n = parse_field(b"< >", &config);
         assert_eq!(n.unwrap().1, Data::Null);
-        let n2 = parse_field(b"<>");
+        let n2 = parse_field(b"<>", &config);
         assert_eq!(n2.unwrap().1, Data::Null);
     }
 
@@ -348,6 +1172,15 @@ mod tests {
         assert_eq!(failing2, None);
         let failing3 = parse_line(b"<+ 1>");
         assert_eq!(failing3, None);
+
+        // bare strings can't span lines
+        assert_eq!(parse_line(b"<a\nb>"), None);
+        assert_eq!(parse_line(b"<a\r\nb>"), None);
+        let greedy = ParserConfig {
+            greedy_bare_strings: true,
+            ..ParserConfig::default()
+        };
+        assert_eq!(parse_line_opts(b"<a\nb>", &greedy), None);
     }
 
     #[test]
@@ -418,6 +1251,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_line_with_schema_custom_delimiters() {
+        let config = ParserConfig {
+            delimiters: Delimiters {
+                open: b'{',
+                close: b'}',
+            },
+            ..ParserConfig::default()
+        };
+        let schema = vec![
+            DataType::String,
+            DataType::Int,
+            DataType::Float,
+            DataType::String,
+            DataType::Bool,
+        ];
+        let line = parse_line_with_schema_opts(
+            b" { hello } {123} {123.123} {} {1} ",
+            &schema,
+            &config,
+        );
+        assert_eq!(
+            line,
+            Some(vec![
+                Data::String("hello".to_string()),
+                Data::Int(123),
+                Data::Float(123.123),
+                Data::Null,
+                Data::Bool(true)
+            ])
+        );
+
+        let string_variants = parse_line_with_schema_opts(
+            b"{ \"{}\" } {-2} {1.19999} {{} {0} ",
+            &schema,
+            &config,
+        );
+        assert_eq!(
+            string_variants,
+            Some(vec![
+                Data::String("{}".to_string()),
+                Data::Int(-2),
+                Data::Float(1.19999),
+                Data::String("{".to_string()),
+                Data::Bool(false)
+            ])
+        );
+
+        // `<` and `>` are no longer delimiters
+        let angle_brackets = parse_line_with_schema_opts(
+            b"{<a>} {1} {1.0} {>} {1}",
+            &schema,
+            &config,
+        );
+        assert_eq!(
+            angle_brackets,
+            Some(vec![
+                Data::String("<a>".to_string()),
+                Data::Int(1),
+                Data::Float(1.0),
+                Data::String(">".to_string()),
+                Data::Bool(true)
+            ])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<hello>", &schema, &config),
+            None
+        );
+
+        // and without a schema
+        assert_eq!(
+            parse_line_opts(b"{1} {{} {\"a }\"}", &config),
+            Some(vec![
+                Data::Bool(true),
+                Data::String("{".to_string()),
+                Data::String("a }".to_string()),
+            ])
+        );
+    }
+
     #[test]
     fn test_parse_line_with_schema_and_missing_fields() {
         let schema = vec![DataType::String, DataType::Int, DataType::Float];
@@ -475,5 +1388,741 @@ mod tests {
 
         let empty = parse_line_with_schema(b"", &schema);
         assert_eq!(empty, None);
+
+        let whitespace = parse_line_with_schema(b"   ", &schema);
+        assert_eq!(whitespace, None);
+        let whitespace = parse_line_with_schema(b" \t \r", &schema);
+        assert_eq!(whitespace, None);
+    }
+
+    #[test]
+    fn test_int_float_precedence() {
+        // bare integers parse as ints without a schema
+        assert_eq!(
+            parse_line(b"<123> <-5> <+7>"),
+            Some(vec![Data::Int(123), Data::Int(-5), Data::Int(7)])
+        );
+        // but as floats with a Float schema
+        assert_eq!(
+            parse_line_with_schema(b"<123>", &[DataType::Float]),
+            Some(vec![Data::Float(123.0)])
+        );
+        // only a `.` or an exponent makes a float without a schema
+        assert_eq!(
+            parse_line(b"<1.0> <1e2> <1>"),
+            Some(vec![Data::Float(1.0), Data::Float(100.0), Data::Bool(true)])
+        );
+    }
+
+    #[test]
+    fn test_greedy_bare_strings() {
+        let greedy = ParserConfig {
+            greedy_bare_strings: true,
+            ..ParserConfig::default()
+        };
+
+        // by default a `>` always ends a bare string
+        assert_eq!(parse_line(b"<a>b>"), None);
+        assert_eq!(
+            parse_line_opts(b"<a>b> <c>", &greedy),
+            Some(vec![
+                Data::String("a>b".to_string()),
+                Data::String("c".to_string())
+            ])
+        );
+        assert_eq!(
+            parse_line_opts(b"< a>b ><c>", &greedy),
+            Some(vec![
+                Data::String("a>b".to_string()),
+                Data::String("c".to_string())
+            ])
+        );
+
+        // fields without spaces between them are still split
+        let adjacent = Some(vec![
+            Data::String("a".to_string()),
+            Data::String("b".to_string()),
+        ]);
+        assert_eq!(parse_line(b"<a><b>"), adjacent);
+        assert_eq!(parse_line_opts(b"<a><b>", &greedy), adjacent);
+
+        // the `<<>` case behaves the same in both modes, but by default the
+        // bare string is truncated at the `>`
+        let schema = vec![DataType::String, DataType::String];
+        let expected = Some(vec![
+            Data::String("<".to_string()),
+            Data::String("x>y".to_string()),
+        ]);
+        assert_eq!(
+            parse_line_with_schema_opts(b"<<> <x>y>", &schema, &greedy),
+            expected
+        );
+        assert_eq!(
+            parse_line_with_schema(b"<<> <x>y>", &schema),
+            Some(vec![
+                Data::String("<".to_string()),
+                Data::String("x".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_string_transform() {
+        let lowercase = ParserConfig {
+            string_transform: StringTransform::Lowercase,
+            ..ParserConfig::default()
+        };
+        let trim = ParserConfig {
+            string_transform: StringTransform::Trim,
+            ..ParserConfig::default()
+        };
+        let line = b"<\"  Hello World \"> <BYE> <\"\">";
+
+        assert_eq!(
+            parse_line(line),
+            Some(vec![
+                Data::String("  Hello World ".to_string()),
+                Data::String("BYE".to_string()),
+                Data::String("".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse_line_opts(line, &lowercase),
+            Some(vec![
+                Data::String("  hello world ".to_string()),
+                Data::String("bye".to_string()),
+                Data::String("".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse_line_opts(line, &trim),
+            Some(vec![
+                Data::String("Hello World".to_string()),
+                Data::String("BYE".to_string()),
+                Data::String("".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(
+                b"<Hi>",
+                &[DataType::String],
+                &lowercase
+            ),
+            Some(vec![Data::String("hi".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_bool_tokens() {
+        let config = ParserConfig {
+            true_tokens: vec!["T".to_string(), "TRUE".to_string()],
+            false_tokens: vec!["F".to_string()],
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_opts(b"<T> < TRUE > <F> <1> <Tea>", &config),
+            Some(vec![
+                Data::Bool(true),
+                Data::Bool(true),
+                Data::Bool(false),
+                Data::Bool(true),
+                Data::String("Tea".to_string()),
+            ])
+        );
+        let schema = vec![DataType::Bool, DataType::Bool];
+        assert_eq!(
+            parse_line_with_schema_opts(b"<F><T>", &schema, &config),
+            Some(vec![Data::Bool(false), Data::Bool(true)])
+        );
+        // the default stays strict
+        assert_eq!(
+            parse_line(b"<T> <F>"),
+            Some(vec![
+                Data::String("T".to_string()),
+                Data::String("F".to_string()),
+            ])
+        );
+        assert_eq!(parse_line_with_schema(b"<T>", &schema), None);
+    }
+
+    #[test]
+    fn test_lenient_bools() {
+        let schema = vec![DataType::Bool, DataType::Int];
+        let line = b"<2> <12>";
+        assert_eq!(parse_line_with_schema(line, &schema), None);
+
+        let config = ParserConfig {
+            lenient_bools: true,
+            ..ParserConfig::default()
+        };
+        let mut stats = ParseStats::default();
+        assert_eq!(
+            parse_line_with_schema_stats(line, &schema, &config, &mut stats),
+            Some(vec![Data::Null, Data::Int(12)])
+        );
+        assert_eq!(
+            parse_line_with_schema_stats(
+                b"<1> <3>", &schema, &config, &mut stats
+            ),
+            Some(vec![Data::Bool(true), Data::Int(3)])
+        );
+        // a discarded row does not count
+        assert_eq!(
+            parse_line_with_schema_stats(
+                b"<yes> <bad>",
+                &schema,
+                &config,
+                &mut stats
+            ),
+            None
+        );
+        assert_eq!(stats.nulled_bools, 1);
+    }
+
+    #[test]
+    fn test_lone_signs() {
+        let schema = vec![DataType::String, DataType::Int];
+        // by default a lone sign is a string, also during inference
+        assert_eq!(
+            parse_line(b"<-> <+>"),
+            Some(vec![
+                Data::String(String::from("-")),
+                Data::String(String::from("+"))
+            ])
+        );
+        assert_eq!(
+            parse_line_with_schema(b"<-> <1>", &schema),
+            Some(vec![Data::String(String::from("-")), Data::Int(1)])
+        );
+        assert_eq!(parse_line_with_schema(b"<1> <+>", &schema), None);
+
+        let config = ParserConfig {
+            reject_lone_signs: true,
+            ..ParserConfig::default()
+        };
+        assert_eq!(parse_line_opts(b"<->", &config), None);
+        assert_eq!(parse_line_opts(b"<+>", &config), None);
+        assert_eq!(
+            parse_line_with_schema_opts(b"<-> <1>", &schema, &config),
+            None
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<+> <1>", &schema, &config),
+            None
+        );
+        // signs that are part of a value are unaffected
+        assert_eq!(
+            parse_line_with_schema_opts(b"<\"-\"> <-1>", &schema, &config),
+            Some(vec![Data::String(String::from("-")), Data::Int(-1)])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<-a> <+1>", &schema, &config),
+            Some(vec![Data::String(String::from("-a")), Data::Int(1)])
+        );
+    }
+
+    #[test]
+    fn test_delimiters_balanced() {
+        let delimiters = Delimiters::default();
+        let balanced: &[&[u8]] = &[
+            b"",
+            b"  ",
+            b"<1> <2> <3>",
+            b"< 1 ><hi>< > <>",
+            b"<<> <0>",
+            // delimiters inside a quoted string don't count
+            b"<\"a > b\"> <1>",
+            b"<\">>\"> <\"<<\">",
+            b"< \"<\" >",
+        ];
+        for line in balanced {
+            assert!(delimiters_balanced(line, delimiters));
+        }
+        let unbalanced: &[&[u8]] = &[
+            b"<1> <2",
+            b"<1> 2>",
+            b"<1>> <2>",
+            b"1",
+            b"<\"a > b\"",
+            b"<\"a\" > b\">",
+        ];
+        for line in unbalanced {
+            assert!(!delimiters_balanced(line, delimiters));
+            assert_eq!(parse_line(line), None);
+        }
+        // an unclosed quote is just part of a bare string
+        assert!(delimiters_balanced(b"<\"a> <1>", delimiters));
+        assert_eq!(
+            parse_line(b"<\"a> <1>"),
+            Some(vec![Data::String(String::from("\"a")), Data::Bool(true)])
+        );
+        assert_eq!(
+            parse_line(b"<\">>\"> <1>"),
+            Some(vec![Data::String(String::from(">>")), Data::Bool(true)])
+        );
+        // balanced rows can still be invalid
+        assert!(delimiters_balanced(b"<1> <2 <3>", delimiters));
+        assert_eq!(parse_line(b"<1> <2 <3>"), None);
+    }
+
+    #[test]
+    fn test_decimal_separator() {
+        let schema = vec![DataType::Float];
+        let config = ParserConfig {
+            decimal_separator: Some(b','),
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_opts(b"<1,25> <-0,5> <1,5e2> <2>", &config),
+            Some(vec![
+                Data::Float(1.25),
+                Data::Float(-0.5),
+                Data::Float(150.0),
+                Data::Int(2)
+            ])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<1,25>", &schema, &config),
+            Some(vec![Data::Float(1.25)])
+        );
+        // a `.` is no longer a decimal separator
+        assert_eq!(
+            parse_line_opts(b"<1.25>", &config),
+            Some(vec![Data::String(String::from("1.25"))])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<1.25>", &schema, &config),
+            None
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<1,2,5>", &schema, &config),
+            None
+        );
+
+        // `<1,25>` under each mode
+        let line = b"<1,25>";
+        let string = Some(vec![Data::String(String::from("1,25"))]);
+        assert_eq!(parse_line(line), string);
+        assert_eq!(parse_line_with_schema(line, &schema), None);
+        let thousands = ParserConfig {
+            thousands_separator: Some(b','),
+            ..ParserConfig::default()
+        };
+        assert_eq!(parse_line_opts(line, &thousands), string);
+        assert_eq!(
+            parse_line_opts(line, &config),
+            Some(vec![Data::Float(1.25)])
+        );
+        let both = ParserConfig {
+            thousands_separator: Some(b','),
+            decimal_separator: Some(b','),
+            ..ParserConfig::default()
+        };
+        assert_eq!(parse_line_opts(line, &both), Some(vec![Data::Float(1.25)]));
+        // the separator is only a decimal separator when they are the same
+        assert_eq!(
+            parse_line_opts(b"<1,250>", &thousands),
+            Some(vec![Data::Int(1250)])
+        );
+        assert_eq!(
+            parse_line_opts(b"<1,250>", &both),
+            Some(vec![Data::Float(1.25)])
+        );
+        // but not when they differ
+        let european = ParserConfig {
+            thousands_separator: Some(b'.'),
+            decimal_separator: Some(b','),
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_opts(b"<1.000> <1,25>", &european),
+            Some(vec![Data::Int(1000), Data::Float(1.25)])
+        );
+    }
+
+    #[test]
+    fn test_radix_ints() {
+        let schema = vec![DataType::Int];
+        assert_eq!(
+            parse_line(b"<0o17> <0b11>"),
+            Some(vec![
+                Data::String(String::from("0o17")),
+                Data::String(String::from("0b11"))
+            ])
+        );
+        assert_eq!(parse_line_with_schema(b"<0o17>", &schema), None);
+
+        let config = ParserConfig {
+            radix_ints: true,
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_opts(b"<0o17> <0b11> < -0o755 > <0b1> <12>", &config),
+            Some(vec![
+                Data::Int(15),
+                Data::Int(3),
+                Data::Int(-493),
+                Data::Int(1),
+                Data::Int(12)
+            ])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<0o17>", &schema, &config),
+            Some(vec![Data::Int(15)])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<+0b11>", &schema, &config),
+            Some(vec![Data::Int(3)])
+        );
+        // digits that are not valid in the base are rejected
+        for field in &["<0o9>", "<0b12>", "<0o>", "<0x1f>", "<0O17>"] {
+            assert_eq!(
+                parse_line_with_schema_opts(field.as_bytes(), &schema, &config),
+                None
+            );
+            assert_eq!(
+                parse_line_opts(field.as_bytes(), &config),
+                Some(vec![Data::String(String::from(
+                    &field[1..field.len() - 1]
+                ))])
+            );
+        }
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let schema = vec![DataType::Int];
+        assert_eq!(
+            parse_line(b"<1,000>"),
+            Some(vec![Data::String(String::from("1,000"))])
+        );
+        assert_eq!(parse_line_with_schema(b"<1,000>", &schema), None);
+
+        let config = ParserConfig {
+            thousands_separator: Some(b','),
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_opts(b"<1,000> <-1,234,567> <+12> <1000>", &config),
+            Some(vec![
+                Data::Int(1000),
+                Data::Int(-1_234_567),
+                Data::Int(12),
+                Data::Int(1000)
+            ])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<1,000,000>", &schema, &config),
+            Some(vec![Data::Int(1_000_000)])
+        );
+        // badly grouped digits are not integers
+        for field in &["<1,00>", "<1,0000>", "<1000,000>", "<1,>", "<,100>"] {
+            assert_eq!(
+                parse_line_with_schema_opts(field.as_bytes(), &schema, &config),
+                None
+            );
+            assert_eq!(
+                parse_line_opts(field.as_bytes(), &config),
+                Some(vec![Data::String(String::from(
+                    &field[1..field.len() - 1]
+                ))])
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_line_with_schema_projected() {
+        let schema = vec![
+            DataType::Bool,
+            DataType::String,
+            DataType::Int,
+            DataType::Float,
+        ];
+        let lines: &[&[u8]] = &[
+            b"<1> <hi> <12> <1.5>",
+            b"<0> <\"a b\"> <> <2>",
+            b"<1> <x>",
+            // invalid in a column that is not kept
+            b"<1> <x> <bad> <1.0>",
+            // invalid utf-8 in a string column that is not kept
+            b"<1> <\xff> <12> <1.5>",
+            b"",
+        ];
+        for line in lines {
+            for columns in
+                &[vec![0, 3], vec![3, 1], vec![1], vec![2, 2], vec![]]
+            {
+                let expected =
+                    parse_line_with_schema(line, &schema).map(|row| {
+                        columns
+                            .iter()
+                            .map(|&c| row[c].clone())
+                            .collect::<Vec<_>>()
+                    });
+                assert_eq!(
+                    parse_line_with_schema_projected(line, &schema, columns),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_strict_width() {
+        let schema = vec![DataType::Int, DataType::Bool, DataType::String];
+        assert_eq!(
+            parse_line_with_schema(b"<12>", &schema),
+            Some(vec![Data::Int(12), Data::Null, Data::Null])
+        );
+
+        let config = ParserConfig {
+            strict_width: true,
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_with_schema_opts(b"<12>", &schema, &config),
+            None
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<12> <1> ", &schema, &config),
+            None
+        );
+        // explicit missing fields and extra fields are still fine
+        assert_eq!(
+            parse_line_with_schema_opts(b"<12> <> <>", &schema, &config),
+            Some(vec![Data::Int(12), Data::Null, Data::Null])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<12> <1> <a> <b>", &schema, &config),
+            Some(vec![
+                Data::Int(12),
+                Data::Bool(true),
+                Data::String(String::from("a"))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_spaced_bare_strings() {
+        let schema = vec![DataType::String, DataType::Int];
+        assert_eq!(parse_line(b"<bye world>"), None);
+        assert_eq!(parse_line_with_schema(b"<bye world> <1>", &schema), None);
+
+        let config = ParserConfig {
+            spaced_bare_strings: true,
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_opts(b"< bye world  > <1>", &config),
+            Some(vec![
+                Data::String(String::from("bye world")),
+                Data::Bool(true)
+            ])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<bye world> <12>", &schema, &config),
+            Some(vec![Data::String(String::from("bye world")), Data::Int(12)])
+        );
+        // numbers are unaffected
+        assert_eq!(
+            parse_line_with_schema_opts(b"<a> <1 2>", &schema, &config),
+            None
+        );
+        let greedy = ParserConfig {
+            greedy_bare_strings: true,
+            ..config
+        };
+        assert_eq!(
+            parse_line_with_schema_opts(b"<a>b c > <1>", &schema, &greedy),
+            Some(vec![Data::String(String::from("a>b c")), Data::Int(1)])
+        );
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        let schema = vec![DataType::Int, DataType::String];
+        let malformed: &[&[u8]] = &[
+            b"<",
+            b">",
+            b"<1",
+            b"1>",
+            b"<1> <",
+            b"<1> >",
+            b"<\"",
+            b"<\"hi\" there>",
+            b"<1 2>",
+            b"<1> <\xff\xfe>",
+            b"<1> <\"\xc3\x28\">",
+            b"\x00\x01\x02",
+            b"<1>\x00<a>",
+            b"<1>stray <a>",
+        ];
+        for line in malformed {
+            assert_eq!(parse_line(line), None, "{:?}", line);
+            assert_eq!(
+                parse_line_with_schema(line, &schema),
+                None,
+                "{:?}",
+                line
+            );
+        }
+        // integers that overflow an `i64` are floats, so not `Int`s
+        let overflows: &[&[u8]] =
+            &[b"<9223372036854775808> <a>", b"<-9223372036854775809> <a>"];
+        for line in overflows {
+            assert_eq!(parse_line_with_schema(line, &schema), None);
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_bytes_never_panic() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        // mostly bytes that mean something to the parser, so that the lines
+        // get past the first field, with the odd arbitrary byte
+        const ALPHABET: &[u8] = b"<>\" +-.,0123456789eE#abc\t\r\n\xff";
+        let schemas = [
+            vec![DataType::Bool, DataType::Int],
+            vec![DataType::Float, DataType::String, DataType::Int],
+            vec![DataType::String],
+        ];
+        let configs = [
+            ParserConfig::default(),
+            ParserConfig {
+                greedy_bare_strings: true,
+                spaced_bare_strings: true,
+                lenient_bools: true,
+                thousands_separator: Some(b','),
+                decimal_separator: Some(b','),
+                radix_ints: true,
+                reject_lone_signs: true,
+                strict_width: true,
+                strict_trailing: true,
+                true_tokens: vec![String::from("yes")],
+                ..ParserConfig::default()
+            },
+        ];
+        let mut rng = StdRng::seed_from_u64(180);
+        for _ in 0..20_000 {
+            let len = rng.gen_range(0, 24);
+            let line: Vec<u8> = (0..len)
+                .map(|_| {
+                    if rng.gen_bool(0.1) {
+                        rng.gen()
+                    } else {
+                        ALPHABET[rng.gen_range(0, ALPHABET.len())]
+                    }
+                })
+                .collect();
+            for config in &configs {
+                parse_line_opts(&line, config);
+                for schema in &schemas {
+                    parse_line_with_schema_opts(&line, schema, config);
+                    parse_line_with_schema_projected_stats(
+                        &line,
+                        schema,
+                        &[0],
+                        config,
+                        &mut ParseStats::default(),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_strict_trailing() {
+        let schema = vec![DataType::Int, DataType::Bool];
+        let garbage = b"<12> <1> garbage";
+        let expected = Some(vec![Data::Int(12), Data::Bool(true)]);
+        assert_eq!(parse_line_with_schema(garbage, &schema), expected);
+
+        let config = ParserConfig {
+            strict_trailing: true,
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line_with_schema_opts(garbage, &schema, &config),
+            None
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<12> <1> <2", &schema, &config),
+            None
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<12> <1>>", &schema, &config),
+            None
+        );
+        // trailing whitespace and extra fields are still fine
+        for line in &[
+            &b"<12> <1>  \t"[..],
+            &b"<12> <1> <> <hi> <\" a b \"> <2.5>"[..],
+        ] {
+            assert_eq!(
+                parse_line_with_schema_opts(line, &schema, &config),
+                expected
+            );
+        }
+        // as are rows with fewer fields than the schema
+        assert_eq!(
+            parse_line_with_schema_opts(b"<12>", &schema, &config),
+            Some(vec![Data::Int(12), Data::Null])
+        );
+    }
+
+    #[test]
+    fn test_bool_null_sentinels() {
+        let config = ParserConfig {
+            bool_null_sentinels: vec![9, -1],
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_line(b"<9> <-1>"),
+            Some(vec![Data::Int(9), Data::Int(-1)])
+        );
+        assert_eq!(
+            parse_line_opts(b"<9> <-1> <2> <1>", &config),
+            Some(vec![Data::Null, Data::Null, Data::Int(2), Data::Bool(true)])
+        );
+
+        let schema = vec![DataType::Bool, DataType::Int];
+        assert_eq!(
+            parse_line_with_schema_opts(b"<9> <9>", &schema, &config),
+            Some(vec![Data::Null, Data::Int(9)])
+        );
+        assert_eq!(
+            parse_line_with_schema_opts(b"<2> <9>", &schema, &config),
+            None
+        );
+        assert_eq!(parse_line_with_schema(b"<9> <9>", &schema), None);
+    }
+
+    #[test]
+    fn test_parse_line_raw() {
+        let line = b"<01> < +2 ><1.50e1> < \" ho ho \" > <> <hi> <0>";
+        let fields = parse_line_raw(line).unwrap();
+        assert_eq!(
+            fields,
+            vec![
+                (Data::Int(1), String::from("01")),
+                (Data::Int(2), String::from("+2")),
+                (Data::Float(15.0), String::from("1.50e1")),
+                (
+                    Data::String(String::from(" ho ho ")),
+                    String::from("\" ho ho \"")
+                ),
+                (Data::Null, String::new()),
+                (Data::String(String::from("hi")), String::from("hi")),
+                (Data::Bool(false), String::from("0")),
+            ]
+        );
+        // the values are the same as those of `parse_line`
+        let values: Vec<Data> = fields.into_iter().map(|(d, _)| d).collect();
+        assert_eq!(parse_line(line), Some(values));
+
+        assert_eq!(parse_line_raw(b""), Some(vec![]));
+        assert_eq!(parse_line_raw(b"<1> <2"), None);
+        assert_eq!(parse_line_raw(b"<1> junk"), None);
     }
 }
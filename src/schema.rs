@@ -1,16 +1,24 @@
 //! A module for inferring `SoR` schemas.
-use crate::dataframe::Data;
-use crate::parsers::parse_line;
+use crate::dataframe::{Data, LineReader};
+use crate::parsers::{is_comment, parse_line, parse_line_opts, ParserConfig};
 use deepsize::DeepSizeOf;
-use easy_reader::EasyReader;
+use memchr::memrchr;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs::{self, File};
-use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::time::UNIX_EPOCH;
 
 /// A plain enumeration of the possible data types used in `SoR`, this one
 /// without its accompanying value.
-#[derive(PartialEq, Debug, Clone, Serialize, Deserialize, DeepSizeOf)]
+///
+/// `DataType`s are ordered by their precedence, so `String > Float > Int >
+/// Bool`. Note that this is the reverse of the order the variants are
+/// declared in, so the ordering can't be derived.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize, DeepSizeOf)]
 pub enum DataType {
     /// Has the highest data type precedence.
     String,
@@ -22,19 +30,280 @@ pub enum DataType {
     Bool,
 }
 
-// Get the dominant data type between two `DataType`s
-fn get_dominant_data_type(
-    cur_dominant_type: &DataType,
-    other_type: &Data,
-) -> DataType {
-    match (cur_dominant_type, other_type) {
-        (_, Data::String(_)) => DataType::String,
-        (DataType::String, _) => DataType::String,
-        (_, Data::Float(_)) => DataType::Float,
-        (DataType::Float, _) => DataType::Float,
-        (_, Data::Int(_)) => DataType::Int,
-        (DataType::Int, _) => DataType::Int,
-        _ => DataType::Bool,
+impl DataType {
+    // Higher values have a higher precedence
+    fn precedence(&self) -> u8 {
+        match self {
+            DataType::String => 3,
+            DataType::Float => 2,
+            DataType::Int => 1,
+            DataType::Bool => 0,
+        }
+    }
+
+    /// Returns `true` if `self` and `other` are the same `DataType`, or if
+    /// one is a `Bool` and the other is an `Int`. A column of only `0`s and
+    /// `1`s may be inferred as either, so this ambiguity is usually not
+    /// meaningful when comparing schemas.
+    pub fn compatible_with(&self, other: &DataType) -> bool {
+        match (self, other) {
+            (DataType::Bool, DataType::Int) => true,
+            (DataType::Int, DataType::Bool) => true,
+            _ => self == other,
+        }
+    }
+}
+
+/// Parses a type name, `"bool"`, `"int"`, `"float"` or `"string"`, or its
+/// first letter, ignoring case. These are the names used by
+/// [`Column::type_name`](crate::dataframe::Column::type_name).
+///
+/// # Examples
+/// ```
+/// use sorer::schema::DataType;
+/// use std::convert::TryFrom;
+///
+/// assert_eq!(DataType::try_from("Int"), Ok(DataType::Int));
+/// assert_eq!(DataType::try_from("s"), Ok(DataType::String));
+/// assert!(DataType::try_from("number").is_err());
+/// ```
+impl TryFrom<&str> for DataType {
+    type Error = String;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name.to_ascii_lowercase().as_str() {
+            "bool" | "b" => Ok(DataType::Bool),
+            "int" | "i" => Ok(DataType::Int),
+            "float" | "f" => Ok(DataType::Float),
+            "string" | "s" => Ok(DataType::String),
+            _ => Err(format!("{} is not the name of a data type", name)),
+        }
+    }
+}
+
+/// The default `DataType` is a `Bool`, the type with the lowest precedence,
+/// which is where inferring the type of a column starts.
+impl Default for DataType {
+    fn default() -> Self {
+        DataType::Bool
+    }
+}
+
+impl PartialOrd for DataType {
+    fn partial_cmp(&self, other: &DataType) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DataType {
+    fn cmp(&self, other: &DataType) -> Ordering {
+        self.precedence().cmp(&other.precedence())
+    }
+}
+
+/// Returns `true` if the schemas `a` and `b` have the same width and each of
+/// their columns are [compatible](DataType::compatible_with).
+pub fn schemas_compatible(a: &[DataType], b: &[DataType]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(t1, t2)| t1.compatible_with(t2))
+}
+
+/// A single difference between two schemas, as reported by [`schema_diff`].
+#[derive(PartialEq, Debug, Clone)]
+pub enum SchemaDiffEntry {
+    /// The column at `index` has a different `DataType` in each schema.
+    TypeChanged {
+        /// The index of the column.
+        index: usize,
+        /// The type of the column in the first schema.
+        a: DataType,
+        /// The type of the column in the second schema.
+        b: DataType,
+    },
+    /// The column at `index` only exists in the first schema.
+    OnlyInA {
+        /// The index of the column.
+        index: usize,
+        /// The type of the column in the first schema.
+        data_type: DataType,
+    },
+    /// The column at `index` only exists in the second schema.
+    OnlyInB {
+        /// The index of the column.
+        index: usize,
+        /// The type of the column in the second schema.
+        data_type: DataType,
+    },
+}
+
+impl fmt::Display for SchemaDiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaDiffEntry::TypeChanged { index, a, b } => {
+                write!(f, "column {}: {:?} != {:?}", index, a, b)
+            }
+            SchemaDiffEntry::OnlyInA { index, data_type } => {
+                write!(f, "column {}: {:?} only in a", index, data_type)
+            }
+            SchemaDiffEntry::OnlyInB { index, data_type } => {
+                write!(f, "column {}: {:?} only in b", index, data_type)
+            }
+        }
+    }
+}
+
+/// Reports every difference between the schemas `a` and `b`, in column
+/// order. Columns of differing types are reported as
+/// [`TypeChanged`](SchemaDiffEntry::TypeChanged), and if the schemas have
+/// different widths every extra column of the wider schema is reported as
+/// well. An empty result means the schemas are equal.
+///
+/// Columns are compared exactly, so a `Bool` column is reported as differing
+/// from an `Int` column even though they are
+/// [compatible](DataType::compatible_with).
+pub fn schema_diff(a: &[DataType], b: &[DataType]) -> Vec<SchemaDiffEntry> {
+    let mut diff = Vec::new();
+    for (index, (t1, t2)) in a.iter().zip(b).enumerate() {
+        if t1 != t2 {
+            diff.push(SchemaDiffEntry::TypeChanged {
+                index,
+                a: t1.clone(),
+                b: t2.clone(),
+            });
+        }
+    }
+    for (index, data_type) in a.iter().enumerate().skip(b.len()) {
+        diff.push(SchemaDiffEntry::OnlyInA {
+            index,
+            data_type: data_type.clone(),
+        });
+    }
+    for (index, data_type) in b.iter().enumerate().skip(a.len()) {
+        diff.push(SchemaDiffEntry::OnlyInB {
+            index,
+            data_type: data_type.clone(),
+        });
+    }
+    diff
+}
+
+/// Returns the dominant `DataType` between the `current` dominant type and
+/// the type of `value`, following the precedence described
+/// [here](../index.html#schema-inference). A `Data::Null` never changes the
+/// `current` type.
+///
+/// This is the primitive that schema inference is built on, and can be
+/// folded over any stream of `Data` to infer the type of a column.
+///
+/// # Examples
+/// ```
+/// use sorer::dataframe::Data;
+/// use sorer::schema::{dominant_type, DataType};
+///
+/// let values = vec![Data::Bool(true), Data::Null, Data::Int(12)];
+/// let data_type = values
+///     .iter()
+///     .fold(DataType::default(), |cur, value| dominant_type(&cur, value));
+/// assert_eq!(data_type, DataType::Int);
+///
+/// assert_eq!(dominant_type(&data_type, &Data::Float(1.2)), DataType::Float);
+/// ```
+pub fn dominant_type(current: &DataType, value: &Data) -> DataType {
+    let value_type = match value {
+        Data::String(_) => DataType::String,
+        Data::Float(_) => DataType::Float,
+        Data::Int(_) => DataType::Int,
+        Data::Bool(_) | Data::Null => DataType::Bool,
+    };
+    current.clone().max(value_type)
+}
+
+/// Merges `schemas` into a single schema that every one of them can be
+/// parsed with. The result is as wide as the widest schema and each column is
+/// the dominant type of that column across all `schemas`, following the same
+/// precedence as [`dominant_type`]. A column that a narrower schema lacks is
+/// treated as a `Bool` column, since its cells are all missing.
+pub fn merge_schemas(schemas: &[Vec<DataType>]) -> Vec<DataType> {
+    let width = schemas.iter().map(Vec::len).max().unwrap_or(0);
+    (0..width)
+        .map(|i| {
+            schemas
+                .iter()
+                .filter_map(|schema| schema.get(i))
+                .max()
+                .cloned()
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Detects when the rows of a stream no longer match the schema they are
+/// parsed with, such as when a column of the schema is an `Int` but the
+/// stream has started to contain `String`s in it. Since such rows are
+/// discarded when parsing with the schema, the rows are observed without it,
+/// with [`observe_line`](DriftDetector::observe_line) or
+/// [`observe`](DriftDetector::observe).
+///
+/// # Examples
+/// ```
+/// use sorer::schema::{DataType, DriftDetector};
+///
+/// let mut detector = DriftDetector::new(vec![DataType::Int]);
+/// detector.observe_line(b"<12>");
+/// assert!(!detector.has_drifted());
+/// detector.observe_line(b"<twelve>");
+/// assert_eq!(detector.drifted_columns(), vec![0]);
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct DriftDetector {
+    schema: Vec<DataType>,
+    observed: Vec<DataType>,
+}
+
+impl DriftDetector {
+    /// Creates a `DriftDetector` for rows that are parsed with `schema`.
+    pub fn new(schema: Vec<DataType>) -> Self {
+        let observed = vec![DataType::default(); schema.len()];
+        DriftDetector { schema, observed }
+    }
+
+    /// Adds the values of `row` to the observed type of each column. Values
+    /// past the width of the schema are ignored.
+    pub fn observe(&mut self, row: &[Data]) {
+        for (observed, value) in self.observed.iter_mut().zip(row) {
+            *observed = dominant_type(observed, value);
+        }
+    }
+
+    /// Parses `line` without a schema and observes it like
+    /// [`observe`](DriftDetector::observe). Invalid lines are ignored.
+    pub fn observe_line(&mut self, line: &[u8]) {
+        if let Some(row) = parse_line(line) {
+            self.observe(&row);
+        }
+    }
+
+    /// Returns the dominant type of every column of the schema across the
+    /// rows observed so far.
+    pub fn observed(&self) -> &[DataType] {
+        &self.observed
+    }
+
+    /// Returns the indices of the columns whose observed type has a higher
+    /// precedence than their type in the schema, in ascending order.
+    pub fn drifted_columns(&self) -> Vec<usize> {
+        self.schema
+            .iter()
+            .zip(&self.observed)
+            .enumerate()
+            .filter(|(_, (expected, observed))| observed > expected)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns `true` if the observed type of any column has a higher
+    /// precedence than its type in the schema.
+    pub fn has_drifted(&self) -> bool {
+        self.schema.iter().zip(&self.observed).any(|(e, o)| o > e)
     }
 }
 
@@ -42,7 +311,165 @@ fn get_dominant_data_type(
 /// Full information on how schema inference works can be found
 /// [here](../index.html#schema-inference)
 pub fn infer_schema(file_name: &str) -> Result<Vec<DataType>, io::Error> {
-    infer_schema_for_n_lines(file_name, 300)
+    infer_schema_with_config(file_name, &ParserConfig::default())
+}
+
+/// The same as [`infer_schema`], but parses the lines used for inference
+/// according to the given [`ParserConfig`], so that for example custom
+/// [`true_tokens`](ParserConfig::true_tokens) are inferred as `Bool`s.
+pub fn infer_schema_with_config(
+    file_name: &str,
+    config: &ParserConfig,
+) -> Result<Vec<DataType>, io::Error> {
+    infer_schema_for_n_lines(file_name, 300, config)
+}
+
+/// Returns the width of the schema of the file with the given `file_name`,
+/// which is the most fields in any of its first 500 valid lines. Only the
+/// fields of each line are split, their types are never resolved, so this is
+/// cheaper than [`infer_schema`] when only the number of columns is needed.
+/// Blank and invalid lines do not count towards the 500.
+pub fn column_count(file_name: &str) -> Result<usize, io::Error> {
+    let reader = BufReader::new(File::open(file_name)?).split(b'\n');
+    let mut width = 0;
+    let mut valid_lines = 0;
+    for line in reader {
+        if valid_lines == 500 {
+            break;
+        }
+        match parse_line(&line?) {
+            Some(fields) if !fields.is_empty() => {
+                width = width.max(fields.len());
+                valid_lines += 1;
+            }
+            _ => (),
+        }
+    }
+    Ok(width)
+}
+
+/// Counts the rows of the file with the given `file_name` by their number of
+/// fields, mapping each width to the number of rows that have it. Schema
+/// inference uses the widest row, so this shows when a file is ragged, such as
+/// one of mostly 4 field rows with a few 6 field rows. Blank and invalid lines
+/// are not counted.
+pub fn field_count_histogram(
+    file_name: &str,
+) -> Result<BTreeMap<usize, usize>, io::Error> {
+    let reader = BufReader::new(File::open(file_name)?).split(b'\n');
+    let mut histogram = BTreeMap::new();
+    for line in reader {
+        match parse_line(&line?) {
+            Some(fields) if !fields.is_empty() => {
+                *histogram.entry(fields.len()).or_insert(0) += 1;
+            }
+            _ => (),
+        }
+    }
+    Ok(histogram)
+}
+
+/// Reads column names from a header comment on the first line of the file
+/// with the given `file_name`, such as `# id name score` with a `prefix` of
+/// `b'#'`. The names are separated by whitespace. Leading whitespace before
+/// `prefix` is ignored, the same as for
+/// [`comment_prefix`](ParserConfig::comment_prefix). Returns `None` if the
+/// first line does not start with `prefix`.
+pub fn header_names(
+    file_name: &str,
+    prefix: u8,
+) -> Result<Option<Vec<String>>, io::Error> {
+    let mut line = Vec::new();
+    BufReader::new(File::open(file_name)?).read_until(b'\n', &mut line)?;
+    let start = line
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(line.len());
+    Ok(match line[start..].split_first() {
+        Some((first, rest)) if *first == prefix => Some(
+            String::from_utf8_lossy(rest)
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+        ),
+        _ => None,
+    })
+}
+
+/// Returns `width` positional column names, `col0` to `col{width - 1}`, for
+/// columns that have not been given a name.
+pub fn default_column_names(width: usize) -> Vec<String> {
+    (0..width).map(|i| format!("col{}", i)).collect()
+}
+
+/// Infers the schema of the file with the given `file_name` like
+/// [`infer_schema`], caching it in a sidecar file named
+/// `<file_name>.schema`. See [`infer_schema_cached_at`] for how the cache is
+/// used.
+pub fn infer_schema_cached(
+    file_name: &str,
+) -> Result<Vec<DataType>, io::Error> {
+    infer_schema_cached_at(file_name, &format!("{}.schema", file_name))
+}
+
+/// Infers the schema of the file with the given `file_name` like
+/// [`infer_schema`], caching it in the file at `cache_path`.
+///
+/// The cache records the size and modification time of the file. If both
+/// still match, the cached schema is returned without reading the file.
+/// Otherwise, or if the cache is missing or unreadable, the schema is
+/// inferred again and the cache is rewritten. Failing to write the cache is
+/// not an error, the inferred schema is returned anyway.
+pub fn infer_schema_cached_at(
+    file_name: &str,
+    cache_path: &str,
+) -> Result<Vec<DataType>, io::Error> {
+    cached_schema_with(file_name, cache_path, || infer_schema(file_name))
+}
+
+// Implements `infer_schema_cached_at`, calling `infer` when the cache is stale
+fn cached_schema_with<F>(
+    file_name: &str,
+    cache_path: &str,
+    infer: F,
+) -> Result<Vec<DataType>, io::Error>
+where
+    F: FnOnce() -> Result<Vec<DataType>, io::Error>,
+{
+    let metadata = fs::metadata(file_name)?;
+    let mtime = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let key = format!(
+        "{} {} {}",
+        metadata.len(),
+        mtime.as_secs(),
+        mtime.subsec_nanos()
+    );
+
+    if let Ok(cache) = fs::read_to_string(cache_path) {
+        let mut lines = cache.lines();
+        if lines.next() == Some(&key) {
+            let schema: Option<Vec<DataType>> = lines
+                .next()
+                .unwrap_or("")
+                .split_whitespace()
+                .map(|t| DataType::try_from(t).ok())
+                .collect();
+            if let Some(schema) = schema {
+                return Ok(schema);
+            }
+        }
+    }
+
+    let schema = infer()?;
+    let types: Vec<String> =
+        schema.iter().map(|t| format!("{:?}", t)).collect();
+    // the cache is only an optimization, so a cache that can't be written
+    // is simply ignored
+    let _ = fs::write(cache_path, format!("{}\n{}\n", key, types.join(" ")));
+    Ok(schema)
 }
 
 /// Infers the schema of the file opened by the given `reader`.
@@ -51,73 +478,360 @@ pub fn infer_schema(file_name: &str) -> Result<Vec<DataType>, io::Error> {
 pub(crate) fn infer_schema_for_n_lines(
     file_name: &str,
     num_lines_to_parse: usize,
+    config: &ParserConfig,
 ) -> Result<Vec<DataType>, io::Error> {
+    Ok(sample_n_lines(file_name, num_lines_to_parse, config)?.0)
+}
+
+// Infers the schema from the lines sampled at the beginning, middle and end
+// of the file, returning it along with the number of lines that were sampled.
+// Stops early once the sample is stable, see
+// `ParserConfig::inference_stable_rows`.
+fn sample_n_lines(
+    file_name: &str,
+    num_lines_to_parse: usize,
+    config: &ParserConfig,
+) -> Result<(Vec<DataType>, usize), io::Error> {
     let book_end = num_lines_to_parse / 3;
-    let mut parsed_lines = Vec::new();
-    let mut reader = BufReader::new(File::open(file_name)?).split(b'\n');
+    let mut sample = Sample::new(config);
+    let mut reader = LineReader::with_max_line(
+        File::open(file_name)?,
+        config.max_line_bytes,
+    );
 
-    // infer the schema at the beginning
+    // infer the schema at the beginning. If the first `book_end` lines are
+    // mostly blank or invalid, keep reading (up to a cap) until enough rows
+    // with at least one field are found
+    let head_cap = book_end * HEAD_CAP_MULTIPLIER;
+    let head_rows = book_end.min(HEAD_MIN_ROWS);
     let mut i = 0;
-    while let Some(line) = reader.next() {
-        handle_line_inference(&line?, &mut parsed_lines);
-        i += 1;
-        if i == book_end {
+    while let Some(line) = reader.next_line()? {
+        if i >= head_cap || (i >= book_end && sample.valid_rows >= head_rows) {
             break;
         }
+        sample.push(line);
+        if sample.is_stable() {
+            return Ok(sample.finish());
+        }
+        i += 1;
     }
 
     // seek to middle and to infer the schema in the middle
     let mid_pt = fs::metadata(file_name)?.len() / 2;
     let mut f = File::open(file_name)?;
     f.seek(SeekFrom::Start(mid_pt))?;
-    let mut reader = BufReader::new(f).split(b'\n');
+    let mut reader = LineReader::with_max_line(f, config.max_line_bytes);
     // throw away the first line since we started somewhere randomly in the
     // middle
-    reader.next();
-    i = 0;
-    while let Some(line) = reader.next() {
-        handle_line_inference(&line?, &mut parsed_lines);
-        i += 1;
-        if i == book_end {
-            break;
+    reader.next_line()?;
+    for _ in 0..book_end {
+        match reader.next_line()? {
+            Some(line) => sample.push(line),
+            None => break,
+        }
+        if sample.is_stable() {
+            return Ok(sample.finish());
         }
     }
 
     // parse the end of the file
-    let mut backward_reader = EasyReader::new(File::open(file_name)?)?;
-    backward_reader.eof();
-    i = 0;
+    let mut backward_reader =
+        BackwardLines::new(File::open(file_name)?, config.max_line_bytes)?;
+    let mut i = 0;
     while let Some(line) = backward_reader.prev_line()? {
-        handle_line_inference(&line.as_bytes(), &mut parsed_lines);
+        sample.push(line);
+        if sample.is_stable() {
+            break;
+        }
         i += 1;
         if i == book_end {
             break;
         }
     }
 
-    let cur_width = parsed_lines.get(0).unwrap_or_else(|| EMPTY).len();
+    Ok(sample.finish())
+}
+
+// Reads the lines of a file backwards from its end, without a trailing
+// newline or carriage return. Lines longer than `max_line` bytes are returned
+// as empty lines without being read into memory
+struct BackwardLines {
+    file: File,
+    // the end of the next line, which is `None` once the first line was read
+    end: Option<u64>,
+    max_line: Option<usize>,
+    line: Vec<u8>,
+    chunk: Vec<u8>,
+}
+
+impl BackwardLines {
+    fn new(mut file: File, max_line: Option<usize>) -> io::Result<Self> {
+        let len = file.seek(SeekFrom::End(0))?;
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Empty file",
+            ));
+        }
+        // a trailing newline does not start another line
+        let mut last = [0];
+        file.seek(SeekFrom::Start(len - 1))?;
+        file.read_exact(&mut last)?;
+        let end = if last[0] == b'\n' { len - 1 } else { len };
+        Ok(BackwardLines {
+            file,
+            end: Some(end),
+            max_line,
+            line: Vec::new(),
+            chunk: vec![0; 8 * 1024],
+        })
+    }
+
+    fn prev_line(&mut self) -> io::Result<Option<&[u8]>> {
+        let end = match self.end {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+        // search backwards for the newline before the line
+        let mut pos = end;
+        let start = loop {
+            if pos == 0 {
+                break 0;
+            }
+            let from = pos.saturating_sub(self.chunk.len() as u64);
+            let chunk = &mut self.chunk[..(pos - from) as usize];
+            self.file.seek(SeekFrom::Start(from))?;
+            self.file.read_exact(chunk)?;
+            if let Some(idx) = memrchr(b'\n', chunk) {
+                break from + idx as u64 + 1;
+            }
+            pos = from;
+        };
+        self.end = start.checked_sub(1);
+
+        self.line.clear();
+        let len = end - start;
+        if matches!(self.max_line, Some(max) if len > max as u64) {
+            return Ok(Some(&[]));
+        }
+        self.line.resize(len as usize, 0);
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut self.line)?;
+        if self.line.last() == Some(&b'\r') {
+            self.line.pop();
+        }
+        Ok(Some(&self.line))
+    }
+}
+
+// The lines sampled for inference, along with the schema they infer so far
+struct Sample<'a> {
+    config: &'a ParserConfig,
+    lines: Vec<Vec<Data>>,
+    schema: Vec<DataType>,
+    // the number of consecutive lines that have not changed `schema`
+    unchanged: usize,
+    lines_read: usize,
+    // the number of lines with at least one field
+    valid_rows: usize,
+}
+
+impl<'a> Sample<'a> {
+    fn new(config: &'a ParserConfig) -> Self {
+        Sample {
+            config,
+            lines: Vec::new(),
+            schema: Vec::new(),
+            unchanged: 0,
+            lines_read: 0,
+            valid_rows: 0,
+        }
+    }
+
+    fn push(&mut self, line: &[u8]) {
+        self.lines_read += 1;
+        let num_lines = self.lines.len();
+        if handle_line_inference(line, &mut self.lines, self.config) {
+            self.valid_rows += 1;
+        }
+        let first = self.lines.first().unwrap_or(EMPTY);
+        let schema = if first.len() > self.schema.len() {
+            // a wider row replaced all the previous ones
+            schema_from_lines(&self.lines)
+        } else if self.lines.len() > num_lines {
+            let last = self.lines.last().unwrap();
+            self.schema
+                .iter()
+                .zip(last)
+                .map(|(data_type, value)| dominant_type(data_type, value))
+                .collect()
+        } else {
+            self.unchanged += 1;
+            return;
+        };
+        if schema == self.schema {
+            self.unchanged += 1;
+        } else {
+            self.schema = schema;
+            self.unchanged = 0;
+        }
+    }
+
+    // Whether sampling can stop early because the schema has not changed in
+    // a while
+    fn is_stable(&self) -> bool {
+        match self.config.inference_stable_rows {
+            Some(n) => !self.schema.is_empty() && self.unchanged >= n,
+            None => false,
+        }
+    }
+
+    fn finish(self) -> (Vec<DataType>, usize) {
+        (self.schema, self.lines_read)
+    }
+}
+
+/// Infers the schema of the file with the given `file_name` from only its
+/// last `n_lines` lines, read backwards from the end of the file. This is
+/// cheaper than [`infer_schema`], and is useful for files that are appended
+/// to over time, where the beginning of the file may be in a stale format.
+pub fn infer_schema_tail(
+    file_name: &str,
+    n_lines: usize,
+) -> Result<Vec<DataType>, io::Error> {
+    infer_schema_tail_with_config(file_name, n_lines, &ParserConfig::default())
+}
+
+/// The same as [`infer_schema_tail`], but parses the lines according to the
+/// given [`ParserConfig`]. Lines longer than
+/// [`max_line_bytes`](ParserConfig::max_line_bytes) are skipped without
+/// being read into memory.
+pub fn infer_schema_tail_with_config(
+    file_name: &str,
+    n_lines: usize,
+    config: &ParserConfig,
+) -> Result<Vec<DataType>, io::Error> {
+    let mut parsed_lines = Vec::new();
+    let mut backward_reader =
+        BackwardLines::new(File::open(file_name)?, config.max_line_bytes)?;
+    for _ in 0..n_lines {
+        match backward_reader.prev_line()? {
+            Some(line) => {
+                handle_line_inference(line, &mut parsed_lines, config);
+            }
+            None => break,
+        }
+    }
+    Ok(schema_from_lines(&parsed_lines))
+}
+
+/// Infers the schema of the file with the given `file_name`, like
+/// [`infer_schema`], but samples at most `max_bytes` bytes of the file
+/// instead of a fixed number of lines. The budget is split evenly between
+/// the beginning, the middle and the end of the file, and only complete
+/// lines within each region are used. This bounds the time spent on
+/// inference no matter how long the rows of the file are.
+///
+/// Files no larger than `max_bytes` are read completely.
+pub fn infer_schema_byte_budget(
+    file_name: &str,
+    max_bytes: usize,
+) -> Result<Vec<DataType>, io::Error> {
+    let config = ParserConfig::default();
+    let (parsed_lines, _) = sample_byte_budget(file_name, max_bytes, &config)?;
+    Ok(schema_from_lines(&parsed_lines))
+}
+
+// Parses the complete lines in the three regions sampled by
+// `infer_schema_byte_budget`, returning the lines used for inference and
+// the total number of bytes that were read
+fn sample_byte_budget(
+    file_name: &str,
+    max_bytes: usize,
+    config: &ParserConfig,
+) -> Result<(Vec<Vec<Data>>, usize), io::Error> {
+    let file_len = fs::metadata(file_name)?.len();
+    let mut parsed_lines = Vec::new();
+    let mut bytes_read = 0;
+    let regions = if file_len <= max_bytes as u64 {
+        vec![(0, file_len)]
+    } else {
+        let budget = max_bytes as u64 / 3;
+        vec![
+            (0, budget),
+            (file_len / 2, budget),
+            (file_len - budget, budget),
+        ]
+    };
+
+    let mut buffer = Vec::new();
+    for (start, len) in regions {
+        let mut f = File::open(file_name)?;
+        f.seek(SeekFrom::Start(start))?;
+        buffer.clear();
+        f.take(len).read_to_end(&mut buffer)?;
+        bytes_read += buffer.len();
+
+        let mut lines: Vec<&[u8]> = buffer.split(|&b| b == b'\n').collect();
+        // the last line is cut off unless the region ends the file, and
+        // the first line is cut off unless the region starts the file
+        if start + len < file_len {
+            lines.pop();
+        }
+        let skip = if start == 0 { 0 } else { 1 };
+        for line in lines.into_iter().skip(skip) {
+            handle_line_inference(line, &mut parsed_lines, config);
+        }
+    }
+    Ok((parsed_lines, bytes_read))
+}
+
+// Chooses the dominant type of every column of `parsed_lines`, which all
+// have the same width
+fn schema_from_lines(parsed_lines: &[Vec<Data>]) -> Vec<DataType> {
+    let cur_width = parsed_lines.first().unwrap_or(EMPTY).len();
     let mut schema = Vec::with_capacity(cur_width);
     for i in 0..cur_width {
-        let mut data_type = DataType::Bool;
-        for row in &parsed_lines {
-            data_type = get_dominant_data_type(&data_type, &row[i]);
+        let mut data_type = DataType::default();
+        for row in parsed_lines {
+            data_type = dominant_type(&data_type, &row[i]);
             if data_type == DataType::String {
                 break;
             }
         }
         schema.push(data_type);
     }
-
-    Ok(schema)
+    schema
 }
 
 const EMPTY: &Vec<Data> = &Vec::new();
 
-fn handle_line_inference(i: &[u8], current_lines: &mut Vec<Vec<Data>>) {
-    if let Some(parsed) = parse_line(i) {
+// How many times more lines than usual the head pass of schema inference
+// reads when the file starts with blank or invalid lines
+const HEAD_CAP_MULTIPLIER: usize = 10;
+
+// How many rows with at least one field the head pass of schema inference
+// collects before it stops reading past the usual number of lines
+const HEAD_MIN_ROWS: usize = 10;
+
+// Adds the row parsed from `i` to `current_lines` if it is at least as wide
+// as the rows collected so far, returning whether it had any fields
+fn handle_line_inference(
+    i: &[u8],
+    current_lines: &mut Vec<Vec<Data>>,
+    config: &ParserConfig,
+) -> bool {
+    if is_comment(i, config) {
+        return false;
+    }
+    if let Some(parsed) = parse_line_opts(i, config) {
+        if matches!(config.max_columns, Some(max) if parsed.len() > max) {
+            return false;
+        }
+        let has_fields = !parsed.is_empty();
         match parsed
             .len()
-            .cmp(&current_lines.get(0).unwrap_or_else(|| EMPTY).len())
+            .cmp(&current_lines.first().unwrap_or(EMPTY).len())
         {
             Ordering::Greater => {
                 current_lines.clear();
@@ -128,5 +842,488 @@ fn handle_line_inference(i: &[u8], current_lines: &mut Vec<Vec<Data>>) {
             }
             Ordering::Less => (),
         }
+        has_fields
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_type_default() {
+        assert_eq!(DataType::default(), DataType::Bool);
+        assert!(DataType::default() <= DataType::Int);
+    }
+
+    #[test]
+    fn test_data_type_try_from() {
+        let names = [
+            ("bool", DataType::Bool),
+            ("int", DataType::Int),
+            ("float", DataType::Float),
+            ("string", DataType::String),
+        ];
+        for (name, data_type) in names.iter() {
+            assert_eq!(DataType::try_from(*name), Ok(data_type.clone()));
+            let upper = name.to_uppercase();
+            assert_eq!(DataType::try_from(&upper[..]), Ok(data_type.clone()));
+            assert_eq!(DataType::try_from(&name[..1]), Ok(data_type.clone()));
+        }
+        assert_eq!(DataType::try_from("Float"), Ok(DataType::Float));
+        assert_eq!(
+            DataType::try_from("integer"),
+            Err("integer is not the name of a data type".to_string())
+        );
+        assert!(DataType::try_from("").is_err());
+    }
+
+    #[test]
+    fn test_data_type_ord() {
+        assert!(DataType::String > DataType::Float);
+        assert!(DataType::Float > DataType::Int);
+        assert!(DataType::Int > DataType::Bool);
+        assert!(DataType::String > DataType::Bool);
+        assert_eq!(DataType::Int.max(DataType::Float), DataType::Float);
+        let mut types = vec![
+            DataType::Int,
+            DataType::String,
+            DataType::Bool,
+            DataType::Float,
+        ];
+        types.sort();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Bool,
+                DataType::Int,
+                DataType::Float,
+                DataType::String
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compatible_with() {
+        let types = vec![
+            DataType::String,
+            DataType::Float,
+            DataType::Int,
+            DataType::Bool,
+        ];
+        for t1 in &types {
+            for t2 in &types {
+                let expected = t1 == t2
+                    || (*t1 == DataType::Bool && *t2 == DataType::Int)
+                    || (*t1 == DataType::Int && *t2 == DataType::Bool);
+                assert_eq!(t1.compatible_with(t2), expected);
+            }
+        }
+        assert!(DataType::Bool.compatible_with(&DataType::Int));
+        assert!(DataType::Int.compatible_with(&DataType::Bool));
+        assert!(!DataType::String.compatible_with(&DataType::Int));
+        assert!(!DataType::Int.compatible_with(&DataType::Float));
+    }
+
+    #[test]
+    fn test_schemas_compatible() {
+        let a = vec![DataType::Int, DataType::String, DataType::Bool];
+        let b = vec![DataType::Bool, DataType::String, DataType::Int];
+        assert!(schemas_compatible(&a, &b));
+        assert!(!schemas_compatible(&a, &b[..2]));
+        let c = vec![DataType::Int, DataType::Int, DataType::Bool];
+        assert!(!schemas_compatible(&a, &c));
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let a = vec![DataType::Int, DataType::String, DataType::Bool];
+        assert_eq!(schema_diff(&a, &a), vec![]);
+
+        let b = vec![DataType::Int, DataType::Float];
+        assert_eq!(
+            schema_diff(&a, &b),
+            vec![
+                SchemaDiffEntry::TypeChanged {
+                    index: 1,
+                    a: DataType::String,
+                    b: DataType::Float,
+                },
+                SchemaDiffEntry::OnlyInA {
+                    index: 2,
+                    data_type: DataType::Bool,
+                },
+            ]
+        );
+        assert_eq!(
+            schema_diff(&b, &a)[1],
+            SchemaDiffEntry::OnlyInB {
+                index: 2,
+                data_type: DataType::Bool,
+            }
+        );
+    }
+
+    #[test]
+    fn test_null_column_across_regions() {
+        // with 3 lines to parse, exactly one line is sampled from each of the
+        // head, middle and tail of the file
+        let config = ParserConfig::default();
+        let file = "tests/null_regions.sor";
+        let expected = vec![DataType::String, DataType::Bool];
+        assert_eq!(
+            infer_schema_for_n_lines(file, 3, &config).unwrap(),
+            expected
+        );
+        assert_eq!(infer_schema(file).unwrap(), expected);
+
+        // a column that is null in every region is a Bool no matter which
+        // order the head, middle and tail regions appear in
+        let regions = ["<1> <>\n", "<hi> <>\n", "<0> < >\n"];
+        let dir = tempfile::tempdir().unwrap();
+        for order in &[
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ] {
+            let path = dir.path().join(format!("{:?}.sor", order));
+            let input: String =
+                order.iter().map(|&r| regions[r].repeat(3)).collect();
+            fs::write(&path, input).unwrap();
+            let (schema, lines_read) =
+                sample_n_lines(path.to_str().unwrap(), 3, &config).unwrap();
+            assert_eq!(schema, expected, "regions in order {:?}", order);
+            assert_eq!(lines_read, 3);
+        }
+    }
+
+    #[test]
+    fn test_values_only_in_tail_region() {
+        let config = ParserConfig::default();
+        let file = "tests/tail_only.sor";
+        let expected = vec![DataType::Bool, DataType::Int];
+        assert_eq!(
+            infer_schema_for_n_lines(file, 3, &config).unwrap(),
+            expected
+        );
+        assert_eq!(infer_schema(file).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_infer_schema_tail() {
+        let file = "tests/stale_head.sor";
+        assert_eq!(
+            infer_schema(file).unwrap(),
+            vec![DataType::String, DataType::String]
+        );
+        assert_eq!(
+            infer_schema_tail(file, 3).unwrap(),
+            vec![DataType::Int, DataType::Float]
+        );
+        assert_eq!(
+            infer_schema_tail(file, 4).unwrap(),
+            vec![DataType::String, DataType::String]
+        );
+        assert_eq!(infer_schema_tail(file, 0).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_infer_schema_tail_with_config() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // an overlong last line is skipped
+        let file = dir.path().join("long_tail.sor");
+        let input = "<12> <2.5>\n".repeat(3) + &"<x> ".repeat(50);
+        fs::write(&file, input).unwrap();
+        let file = file.to_str().unwrap();
+        assert_eq!(
+            infer_schema_tail(file, 2).unwrap(),
+            vec![DataType::String; 50]
+        );
+        let config = ParserConfig {
+            max_line_bytes: Some(64),
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            infer_schema_tail_with_config(file, 2, &config).unwrap(),
+            vec![DataType::Int, DataType::Float]
+        );
+
+        // a last line that is not utf-8 is skipped instead of failing
+        let file = dir.path().join("latin1_tail.sor");
+        fs::write(&file, b"<1> <plain>\n<2> <caf\xe9>\n").unwrap();
+        let file = file.to_str().unwrap();
+        assert_eq!(infer_schema_tail(file, 1).unwrap(), vec![]);
+        assert_eq!(
+            infer_schema_tail(file, 2).unwrap(),
+            vec![DataType::Bool, DataType::String]
+        );
+    }
+
+    #[test]
+    fn test_infer_schema_byte_budget() {
+        let file = "tests/long_rows.sor";
+        let config = ParserConfig::default();
+        let expected = infer_schema(file).unwrap();
+        assert_eq!(expected.len(), 4);
+        for &max_bytes in &[4096, 10_000, usize::MAX] {
+            let (_, bytes_read) =
+                sample_byte_budget(file, max_bytes, &config).unwrap();
+            assert!(bytes_read <= max_bytes);
+            assert_eq!(
+                infer_schema_byte_budget(file, max_bytes).unwrap(),
+                expected
+            );
+        }
+        // too small a budget to fit a single complete line
+        assert_eq!(infer_schema_byte_budget(file, 100).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_infer_schema_cached() {
+        use std::cell::Cell;
+        use std::time::{Duration, SystemTime};
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("2.sor");
+        fs::copy("tests/2.sor", &file).unwrap();
+        let file = file.to_str().unwrap();
+        let cache = dir.path().join("2.schema");
+        let cache = cache.to_str().unwrap();
+
+        let inferred = Cell::new(0);
+        let cached = || {
+            cached_schema_with(file, cache, || {
+                inferred.set(inferred.get() + 1);
+                infer_schema(file)
+            })
+            .unwrap()
+        };
+        let expected = infer_schema("tests/2.sor").unwrap();
+        assert_eq!(cached(), expected);
+        assert_eq!(inferred.get(), 1);
+        assert_eq!(cached(), expected);
+        assert_eq!(inferred.get(), 1);
+
+        // touching the file invalidates the cache
+        let later = SystemTime::now() + Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(file)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(cached(), expected);
+        assert_eq!(inferred.get(), 2);
+        assert_eq!(cached(), expected);
+        assert_eq!(inferred.get(), 2);
+
+        // and so does a corrupt cache
+        fs::write(cache, "garbage").unwrap();
+        assert_eq!(cached(), expected);
+        assert_eq!(inferred.get(), 3);
+
+        // the public function writes the same cache
+        fs::remove_file(cache).unwrap();
+        assert_eq!(infer_schema_cached_at(file, cache).unwrap(), expected);
+        assert_eq!(cached(), expected);
+        assert_eq!(inferred.get(), 3);
+    }
+
+    #[test]
+    fn test_drift_detector() {
+        let schema = vec![DataType::Int, DataType::Float];
+        let mut detector = DriftDetector::new(schema);
+        for line in &[&b"<1> <2.5>"[..], b"<12> <>", b"<-3> <4>"] {
+            detector.observe_line(line);
+        }
+        assert!(!detector.has_drifted());
+        assert_eq!(detector.observed(), &[DataType::Int, DataType::Float][..]);
+
+        detector.observe_line(b"<not an int> <1.5>");
+        assert!(!detector.has_drifted(), "invalid lines are ignored");
+        detector.observe_line(b"<twelve> <1.5> <extra>");
+        assert!(detector.has_drifted());
+        assert_eq!(detector.drifted_columns(), vec![0]);
+        assert_eq!(
+            detector.observed(),
+            &[DataType::String, DataType::Float][..]
+        );
+    }
+
+    #[test]
+    fn test_merge_schemas() {
+        let merged = merge_schemas(&[
+            vec![DataType::Int, DataType::Bool],
+            vec![DataType::Float, DataType::Int, DataType::String],
+        ]);
+        assert_eq!(
+            merged,
+            vec![DataType::Float, DataType::Int, DataType::String]
+        );
+
+        let merged = merge_schemas(&[
+            vec![DataType::Bool, DataType::String, DataType::Bool],
+            vec![DataType::Int],
+        ]);
+        assert_eq!(
+            merged,
+            vec![DataType::Int, DataType::String, DataType::Bool]
+        );
+        assert!(merge_schemas(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_column_count() {
+        for file in &["tests/0.sor", "tests/2.sor", "tests/sor_terator.sor"] {
+            assert_eq!(
+                column_count(file).unwrap(),
+                infer_schema(file).unwrap().len()
+            );
+        }
+        assert_eq!(column_count("tests/blank_preamble.sor").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_head_past_blank_preamble() {
+        // 200 blank lines, then 5 rows of strings and 1000 rows of bools
+        let file = "tests/blank_preamble.sor";
+        let (schema, lines_read) =
+            sample_n_lines(file, 300, &ParserConfig::default()).unwrap();
+        assert_eq!(schema, vec![DataType::String, DataType::Bool]);
+        // the head reads past the preamble until it has 10 rows, then the
+        // middle and the tail read 100 lines each
+        assert_eq!(lines_read, 200 + HEAD_MIN_ROWS + 100 + 100);
+
+        // with fewer lines to sample the head gives up inside the preamble
+        let (_, lines_read) =
+            sample_n_lines(file, 15, &ParserConfig::default()).unwrap();
+        assert_eq!(lines_read, 5 * HEAD_CAP_MULTIPLIER + 5 + 5);
+    }
+
+    #[test]
+    fn test_field_count_histogram() {
+        let histogram = field_count_histogram("tests/ragged.sor").unwrap();
+        let expected: BTreeMap<usize, usize> =
+            vec![(2, 1), (4, 5), (6, 2)].into_iter().collect();
+        assert_eq!(histogram, expected);
+        assert_eq!(column_count("tests/ragged.sor").unwrap(), 6);
+    }
+
+    #[test]
+    fn test_inference_stable_rows() {
+        let file = "tests/all_strings.sor";
+        let expected = vec![DataType::String; 3];
+        let (schema, lines_read) =
+            sample_n_lines(file, 300, &ParserConfig::default()).unwrap();
+        assert_eq!(schema, expected);
+        assert_eq!(lines_read, 300);
+
+        // every column is a `String` after the first line, but the schema
+        // still has to stay the same for the next 50 lines
+        let config = ParserConfig {
+            inference_stable_rows: Some(50),
+            ..ParserConfig::default()
+        };
+        assert_eq!(sample_n_lines(file, 300, &config).unwrap(), (expected, 51));
+
+        // the first line is a single `String`, which the wider second line
+        // replaces. The first column is a `Bool` until its `<2>` on the
+        // fourth line, which is stable for the five lines after it
+        let file = "tests/narrow_first.sor";
+        let config = ParserConfig {
+            inference_stable_rows: Some(5),
+            ..ParserConfig::default()
+        };
+        let (schema, lines_read) = sample_n_lines(file, 300, &config).unwrap();
+        assert_eq!(
+            schema,
+            vec![
+                DataType::Int,
+                DataType::Float,
+                DataType::String,
+                DataType::Bool
+            ]
+        );
+        assert_eq!(schema, infer_schema(file).unwrap());
+        assert_eq!(lines_read, 9);
+
+        // the schema of the first line doesn't change for the next 10 lines
+        let file = "tests/long_rows.sor";
+        let config = ParserConfig {
+            inference_stable_rows: Some(10),
+            ..ParserConfig::default()
+        };
+        let (schema, lines_read) = sample_n_lines(file, 300, &config).unwrap();
+        assert_eq!(schema, infer_schema(file).unwrap());
+        assert_eq!(lines_read, 11);
+    }
+
+    #[test]
+    fn test_max_columns() {
+        // a single line of 50 fields among rows of 4
+        let file = "tests/garbled_width.sor";
+        assert_eq!(infer_schema(file).unwrap(), vec![DataType::String; 50]);
+        let config = ParserConfig {
+            max_columns: Some(4),
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            infer_schema_with_config(file, &config).unwrap(),
+            vec![
+                DataType::Int,
+                DataType::Float,
+                DataType::String,
+                DataType::Bool
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inference_max_line_bytes() {
+        // a line of 50 fields among 2000 rows of 2, in each of the regions
+        // that are sampled
+        let wide = "<x> ".repeat(50) + "\n";
+        let short = "<1> <2>\n";
+        let dir = tempfile::tempdir().unwrap();
+        for &at in &[0, 1050, 2000] {
+            let input = short.repeat(at) + &wide + &short.repeat(2000 - at);
+            let file = dir.path().join(format!("wide_{}.sor", at));
+            fs::write(&file, input).unwrap();
+            let file = file.to_str().unwrap();
+
+            assert_eq!(infer_schema(file).unwrap(), vec![DataType::String; 50]);
+            let config = ParserConfig {
+                max_line_bytes: Some(64),
+                ..ParserConfig::default()
+            };
+            assert_eq!(
+                infer_schema_with_config(file, &config).unwrap(),
+                vec![DataType::Bool, DataType::Int]
+            );
+        }
+    }
+
+    #[test]
+    fn test_header_names() {
+        assert_eq!(
+            header_names("tests/names.sor", b'#').unwrap(),
+            Some(vec!["id".to_string(), "name".to_string()])
+        );
+        assert_eq!(header_names("tests/2.sor", b'#').unwrap(), None);
+        assert_eq!(default_column_names(2), vec!["col0", "col1"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("indented.sor");
+        fs::write(&file, "  # a b\n<1> <2>\n").unwrap();
+        assert_eq!(
+            header_names(file.to_str().unwrap(), b'#').unwrap(),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
     }
 }
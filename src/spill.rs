@@ -0,0 +1,281 @@
+//! A module for parsing `SoR` files into a columnar representation that is
+//! stored on disk instead of in memory, for files whose parsed data does not
+//! fit in memory but still needs random access.
+//!
+//! Each column is written to its own temporary file as the chunks of a
+//! [`SorTerator`](crate::dataframe::SorTerator) are parsed, so at most one
+//! chunk is held in memory at a time. Cells are read back lazily, one at a
+//! time, with [`SpilledDataFrame::get`]. The temporary files are deleted when
+//! the `SpilledDataFrame` is dropped.
+//!
+//! `Bool`, `Int` and `Float` cells are stored with a fixed width so that a
+//! cell can be found by seeking directly to it. Since `String` cells have a
+//! variable width, `String` columns use a second temporary file holding the
+//! offset and length of every cell.
+
+use crate::dataframe::{Column, Data, SorTerator};
+use crate::schema::DataType;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+
+// The width of an `Int` or `Float` cell: a byte marking whether the cell is
+// missing followed by the 8 byte little endian value
+const NUMBER_WIDTH: u64 = 9;
+
+// The width of an entry in the offsets file of a `String` column: the 8 byte
+// offset of the cell followed by its 8 byte length
+const OFFSET_WIDTH: u64 = 16;
+
+// The value stored as the length of a missing `String` cell
+const MISSING_STRING: u64 = u64::MAX;
+
+// The value stored for a missing `Bool` cell
+const MISSING_BOOL: u8 = 2;
+
+/// Parses the file at `file_path` according to `schema`, `chunk_size` rows at
+/// a time, spilling every column to a temporary file. Returns an error if the
+/// temporary files could not be created or written to.
+///
+/// # Panics
+/// If the file at `file_path` can not be opened.
+pub fn from_file_spilled(
+    file_path: &str,
+    schema: Vec<DataType>,
+    chunk_size: usize,
+) -> io::Result<SpilledDataFrame> {
+    let mut writers = Vec::with_capacity(schema.len());
+    for data_type in &schema {
+        writers.push(ColumnWriter::new(data_type)?);
+    }
+
+    let mut len = 0;
+    for chunk in SorTerator::new(file_path, schema, chunk_size) {
+        len += chunk.first().map_or(0, Column::len);
+        for (writer, column) in writers.iter_mut().zip(&chunk) {
+            writer.write(column)?;
+        }
+    }
+
+    let mut columns = Vec::with_capacity(writers.len());
+    for writer in writers {
+        columns.push(writer.finish()?);
+    }
+    Ok(SpilledDataFrame { columns, len })
+}
+
+/// A parsed `SoR` file whose columns are stored in temporary files, created
+/// by [`from_file_spilled`].
+#[derive(Debug)]
+pub struct SpilledDataFrame {
+    columns: Vec<SpilledColumn>,
+    len: usize,
+}
+
+impl SpilledDataFrame {
+    /// Returns the number of rows.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of columns.
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Reads the `Data` at the given `col_idx`, `row_idx` offsets back from
+    /// disk.
+    ///
+    /// # Panics
+    /// If `col_idx` or `row_idx` is out of bounds.
+    pub fn get(&mut self, col_idx: usize, row_idx: usize) -> io::Result<Data> {
+        assert!(
+            row_idx < self.len,
+            "Row index {} is out of bounds for {} rows",
+            row_idx,
+            self.len
+        );
+        let column = &mut self.columns[col_idx];
+        let row_idx = row_idx as u64;
+        match column.data_type {
+            DataType::Bool => {
+                let mut buf = [0; 1];
+                column.data.seek(SeekFrom::Start(row_idx))?;
+                column.data.read_exact(&mut buf)?;
+                Ok(match buf[0] {
+                    MISSING_BOOL => Data::Null,
+                    b => Data::Bool(b == 1),
+                })
+            }
+            DataType::Int | DataType::Float => {
+                let mut buf = [0; NUMBER_WIDTH as usize];
+                column.data.seek(SeekFrom::Start(row_idx * NUMBER_WIDTH))?;
+                column.data.read_exact(&mut buf)?;
+                if buf[0] == 0 {
+                    return Ok(Data::Null);
+                }
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&buf[1..]);
+                Ok(if column.data_type == DataType::Int {
+                    Data::Int(i64::from_le_bytes(bytes))
+                } else {
+                    Data::Float(f64::from_le_bytes(bytes))
+                })
+            }
+            DataType::String => {
+                let offsets = column.offsets.as_mut().unwrap();
+                let mut buf = [0; OFFSET_WIDTH as usize];
+                offsets.seek(SeekFrom::Start(row_idx * OFFSET_WIDTH))?;
+                offsets.read_exact(&mut buf)?;
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&buf[..8]);
+                let offset = u64::from_le_bytes(bytes);
+                bytes.copy_from_slice(&buf[8..]);
+                let len = u64::from_le_bytes(bytes);
+                if len == MISSING_STRING {
+                    return Ok(Data::Null);
+                }
+                let mut s = vec![0; len as usize];
+                column.data.seek(SeekFrom::Start(offset))?;
+                column.data.read_exact(&mut s)?;
+                // only valid utf-8 was ever written
+                Ok(Data::String(String::from_utf8(s).unwrap()))
+            }
+        }
+    }
+}
+
+// A single column that has been completely written to disk
+#[derive(Debug)]
+struct SpilledColumn {
+    data_type: DataType,
+    data: File,
+    // only used by `String` columns
+    offsets: Option<File>,
+}
+
+// Appends the chunks of a single column to its temporary file(s)
+struct ColumnWriter {
+    data_type: DataType,
+    data: BufWriter<File>,
+    offsets: Option<BufWriter<File>>,
+    // the number of bytes written to `data` so far
+    written: u64,
+}
+
+impl ColumnWriter {
+    fn new(data_type: &DataType) -> io::Result<Self> {
+        let offsets = match data_type {
+            DataType::String => Some(BufWriter::new(tempfile::tempfile()?)),
+            _ => None,
+        };
+        Ok(ColumnWriter {
+            data_type: data_type.clone(),
+            data: BufWriter::new(tempfile::tempfile()?),
+            offsets,
+            written: 0,
+        })
+    }
+
+    fn write(&mut self, column: &Column) -> io::Result<()> {
+        match column {
+            Column::Bool(c) => {
+                for b in c {
+                    self.data.write_all(&[b.map_or(MISSING_BOOL, u8::from)])?;
+                }
+            }
+            Column::PackedBool(c) => {
+                for b in c.iter() {
+                    self.data.write_all(&[b.map_or(MISSING_BOOL, u8::from)])?;
+                }
+            }
+            Column::Int(c) => {
+                for n in c {
+                    self.write_number(n.map(i64::to_le_bytes))?;
+                }
+            }
+            Column::Int32(c) => {
+                for n in c {
+                    self.write_number(n.map(|n| i64::from(n).to_le_bytes()))?;
+                }
+            }
+            Column::Float(c) => {
+                for f in c {
+                    self.write_number(f.map(f64::to_le_bytes))?;
+                }
+            }
+            Column::String(c) => {
+                let offsets = self.offsets.as_mut().unwrap();
+                for s in c {
+                    offsets.write_all(&self.written.to_le_bytes())?;
+                    match s {
+                        Some(s) => {
+                            let len = s.len() as u64;
+                            offsets.write_all(&len.to_le_bytes())?;
+                            self.data.write_all(s.as_bytes())?;
+                            self.written += len;
+                        }
+                        None => {
+                            offsets.write_all(&MISSING_STRING.to_le_bytes())?
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_number(&mut self, bytes: Option<[u8; 8]>) -> io::Result<()> {
+        match bytes {
+            Some(bytes) => {
+                self.data.write_all(&[1])?;
+                self.data.write_all(&bytes)
+            }
+            None => self.data.write_all(&[0; NUMBER_WIDTH as usize]),
+        }
+    }
+
+    fn finish(self) -> io::Result<SpilledColumn> {
+        let offsets = match self.offsets {
+            Some(offsets) => Some(offsets.into_inner()?),
+            None => None,
+        };
+        Ok(SpilledColumn {
+            data_type: self.data_type,
+            data: self.data.into_inner()?,
+            offsets,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::{from_file, get};
+    use crate::schema::infer_schema;
+
+    #[test]
+    fn test_from_file_spilled() {
+        for file in &["tests/2.sor", "tests/sor_terator.sor"] {
+            let schema = infer_schema(file).unwrap();
+            let in_memory = from_file(file, schema.clone(), 0, usize::MAX, 8);
+            let mut spilled = from_file_spilled(file, schema, 3).unwrap();
+
+            assert_eq!(spilled.width(), in_memory.len());
+            assert_eq!(spilled.len(), in_memory[0].len());
+            // read the cells back in reverse to exercise seeking backwards
+            for col_idx in (0..spilled.width()).rev() {
+                for row_idx in (0..spilled.len()).rev() {
+                    assert_eq!(
+                        spilled.get(col_idx, row_idx).unwrap(),
+                        get(&in_memory, col_idx, row_idx)
+                    );
+                }
+            }
+        }
+    }
+}
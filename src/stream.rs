@@ -0,0 +1,323 @@
+//! A module for chunking `SoR` files without blocking an async runtime,
+//! enabled with the `tokio` feature.
+//!
+//! [`AsyncSorTerator`] yields the same chunks as a
+//! [`SorTerator`](crate::dataframe::SorTerator), but reads the file with
+//! `tokio`'s non-blocking file i/o. Parsing a chunk is still done on the
+//! task polling the stream.
+
+use crate::dataframe::{
+    check_projection, init_columnar, push_line, Column, JoinedRow,
+};
+use crate::parsers::{
+    has_unclosed_quote, is_comment, ParseStats, ParserConfig,
+};
+use crate::schema::DataType;
+use futures_core::Stream;
+use memchr::memchr;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::fs::File;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+type ChunkFuture =
+    Pin<Box<dyn Future<Output = (Box<Reader>, Option<Vec<Column>>)> + Send>>;
+
+/// A `Stream` that chunks a `SoR` file into `Vec<Column>`s where all columns
+/// have the same length (number of rows), like a
+/// [`SorTerator`](crate::dataframe::SorTerator). The last chunk may have less
+/// than `chunk_size` rows.
+pub struct AsyncSorTerator {
+    state: State,
+}
+
+enum State {
+    // waiting for the next call to `poll_next`
+    Idle(Box<Reader>),
+    // reading the next chunk
+    Reading(ChunkFuture),
+    // the whole file has been read
+    Done,
+}
+
+// Everything needed to read the next chunk, which is moved into the future
+// reading it and handed back once the chunk is read
+struct Reader {
+    reader: BufReader<File>,
+    line: Vec<u8>,
+    joined: JoinedRow,
+    chunk_size: usize,
+    schema: Vec<DataType>,
+    config: ParserConfig,
+}
+
+impl AsyncSorTerator {
+    /// Opens the file at `file_name` to be chunked according to `schema`,
+    /// `chunk_size` rows at a time.
+    pub async fn new(
+        file_name: &str,
+        schema: Vec<DataType>,
+        chunk_size: usize,
+    ) -> io::Result<Self> {
+        AsyncSorTerator::with_config(
+            file_name,
+            schema,
+            chunk_size,
+            ParserConfig::default(),
+        )
+        .await
+    }
+
+    /// Opens the file at `file_name` to be chunked according to `schema` and
+    /// the given [`ParserConfig`], `chunk_size` rows at a time.
+    ///
+    /// # Panics
+    /// Panics if `config.projection` has an index that is out of bounds for
+    /// `schema`.
+    pub async fn with_config(
+        file_name: &str,
+        schema: Vec<DataType>,
+        chunk_size: usize,
+        config: ParserConfig,
+    ) -> io::Result<Self> {
+        check_projection(&schema, &config);
+        let file = File::open(file_name).await?;
+        Ok(AsyncSorTerator {
+            state: State::Idle(Box::new(Reader {
+                reader: BufReader::new(file),
+                line: Vec::new(),
+                joined: JoinedRow::new(config.max_line_bytes),
+                chunk_size,
+                schema,
+                config,
+            })),
+        })
+    }
+}
+
+impl Stream for AsyncSorTerator {
+    type Item = Vec<Column>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            match std::mem::replace(&mut self.state, State::Done) {
+                State::Idle(reader) => {
+                    self.state = State::Reading(Box::pin(next_chunk(reader)))
+                }
+                State::Reading(mut chunk) => {
+                    return match chunk.as_mut().poll(cx) {
+                        Poll::Pending => {
+                            self.state = State::Reading(chunk);
+                            Poll::Pending
+                        }
+                        Poll::Ready((reader, Some(chunk))) => {
+                            self.state = State::Idle(reader);
+                            Poll::Ready(Some(chunk))
+                        }
+                        Poll::Ready((_, None)) => Poll::Ready(None),
+                    };
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+// Reads and parses lines until `chunk_size` rows have been parsed, the same
+// way as `SorTerator::next`
+async fn next_chunk(mut r: Box<Reader>) -> (Box<Reader>, Option<Vec<Column>>) {
+    let mut parsed_data = init_columnar(&r.schema, &r.config);
+    let Reader {
+        reader,
+        line,
+        joined,
+        chunk_size,
+        schema,
+        config,
+    } = &mut *r;
+    loop {
+        match read_line_capped(reader, line, config.max_line_bytes).await {
+            Ok(Some(_)) => (),
+            Ok(None) | Err(_) => break,
+        }
+        if is_comment(line, config) {
+            continue;
+        }
+        let row = if config.multi_line_strings && has_unclosed_quote(line) {
+            // keep reading lines until the quoted string is closed
+            joined.start(line);
+            while joined.is_open() {
+                match read_line_capped(reader, line, config.max_line_bytes)
+                    .await
+                {
+                    Ok(Some(too_long)) => joined.push(line, too_long),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+            joined.row()
+        } else {
+            &line[..]
+        };
+        push_line(
+            row,
+            schema,
+            config,
+            &mut parsed_data,
+            &mut ParseStats::default(),
+        );
+        if let Some(column) = parsed_data.first() {
+            if column.len() == *chunk_size {
+                return (r, Some(parsed_data));
+            }
+        }
+    }
+    match parsed_data.first() {
+        Some(column) if !column.is_empty() => (r, Some(parsed_data)),
+        _ => (r, None),
+    }
+}
+
+// Reads the next line into `line` without its trailing newline, returning
+// whether it was longer than `max_len` bytes, or `None` at the end of the
+// file. A line that is too long is left empty, and the rest of it is read
+// without being buffered, like `LineReader::with_max_line`.
+async fn read_line_capped<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    line: &mut Vec<u8>,
+    max_len: Option<usize>,
+) -> io::Result<Option<bool>> {
+    line.clear();
+    let mut read_any = false;
+    let mut too_long = false;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(if read_any { Some(too_long) } else { None });
+        }
+        read_any = true;
+        let (used, found_newline) = match memchr(b'\n', available) {
+            Some(idx) => (idx + 1, true),
+            None => (available.len(), false),
+        };
+        if !too_long {
+            line.extend_from_slice(&available[..used]);
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            if matches!(max_len, Some(max) if line.len() > max) {
+                too_long = true;
+                line.clear();
+            }
+        }
+        reader.consume(used);
+        if found_newline {
+            return Ok(Some(too_long));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::SorTerator;
+    use crate::schema::infer_schema_with_config;
+
+    // Collects every chunk of `stream` by polling it on the current runtime
+    async fn collect(mut stream: AsyncSorTerator) -> Vec<Vec<Column>> {
+        let mut chunks = Vec::new();
+        while let Some(chunk) =
+            std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    #[tokio::test]
+    async fn test_same_chunks_as_sor_terator() {
+        let files = vec![
+            ("tests/sor_terator.sor", ParserConfig::default()),
+            ("tests/no_trailing_newline.sor", ParserConfig::default()),
+            (
+                "tests/multi_line.sor",
+                ParserConfig {
+                    multi_line_strings: true,
+                    ..ParserConfig::default()
+                },
+            ),
+            (
+                "tests/comments.sor",
+                ParserConfig {
+                    comment_prefix: Some(b'#'),
+                    ..ParserConfig::default()
+                },
+            ),
+        ];
+        for (file, config) in files {
+            let schema = infer_schema_with_config(file, &config).unwrap();
+            for chunk_size in &[1, 4, 100] {
+                let sync: Vec<Vec<Column>> = SorTerator::with_config(
+                    file,
+                    schema.clone(),
+                    *chunk_size,
+                    config.clone(),
+                )
+                .collect();
+                let stream = AsyncSorTerator::with_config(
+                    file,
+                    schema.clone(),
+                    *chunk_size,
+                    config.clone(),
+                )
+                .await
+                .unwrap();
+                assert!(!sync.is_empty());
+                assert_eq!(collect(stream).await, sync, "{}", file);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_line_bytes() {
+        // a long line, and a multi-line string whose joined lines are long
+        let mut input = b"<1>\n<".to_vec();
+        input.extend_from_slice(&[b'a'; 4096]);
+        input.extend_from_slice(b">\n<\"x");
+        for _ in 0..100 {
+            input.extend_from_slice(b"\nyyyyyyyyyyyyyyyyyyyy");
+        }
+        input.extend_from_slice(b"\">\n<2>\n");
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("long_lines.sor");
+        std::fs::write(&file, input).unwrap();
+        let file = file.to_str().unwrap();
+
+        let schema = vec![DataType::String];
+        let config = ParserConfig {
+            max_line_bytes: Some(1024),
+            multi_line_strings: true,
+            ..ParserConfig::default()
+        };
+        let stream = AsyncSorTerator::with_config(
+            file,
+            schema.clone(),
+            100,
+            config.clone(),
+        )
+        .await
+        .unwrap();
+        let expected = vec![vec![Column::String(vec![
+            Some(String::from("1")),
+            Some(String::from("2")),
+        ])]];
+        assert_eq!(collect(stream).await, expected);
+        let sync: Vec<Vec<Column>> =
+            SorTerator::with_config(file, schema, 100, config).collect();
+        assert_eq!(sync, expected);
+    }
+}
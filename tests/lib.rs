@@ -30,8 +30,8 @@ fn is_missing_idx() {
     ];
 
     for t in is_missing_tests {
-        let schema = infer_schema(t.0.clone()).unwrap();
-        let data_frame = from_file(t.0, schema, 0, std::usize::MAX, 8);
+        let schema = infer_schema(t.0).unwrap();
+        let data_frame = from_file(t.0, schema, 0, usize::MAX, 8);
 
         assert_eq!(get(&data_frame, t.1, t.2) == Data::Null, t.3);
     }
@@ -41,7 +41,7 @@ fn is_missing_idx() {
     let schema = infer_schema("tests/1.sor").unwrap();
     let data_frame = from_file("tests/1.sor", schema, 1, 74, 8);
 
-    assert_eq!(get(&data_frame, 0, 0) == Data::Null, false);
+    assert!(get(&data_frame, 0, 0) != Data::Null);
 }
 
 #[test]
@@ -72,6 +72,25 @@ fn schema_inference() {
     );
 }
 
+#[test]
+fn schema_inference_int_float_precedence() {
+    // a column of bare integers never infers as a Float
+    let ints = infer_schema("tests/schema4.sor").unwrap();
+    assert_eq!(ints, vec![DataType::Int, DataType::Int]);
+
+    // mixing `1` and `1.0` infers as a Float
+    let mixed = infer_schema("tests/schema5.sor").unwrap();
+    assert_eq!(mixed, vec![DataType::Float, DataType::Float]);
+}
+
+#[test]
+fn schema_inference_blank_preamble() {
+    // 200 blank lines, then 5 rows with a string in the first column, then
+    // 1000 rows of bools. Only the head pass sees the strings.
+    let schema = infer_schema("tests/blank_preamble.sor").unwrap();
+    assert_eq!(schema, vec![DataType::String, DataType::Bool]);
+}
+
 // NOTE: This test is ignored by default since running `cargo test` uses the debug build, which is
 // much much slower than the release version (release is roughly 60x faster).
 // If you want to run this test, run `cargo test --release -- --ignored`
@@ -94,8 +113,8 @@ fn print_col_idx() {
     ];
 
     for t in print_col_idx_tests {
-        let schema = infer_schema(t.0.clone()).unwrap();
-        let data_frame = from_file(t.0, schema, 0, std::usize::MAX, 8);
+        let schema = infer_schema(t.0).unwrap();
+        let data_frame = from_file(t.0, schema, 0, usize::MAX, 8);
 
         assert_eq!(get(&data_frame, t.1, t.2), t.3);
     }
@@ -103,3 +122,117 @@ fn print_col_idx() {
     // ./sorer./sorer -f 1.sor -from 1 -len 74 -print_col_idx 0 6
     // "+2.2"
 }
+
+#[test]
+fn schema_diff_between_files() {
+    let a = infer_schema("tests/diff_a.sor").unwrap();
+    let b = infer_schema("tests/diff_b.sor").unwrap();
+    assert_eq!(
+        schema_diff(&a, &b),
+        vec![
+            SchemaDiffEntry::TypeChanged {
+                index: 1,
+                a: DataType::String,
+                b: DataType::Int,
+            },
+            SchemaDiffEntry::OnlyInB {
+                index: 3,
+                data_type: DataType::Bool,
+            },
+        ]
+    );
+}
+
+#[test]
+fn schema_inference_bool_tokens() {
+    let file = "tests/bool_tokens.sor";
+    let config = sorer::parsers::ParserConfig {
+        true_tokens: vec!["T".to_string()],
+        false_tokens: vec!["F".to_string()],
+        ..Default::default()
+    };
+    let schema = infer_schema_with_config(file, &config).unwrap();
+    assert_eq!(schema, vec![DataType::Bool, DataType::String]);
+
+    let schema = infer_schema(file).unwrap();
+    assert_eq!(schema, vec![DataType::String, DataType::String]);
+}
+
+#[test]
+fn schema_inference_bool_null_sentinels() {
+    let file = "tests/bool_sentinels.sor";
+    let config = sorer::parsers::ParserConfig {
+        bool_null_sentinels: vec![9],
+        ..Default::default()
+    };
+    // the `<2>` is not a sentinel, so it still makes its column an `Int`
+    let schema = infer_schema_with_config(file, &config).unwrap();
+    assert_eq!(schema, vec![DataType::Bool, DataType::Int]);
+
+    let schema = infer_schema(file).unwrap();
+    assert_eq!(schema, vec![DataType::Int, DataType::Int]);
+
+    let parsed = from_file_with_config(
+        file,
+        vec![DataType::Bool, DataType::Int],
+        0,
+        usize::MAX,
+        8,
+        &config,
+    );
+    assert_eq!(
+        parsed,
+        vec![
+            Column::Bool(vec![Some(true), None, Some(false), Some(true)]),
+            Column::Int(vec![Some(9), Some(0), Some(2), Some(1)]),
+        ]
+    );
+}
+
+#[test]
+fn schema_inference_bool_null_sentinel_columns() {
+    let config = sorer::parsers::ParserConfig {
+        bool_null_sentinels: vec![9],
+        ..Default::default()
+    };
+    // only `<0>`, `<1>` and the sentinel `<9>`
+    let file = "tests/bool_sentinel_column.sor";
+    let schema = infer_schema_with_config(file, &config).unwrap();
+    assert_eq!(schema, vec![DataType::Bool]);
+    assert_eq!(infer_schema(file).unwrap(), vec![DataType::Int]);
+
+    // the same with a `<2>` row, which is not a sentinel
+    let file = "tests/bool_sentinel_int_column.sor";
+    let schema = infer_schema_with_config(file, &config).unwrap();
+    assert_eq!(schema, vec![DataType::Int]);
+}
+
+#[test]
+fn comment_lines_are_skipped() {
+    let config = sorer::parsers::ParserConfig {
+        comment_prefix: Some(b'#'),
+        keep_invalid_rows: true,
+        multi_line_strings: true,
+        ..Default::default()
+    };
+    let schema =
+        infer_schema_with_config("tests/comments.sor", &config).unwrap();
+    assert_eq!(schema, infer_schema("tests/schema1.sor").unwrap());
+
+    let expected =
+        from_file("tests/schema1.sor", schema.clone(), 0, usize::MAX, 8);
+    let parsed = from_file_with_config(
+        "tests/comments.sor",
+        schema.clone(),
+        0,
+        usize::MAX,
+        8,
+        &config,
+    );
+    assert_eq!(parsed, expected);
+
+    let chunks: Vec<Vec<Column>> =
+        SorTerator::with_config("tests/comments.sor", schema, 10, config)
+            .collect();
+    assert_eq!(chunks, vec![expected]);
+}